@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 use std::error::Error;
-use std::f64::consts::PI;
 use std::fmt::Debug;
 
+use dfdl::schema::CompiledSchema;
 use dfdl::serde::{from_infoset, to_infoset};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -108,9 +108,13 @@ fn main() -> Result<(), Box<dyn Error>> {
     let val = ();
     roundtrip(&val)?;
     println!("finished");
-    /*let mut xml_src = String::new();
-    let mut file = BufReader::new(File::open("test.xsd")?);
-    file.read_to_string(&mut xml_src)?;
-    let schema = Schema::new(&xml_src)?;*/
+    let schema = CompiledSchema::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/test.xsd"))?;
+    println!("schema: {schema:#?}");
+    let data = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test.bin"))?;
+    let infoset = dfdl::parse(&schema, "test", &data)?;
+    println!("parsed: {infoset:#?}");
+    let mut unparsed = Vec::new();
+    dfdl::unparse(&schema, "test", &infoset, &mut unparsed)?;
+    assert_eq!(data, unparsed);
     Ok(())
 }
@@ -0,0 +1,292 @@
+//! Arbitrary precision decimal numbers, the values of `xs:decimal` elements
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+
+/// Name of the newtype struct a [`Decimal`] is serialized as, which lets the infoset serializer
+/// tell decimals apart from strings
+#[cfg(feature = "serde")]
+pub(crate) const DECIMAL_TOKEN: &str = "$dfdl::private::Decimal";
+
+/// An arbitrary precision decimal number, which keeps the number of its fraction digits
+///
+/// Decimals compare by their value, so `1.5` equals `1.50`, while both keep their own scale
+/// when they are written as text.
+#[derive(Clone, Debug, Default)]
+pub struct Decimal {
+    negative: bool,
+    /// the digits of the value multiplied by 10^`scale`, without leading zeros
+    digits: Vec<u8>,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Creates the decimal `unscaled` × 10^-`scale`
+    #[must_use]
+    pub fn new(unscaled: i128, scale: u32) -> Self {
+        let digits = unscaled.unsigned_abs().to_string().bytes().map(|digit| digit - b'0').collect();
+        Self::from_digits(unscaled < 0, digits, scale)
+    }
+
+    /// the decimal of the decimal `digits` multiplied by 10^-`scale`
+    pub(crate) fn from_digits(negative: bool, mut digits: Vec<u8>, scale: u32) -> Self {
+        let leading = digits.iter().take_while(|&&digit| digit == 0).count();
+        digits.drain(..leading);
+        Self { negative: negative && !digits.is_empty(), digits, scale }
+    }
+
+    /// the decimal digits of the value multiplied by 10^`scale`, without leading zeros
+    pub(crate) fn digits(&self) -> &[u8] {
+        &self.digits
+    }
+
+    /// Returns the number of digits after the decimal point
+    #[must_use]
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Returns `true` if the value is less than zero
+    #[must_use]
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns `true` if the value is zero
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Returns the value as an integer, or `None` if it has a fraction or does not fit into an `i128`
+    #[must_use]
+    pub fn to_i128(&self) -> Option<i128> {
+        let scale = self.scale as usize;
+        let integer = self.digits.len().saturating_sub(scale);
+        if self.digits[integer..].iter().any(|&digit| digit != 0) {
+            return None;
+        }
+        let value = self.digits[..integer].iter().try_fold(0i128, |value, &digit| value.checked_mul(10)?.checked_add(digit.into()))?;
+        Some(if self.negative { -value } else { value })
+    }
+
+    /// the value multiplied by 10^`scale`, `None` if it does not fit into an `i128`
+    pub(crate) fn unscaled(&self) -> Option<i128> {
+        let value = self.digits.iter().try_fold(0i128, |value, &digit| value.checked_mul(10)?.checked_add(digit.into()))?;
+        Some(if self.negative { -value } else { value })
+    }
+
+    /// Returns the floating point number closest to the value
+    #[must_use]
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// the significant digits and the power of ten they are multiplied with
+    fn normalized(&self) -> (&[u8], i64) {
+        let trailing = self.digits.iter().rev().take_while(|&&digit| digit == 0).count();
+        (&self.digits[..self.digits.len() - trailing], trailing as i64 - i64::from(self.scale))
+    }
+
+    /// compares the absolute values
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        let ((digits, exponent), (other_digits, other_exponent)) = (self.normalized(), other.normalized());
+        match (digits.is_empty(), other_digits.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {},
+        }
+        // the position of the leading digit decides first
+        let magnitude = digits.len() as i64 + exponent;
+        let other_magnitude = other_digits.len() as i64 + other_exponent;
+        magnitude.cmp(&other_magnitude).then_with(|| digits.cmp(other_digits))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+        }
+    }
+}
+
+/// the text of the value in the lexical space of `xs:decimal`, with `scale` fraction digits
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scale = self.scale as usize;
+        let digits: String = self.digits.iter().map(|digit| char::from(b'0' + digit)).collect();
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        if self.negative {
+            f.write_str("-")?;
+        }
+        f.write_str(integer)?;
+        if !fraction.is_empty() {
+            write!(f, ".{fraction}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when a text is not in the lexical space of `xs:decimal`
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("'{0}' is not a decimal number")]
+pub struct ParseDecimalError(String);
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDecimalError(text.to_string());
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(error());
+        }
+        if !integer.bytes().chain(fraction.bytes()).all(|digit| digit.is_ascii_digit()) {
+            return Err(error());
+        }
+        let digits = integer.bytes().chain(fraction.bytes()).map(|digit| digit - b'0').collect();
+        let scale = u32::try_from(fraction.len()).map_err(|_| error())?;
+        Ok(Self::from_digits(negative, digits, scale))
+    }
+}
+
+macro_rules! from_integer {
+    ($($integer:ty),*) => {$(
+        impl From<$integer> for Decimal {
+            fn from(value: $integer) -> Self {
+                Self::new(value.into(), 0)
+            }
+        }
+    )*};
+}
+from_integer!(i8, i16, i32, i64, i128, u8, u16, u32, u64);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Decimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DECIMAL_TOKEN, &self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Decimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(DECIMAL_TOKEN, DecimalVisitor)
+    }
+}
+
+/// reads a [`Decimal`] from its text or from a number
+#[cfg(feature = "serde")]
+struct DecimalVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a decimal number or its text")
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Decimal, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i128<E: serde::de::Error>(self, value: i128) -> Result<Decimal, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Decimal, E> {
+        Ok(value.into())
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Decimal, E> {
+        if !value.is_finite() {
+            return Err(E::custom(format!("{value} is not a decimal number")));
+        }
+        // the shortest text converting back to the value, which never uses an exponent
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Decimal, E> {
+        value.trim().parse().map_err(E::custom)
+    }
+
+    fn visit_newtype_struct<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Decimal, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_scale() {
+        let decimal: Decimal = "-0012.340".parse().unwrap();
+        assert_eq!(decimal.to_string(), "-12.340");
+        assert_eq!(decimal.scale(), 3);
+        assert_eq!(decimal, Decimal::new(-1234, 2));
+        assert_eq!(".5".parse::<Decimal>().unwrap().to_string(), "0.5");
+        assert_eq!("-0.00".parse::<Decimal>().unwrap().to_string(), "0.00");
+        assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+        for invalid in ["", "-", ".", "1e3", "1.2.3", " 1", "0x1"] {
+            assert!(invalid.parse::<Decimal>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn compares_by_value() {
+        let decimals: Vec<Decimal> = ["-10", "-9.99", "0", "0.001", "0.01", "1", "1.5", "10"].iter().map(|text| text.parse().unwrap()).collect();
+        assert!(decimals.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(Decimal::from(150u8), "150.00".parse().unwrap());
+        assert_eq!(Decimal::new(12300, 2).to_i128(), Some(123));
+        assert_eq!(Decimal::new(12345, 2).to_i128(), None);
+        assert_eq!(Decimal::new(-125, 2).to_f64(), -1.25);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_into_the_infoset() {
+        use crate::{Data, Element, SimpleElement};
+
+        let decimal: Decimal = "-12.50".parse().unwrap();
+        let infoset = crate::serde::to_infoset(&decimal).unwrap();
+        let Some(Element::SimpleElement(SimpleElement { data: Some(Data::Decimal(value)), .. })) = &infoset.root_element
+            else { panic!("expected a decimal element") };
+        assert_eq!(value.to_string(), "-12.50");
+        assert_eq!(crate::serde::from_infoset::<Decimal>(&infoset).unwrap().to_string(), "-12.50");
+        assert_eq!(crate::serde::from_infoset::<String>(&infoset).unwrap(), "-12.50");
+        let infoset = crate::serde::to_infoset(&7u16).unwrap();
+        assert_eq!(crate::serde::from_infoset::<Decimal>(&infoset).unwrap(), Decimal::from(7u8));
+    }
+}
@@ -1,7 +1,7 @@
 #![warn(missing_docs)]
 //! A DFDL Implementation for Rust
 //! ==============================
-//! <div class="warning">Still In early development, no acutual functionality provided yet</div>
+//! <div class="warning">Still in early development, no actual functionality provided yet</div>
 //!
 //! The [Data Format Description Language (DFDL)](https://ogf.org/ogf/doku.php/standards/dfdl/dfdl) is a
 //! language developed by the Open Grid Forum (OGF), to describe binary and textual data formats
@@ -32,11 +32,21 @@ pub const XSD_NAMESPACE: &str = r"http://www.w3.org/2001/XMLSchema";
 /// utilities relating to serde (serializing, deserializing)
 pub mod serde;
 
+pub mod schema;
 
+mod decimal;
+pub use decimal::{Decimal, ParseDecimalError};
+
+mod runtime;
+pub use runtime::{parse, unparse, FormatError, ParseError, RecoverableError, UnparseError};
+
+
+/// A DFDL Infoset, the logical representation of parsed data
 #[derive(Clone, Debug)]
 pub struct Infoset<'i> {
     dfdl_version: String,
-    root_element: Option<Element<'i>>
+    root_element: Option<Element<'i>>,
+    diagnostics: Vec<RecoverableError>,
 }
 impl Infoset<'_> {
     /// Returns the DFDL version of this [`Infoset`].
@@ -44,11 +54,16 @@ impl Infoset<'_> {
     pub fn version(&self) -> &str {
         &self.dfdl_version
     }
+    /// Returns the recoverable errors found while parsing the data of this [`Infoset`]
+    #[must_use]
+    pub fn diagnostics(&self) -> &[RecoverableError] {
+        &self.diagnostics
+    }
     /// Turns this [`Infoset`] onto a version of itself that owns all its data
     /// specifically, any borrowed string and/or binary sequence is turned into
     /// an owned version of itself
     pub fn into_owned(self) -> Infoset<'static> {
-        Infoset { dfdl_version: self.dfdl_version, root_element: self.root_element.map(Element::into_owned) }
+        Infoset { dfdl_version: self.dfdl_version, root_element: self.root_element.map(Element::into_owned), diagnostics: self.diagnostics }
     }
 }
 
@@ -129,7 +144,7 @@ impl SimpleElement<'_> {
 enum Data<'d> {
     Double(f64),
     Float(f32),
-    Decimal(/*TODO*/),
+    Decimal(Decimal),
     Integer(i32), //TODO: Generic Int?
     NonNegativeInteger(u32), //TODO:: Generic Int?
     Long(i64),
@@ -142,9 +157,12 @@ enum Data<'d> {
     UnsignedByte(u8),
 
     String(Cow<'d, str>),
-    //TODO: Calendar Types
+    //TODO: Calendar Types, not produced until they are supported
+    #[allow(dead_code)]
     DateTime,
+    #[allow(dead_code)]
     Date,
+    #[allow(dead_code)]
     Time,
 
     HexBinary(Cow<'d, [u8]>),
@@ -158,7 +176,7 @@ impl Data<'_> {
         match self {
             Data::Double(_) => "f64",
             Data::Float(_) => "f32",
-            Data::Decimal() => "decimal",
+            Data::Decimal(_) => "decimal",
             Data::Integer(_) => "integer",
             Data::NonNegativeInteger(_) => "unsigned",
             Data::Long(_) => "i64",
@@ -181,7 +199,7 @@ impl Data<'_> {
         match self {
             Data::Double(data) => Data::Double(data),
             Data::Float(data) => Data::Float(data),
-            Data::Decimal() => Data::Decimal(),
+            Data::Decimal(data) => Data::Decimal(data),
             Data::Integer(data) => Data::Integer(data),
             Data::NonNegativeInteger(data) => Data::NonNegativeInteger(data),
             Data::Long(data) => Data::Long(data),
@@ -204,16 +222,12 @@ impl Data<'_> {
 
 #[derive(Clone, Debug)]
 struct ComplexElement<'e> {
-    #[allow(dead_code)]
     schema: String,
-    #[allow(dead_code)]
     namespace: String,
     name: String,
     nilled: bool,
     array: bool,
-    #[allow(dead_code)]
     valid: bool,
-    #[allow(dead_code)]
     union_member_schema: String,
     children: Vec<Element<'e>>,
 }
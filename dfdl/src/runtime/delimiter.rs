@@ -0,0 +1,165 @@
+use std::fmt::Display;
+
+use crate::schema::{BitOrder, CharClass, DfdlLiteral, Format, LiteralPart, RuntimeValue};
+
+use super::encoding::Encoding;
+use super::{bit_order, encoding, required, runtime_value, FormatError};
+
+
+/// The alternatives of a delimiter property, ready to be matched against the data
+///
+/// A delimiter is matched in the encoding and with the `ignoreCase` of the component defining it.
+/// Of several alternatives matching at the same position, the longest one is used.
+#[derive(Clone, Debug)]
+pub(crate) struct Delimiters {
+    /// name of the property, for diagnostics
+    pub(crate) property: &'static str,
+    alternatives: Vec<DfdlLiteral>,
+    encoding: Encoding,
+    ignore_case: bool,
+    pub(crate) bit_order: BitOrder,
+}
+
+impl Delimiters {
+    /// the delimiters given by the property `property` of `format`, `None` if there are none
+    pub(crate) fn new(format: &Format, value: &Option<RuntimeValue<Vec<DfdlLiteral>>>, property: &'static str, component: &str) -> Result<Option<Self>, FormatError> {
+        if value.is_none() {
+            return Ok(None);
+        }
+        let alternatives: Vec<DfdlLiteral> = runtime_value(value, property, component)?.iter().filter(|literal| !literal.is_empty()).cloned().collect();
+        if alternatives.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            property,
+            alternatives,
+            encoding: encoding(format, component)?,
+            ignore_case: *required(&format.ignore_case, "ignoreCase", component)?,
+            bit_order: bit_order(format, component)?,
+        }))
+    }
+
+    /// the length in bytes of the longest alternative matching at the start of `data`
+    pub(crate) fn match_at(&self, data: &[u8]) -> Option<usize> {
+        self.alternatives.iter().filter_map(|literal| self.match_literal(literal.parts(), data)).max()
+    }
+
+    /// the length in bytes of the match of `parts` at the start of `data`
+    fn match_literal(&self, parts: &[LiteralPart], data: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        for part in parts {
+            let rest = &data[offset..];
+            offset += match part {
+                LiteralPart::Byte(byte) => (rest.first() == Some(byte)).then_some(1)?,
+                LiteralPart::Char(expected) => {
+                    let (c, length) = self.encoding.decode_char(rest)?;
+                    let equal = c == *expected || (self.ignore_case && c.to_lowercase().eq(expected.to_lowercase()));
+                    equal.then_some(length)?
+                },
+                LiteralPart::Class(CharClass::NewLine) => match self.encoding.decode_char(rest)? {
+                    ('\r', length) => length + self.encoding.decode_char(&rest[length..]).filter(|(c, _)| *c == '\n').map_or(0, |(_, length)| length),
+                    ('\n' | '\u{85}' | '\u{2028}', length) => length,
+                    _ => return None,
+                },
+                LiteralPart::Class(CharClass::Whitespace) => self.whitespace(rest, 1, Some(1))?,
+                LiteralPart::Class(CharClass::WhitespacePlus) => self.whitespace(rest, 1, None)?,
+                LiteralPart::Class(CharClass::WhitespaceStar) => self.whitespace(rest, 0, None)?,
+                LiteralPart::Class(CharClass::Empty) => 0,
+            };
+        }
+        Some(offset)
+    }
+
+    /// the length in bytes of the whitespace at the start of `data`, which has to consist of
+    /// at least `min` and at most `max` characters
+    fn whitespace(&self, data: &[u8], min: usize, max: Option<usize>) -> Option<usize> {
+        let (mut count, mut offset) = (0, 0);
+        while max.is_none_or(|max| count < max) {
+            match self.encoding.decode_char(&data[offset..]) {
+                Some((c, length)) if is_whitespace(c) => {
+                    count += 1;
+                    offset += length;
+                },
+                _ => break,
+            }
+        }
+        (count >= min).then_some(offset)
+    }
+}
+
+impl Display for Delimiters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, literal) in self.alternatives.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{literal}")?;
+        }
+        Ok(())
+    }
+}
+
+/// whether `c` is matched by the `%WSP;` character classes
+fn is_whitespace(c: char) -> bool {
+    matches!(c, '\u{09}'..='\u{0D}' | ' ' | '\u{85}' | '\u{A0}' | '\u{1680}' | '\u{180E}' | '\u{2000}'..='\u{200A}'
+        | '\u{2028}' | '\u{2029}' | '\u{202F}' | '\u{205F}' | '\u{3000}')
+}
+
+/// finds the first of `delimiters` in `data`, stepping through it by the characters of `encoding`
+///
+/// Returns the offset of the match and its length in bytes. Delimiters only match if they are not empty,
+/// of several delimiters matching at the same offset the longest one is found.
+pub(crate) fn scan(data: &[u8], encoding: Encoding, delimiters: &[Delimiters]) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while offset < data.len() {
+        let found = delimiters.iter().filter_map(|delimiters| delimiters.match_at(&data[offset..])).filter(|length| *length > 0).max();
+        if let Some(length) = found {
+            return Some((offset, length));
+        }
+        // data that is not valid in the encoding is skipped bytewise
+        offset += encoding.decode_char(&data[offset..]).map_or(1, |(_, length)| length);
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delimiters(list: &str, ignore_case: bool) -> Delimiters {
+        Delimiters {
+            property: "terminator",
+            alternatives: DfdlLiteral::parse_list(list).unwrap(),
+            encoding: Encoding::Utf8,
+            ignore_case,
+            bit_order: BitOrder::MostSignificantBitFirst,
+        }
+    }
+
+    #[test]
+    fn matches_entities_and_alternatives() {
+        let terminators = delimiters("%NL; ; ;;", false);
+        assert_eq!(terminators.match_at(b"\r\nx"), Some(2));
+        assert_eq!(terminators.match_at(b"\rx"), Some(1));
+        assert_eq!(terminators.match_at(b";;x"), Some(2));
+        assert_eq!(terminators.match_at(b"x;"), None);
+        assert_eq!(delimiters("%WSP*;,%WSP+;", false).match_at(b" \t, x"), Some(4));
+        assert_eq!(delimiters("%WSP*;,%WSP+;", false).match_at(b",x"), None);
+        assert_eq!(delimiters("%%%SP;%#x0D;", false).match_at(b"% \r"), Some(3));
+        assert_eq!(delimiters("end", true).match_at("ÉND".as_bytes()), None);
+        assert_eq!(delimiters("énd", true).match_at("ÉND".as_bytes()), Some(4));
+        assert_eq!(delimiters("end", false).match_at(b"END"), None);
+    }
+
+    #[test]
+    fn scans_for_the_first_and_longest_delimiter() {
+        let scope = [delimiters(":", false), delimiters("+ ++", false), delimiters("'", false)];
+        assert_eq!(scan(b"abc++d", Encoding::Utf8, &scope), Some((3, 2)));
+        assert_eq!(scan(b"ab:c'", Encoding::Utf8, &scope), Some((2, 1)));
+        assert_eq!(scan("ä'".as_bytes(), Encoding::Utf8, &scope), Some((2, 1)));
+        assert_eq!(scan(b"abc", Encoding::Utf8, &scope), None);
+        // delimiters that only match the empty string never end the content
+        assert_eq!(scan(b"a b", Encoding::Utf8, &[delimiters("%WSP*;", false)]), Some((1, 1)));
+    }
+}
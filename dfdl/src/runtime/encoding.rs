@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+
+use crate::schema::EncodingErrorPolicy;
+
+
+/// The character encodings supported for text data
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Ascii,
+    Latin1,
+}
+
+impl Encoding {
+    /// looks up an encoding by one of its (case-insensitive) names
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "UTF-8" | "UTF8" => Some(Self::Utf8),
+            "US-ASCII" | "ASCII" => Some(Self::Ascii),
+            "ISO-8859-1" | "ISO8859-1" | "LATIN1" => Some(Self::Latin1),
+            _ => None,
+        }
+    }
+
+    /// the canonical name of the encoding
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Ascii => "US-ASCII",
+            Self::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// the number of bytes of every character, `None` for variable width encodings
+    pub(crate) fn fixed_width(self) -> Option<u64> {
+        match self {
+            Self::Utf8 => None,
+            Self::Ascii | Self::Latin1 => Some(1),
+        }
+    }
+
+    /// the number of bytes taken by the first `chars` characters of `data`,
+    /// or `None` if `data` holds fewer characters
+    pub(crate) fn char_bytes(self, data: &[u8], chars: usize) -> Option<usize> {
+        match self {
+            Self::Ascii | Self::Latin1 => (chars <= data.len()).then_some(chars),
+            Self::Utf8 => {
+                let mut bytes = 0;
+                for _ in 0..chars {
+                    let first = *data.get(bytes)?;
+                    bytes += match first {
+                        0xF0.. => 4,
+                        0xE0.. => 3,
+                        0xC0.. => 2,
+                        _ => 1,
+                    };
+                }
+                (bytes <= data.len()).then_some(bytes)
+            },
+        }
+    }
+
+    /// decodes the first character of `data` and returns it with its length in bytes,
+    /// `None` if `data` is empty or does not start with a valid character
+    pub(crate) fn decode_char(self, data: &[u8]) -> Option<(char, usize)> {
+        match self {
+            Self::Utf8 => {
+                let length = self.char_bytes(data, 1)?;
+                let c = std::str::from_utf8(&data[..length]).ok()?.chars().next()?;
+                Some((c, length))
+            },
+            Self::Ascii => data.first().filter(|b| b.is_ascii()).map(|&b| (char::from(b), 1)),
+            Self::Latin1 => data.first().map(|&b| (char::from(b), 1)),
+        }
+    }
+
+    /// decodes `data`, returning `None` for malformed data under [`EncodingErrorPolicy::Error`]
+    pub(crate) fn decode(self, data: &[u8], policy: EncodingErrorPolicy) -> Option<Cow<'_, str>> {
+        let replace = policy == EncodingErrorPolicy::Replace;
+        match self {
+            Self::Utf8 => match std::str::from_utf8(data) {
+                Ok(text) => Some(Cow::Borrowed(text)),
+                Err(_) if replace => Some(String::from_utf8_lossy(data)),
+                Err(_) => None,
+            },
+            Self::Ascii => match std::str::from_utf8(data) {
+                Ok(text) if text.is_ascii() => Some(Cow::Borrowed(text)),
+                _ if replace => Some(Cow::Owned(data.iter().map(|&b| if b.is_ascii() { b as char } else { char::REPLACEMENT_CHARACTER }).collect())),
+                _ => None,
+            },
+            Self::Latin1 => match std::str::from_utf8(data) {
+                Ok(text) if text.is_ascii() => Some(Cow::Borrowed(text)),
+                _ => Some(Cow::Owned(data.iter().map(|&b| char::from(b)).collect())),
+            },
+        }
+    }
+
+    /// decodes `data` under [`EncodingErrorPolicy::Replace`], replacing every byte that does not start a valid character,
+    /// along with the offset in `data` after each decoded character;
+    /// unless `data` is `complete`, a character cut off at its end is left out
+    pub(crate) fn decode_with_offsets(self, data: &[u8], complete: bool) -> (String, Vec<usize>) {
+        let mut text = String::new();
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let rest = &data[offset..];
+            let (c, length) = match self.decode_char(rest) {
+                Some(decoded) => decoded,
+                // the rest of the character may follow the data
+                None if !complete && self.char_bytes(rest, 1).is_none() => break,
+                None => (char::REPLACEMENT_CHARACTER, 1),
+            };
+            text.push(c);
+            offset += length;
+            offsets.push(offset);
+        }
+        (text, offsets)
+    }
+
+    /// encodes `text`, returning `None` if it contains characters the encoding can not represent
+    /// under [`EncodingErrorPolicy::Error`]
+    pub(crate) fn encode(self, text: &str, policy: EncodingErrorPolicy) -> Option<Cow<'_, [u8]>> {
+        let limit = match self {
+            Self::Utf8 => return Some(Cow::Borrowed(text.as_bytes())),
+            Self::Ascii => 0x7F,
+            Self::Latin1 => 0xFF,
+        };
+        if text.is_ascii() {
+            return Some(Cow::Borrowed(text.as_bytes()));
+        }
+        text.chars().map(|c| match u8::try_from(u32::from(c)) {
+            Ok(b) if b <= limit => Some(b),
+            // the substitution character of ASCII based encodings
+            _ if policy == EncodingErrorPolicy::Replace => Some(0x1A),
+            _ => None,
+        }).collect::<Option<Vec<u8>>>().map(Cow::Owned)
+    }
+}
@@ -0,0 +1,738 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+use regex::Regex;
+
+use crate::schema::{BinaryOperator, DfdlLiteral, ElementContent, Expression, ExpressionKind, Function, Literal, LiteralPart, PathExpression, PrimitiveType, SimpleType, Step, TextNumberRoundingMode, Type};
+use crate::{Data, Decimal, Element, SimpleElement};
+
+use super::number::Number;
+use super::path::{Location, Node, Tree};
+use super::{implicit_binary_length, integer_data, integer_value};
+
+
+/// The value of an expression
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Boolean(bool),
+    /// a value of `xs:integer` or one of the types derived from it
+    Integer(i128),
+    /// a value of `xs:decimal`
+    Decimal(Decimal),
+    /// a value of `xs:double` or `xs:float`
+    Double(f64),
+    String(String),
+    HexBinary(Vec<u8>),
+}
+
+/// The length of (a part of) the representation of an element
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Length {
+    pub(crate) bits: u64,
+    /// the number of characters, for text
+    pub(crate) characters: Option<u64>,
+}
+
+/// The lengths of the representation of an element, as returned by `dfdl:contentLength` and `dfdl:valueLength`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Lengths {
+    /// the content, without the initiator, terminator and length prefix
+    pub(crate) content: Length,
+    /// the value, which is the content without padding
+    pub(crate) value: Length,
+}
+
+impl Lengths {
+    /// the lengths of content of `bits` bits without padding
+    pub(crate) fn unpadded(bits: u64) -> Self {
+        let length = Length { bits, characters: None };
+        Self { content: length, value: length }
+    }
+}
+
+/// What evaluating an expression needs to know about the elements beyond their values in the infoset
+pub(crate) trait Environment {
+    /// the length of the content of the element at `location`, or of its value if `value` is set
+    fn length(&mut self, location: &[usize], value: bool) -> Result<Length, String>;
+
+    /// the value of the element at `location` if it is calculated rather than taken from the infoset
+    fn calculated(&mut self, _location: &[usize]) -> Result<Option<Value>, String> {
+        Ok(None)
+    }
+}
+
+impl Value {
+    /// the value of infoset data
+    fn from_data(data: &Data<'_>) -> Result<Self, String> {
+        Ok(match data {
+            Data::Boolean(value) => Self::Boolean(*value),
+            Data::Double(value) => Self::Double(*value),
+            Data::Float(value) => Self::Double((*value).into()),
+            Data::String(text) => Self::String(text.to_string()),
+            Data::HexBinary(bytes) => Self::HexBinary(bytes.to_vec()),
+            Data::Decimal(decimal) => Self::Decimal(decimal.clone()),
+            Data::DateTime | Data::Date | Data::Time => return Err(format!("{} values are not supported in expressions yet", data.typename())),
+            _ => Self::Integer(integer_value(data).unwrap_or_default()),
+        })
+    }
+
+    /// the value converted to infoset data of type `primitive`
+    pub(crate) fn to_data(&self, primitive: PrimitiveType) -> Result<Data<'static>, String> {
+        Ok(match cast(self.clone(), primitive)? {
+            Self::Boolean(value) => Data::Boolean(value),
+            Self::Integer(value) => integer_data(primitive, value).ok_or_else(|| format!("{value} is out of range for {primitive:?}"))?,
+            Self::Decimal(value) => Data::Decimal(value),
+            Self::Double(value) if primitive == PrimitiveType::Float => Data::Float(value as f32),
+            Self::Double(value) => Data::Double(value),
+            Self::String(text) => Data::String(text.into()),
+            Self::HexBinary(bytes) => Data::HexBinary(bytes.into()),
+        })
+    }
+
+    /// the value of a number as a floating point number
+    fn number(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Decimal(value) => Some(value.to_f64()),
+            Self::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// the exact value of an integer or decimal
+    fn decimal(&self) -> Option<Decimal> {
+        match self {
+            Self::Integer(value) => Some(Decimal::from(*value)),
+            Self::Decimal(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// the text of a value, as given by `fn:string`
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Boolean(value) => write!(f, "{value}"),
+            Self::Integer(value) => write!(f, "{value}"),
+            // decimals are written without trailing zeros in their fraction
+            Self::Decimal(value) => write!(f, "{}", Number::from_decimal(value).to_decimal(0)),
+            Self::Double(value) if value.is_nan() => f.write_str("NaN"),
+            Self::Double(value) if value.is_infinite() => f.write_str(if *value < 0.0 { "-INF" } else { "INF" }),
+            // doubles of moderate magnitude are written like decimals, all others in scientific notation
+            Self::Double(value) if *value == 0.0 || (1e-6..1e6).contains(&value.abs()) => write!(f, "{value}"),
+            Self::Double(value) => {
+                let text = format!("{value:E}");
+                match text.split_once('E') {
+                    Some((mantissa, exponent)) if !mantissa.contains('.') => write!(f, "{mantissa}.0E{exponent}"),
+                    _ => f.write_str(&text),
+                }
+            },
+            Self::String(text) => f.write_str(text),
+            Self::HexBinary(bytes) => bytes.iter().try_for_each(|byte| write!(f, "{byte:02X}")),
+        }
+    }
+}
+
+/// Evaluates the type checked `expression` against `tree`
+///
+/// Fails with a description of the problem.
+pub(crate) fn evaluate(expression: &Expression, tree: &Tree<'_, '_>, environment: &mut dyn Environment) -> Result<Value, String> {
+    Evaluator { tree, environment }.evaluate(expression)
+}
+
+struct Evaluator<'t, 'a, 'i> {
+    tree: &'t Tree<'a, 'i>,
+    environment: &'t mut dyn Environment,
+}
+
+impl Evaluator<'_, '_, '_> {
+    fn evaluate(&mut self, expression: &Expression) -> Result<Value, String> {
+        let value = match &expression.kind {
+            ExpressionKind::Literal(literal) => match literal {
+                Literal::String(text) => Value::String(text.clone()),
+                Literal::Integer(value) => Value::Integer(*value),
+                Literal::Decimal(text) => Value::Decimal(text.parse().map_err(|_| format!("invalid decimal '{text}'"))?),
+                Literal::Double(text) => Value::Double(text.parse().map_err(|_| format!("invalid double '{text}'"))?),
+            },
+            ExpressionKind::Path(path) => {
+                let location = self.single(path)?;
+                self.value(&location, path)?
+            },
+            ExpressionKind::Negate(operand) => match self.evaluate(operand)? {
+                Value::Integer(value) => Value::Integer(value.checked_neg().ok_or("integer overflow")?),
+                Value::Decimal(value) => Value::Decimal(Decimal::from_digits(!value.is_negative(), value.digits().to_vec(), value.scale())),
+                Value::Double(value) => Value::Double(-value),
+                value => return Err(format!("'{value}' is not a number")),
+            },
+            ExpressionKind::Binary { operator: BinaryOperator::Or, left, right } => Value::Boolean(self.boolean(left)? || self.boolean(right)?),
+            ExpressionKind::Binary { operator: BinaryOperator::And, left, right } => Value::Boolean(self.boolean(left)? && self.boolean(right)?),
+            ExpressionKind::Binary { operator, left, right } if operator.is_comparison() => {
+                let ordering = compare(&self.evaluate(left)?, &self.evaluate(right)?);
+                // comparisons with NaN only hold for 'ne'
+                Value::Boolean(match operator {
+                    BinaryOperator::Equal => ordering == Some(Ordering::Equal),
+                    BinaryOperator::NotEqual => ordering != Some(Ordering::Equal),
+                    BinaryOperator::Less => ordering == Some(Ordering::Less),
+                    BinaryOperator::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    BinaryOperator::Greater => ordering == Some(Ordering::Greater),
+                    _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                })
+            },
+            ExpressionKind::Binary { operator, left, right } => {
+                let (left, right) = (self.evaluate(left)?, self.evaluate(right)?);
+                arithmetic(*operator, left, right, expression.ty)?
+            },
+            ExpressionKind::If { condition, then, otherwise } => {
+                let value = if self.boolean(condition)? { self.evaluate(then)? } else { self.evaluate(otherwise)? };
+                // the branches may be numbers of different types
+                match expression.ty {
+                    Type::Atomic(ty) if value.number().is_some() => cast(value, ty)?,
+                    _ => value,
+                }
+            },
+            ExpressionKind::Cast { operand, target } => match (self.evaluate(operand)?, *target) {
+                // numbers are converted to their two's complement representation in the length of their type
+                (Value::Integer(value), PrimitiveType::HexBinary) => {
+                    let bytes = match operand.ty {
+                        Type::Atomic(ty) => implicit_binary_length(ty).map(|bits| bits as usize / 8),
+                        _ => None,
+                    };
+                    let full = value.to_be_bytes();
+                    let bytes = bytes.unwrap_or_else(|| {
+                        // the shortest representation that keeps the sign
+                        (1..16).find(|bytes| matches!(value >> (bytes * 8 - 1), 0 | -1)).unwrap_or(16)
+                    });
+                    Value::HexBinary(full[16 - bytes..].to_vec())
+                },
+                (value, target) => cast(value, target)?,
+            },
+            ExpressionKind::Call { function, arguments } => self.call(*function, arguments)?,
+        };
+        Ok(value)
+    }
+
+    fn boolean(&mut self, expression: &Expression) -> Result<bool, String> {
+        match self.evaluate(expression)? {
+            Value::Boolean(value) => Ok(value),
+            value => Err(format!("'{value}' is not a boolean")),
+        }
+    }
+
+    fn integer(&mut self, expression: &Expression) -> Result<i128, String> {
+        match self.evaluate(expression)? {
+            Value::Integer(value) => Ok(value),
+            value => Err(format!("'{value}' is not an integer")),
+        }
+    }
+
+    fn string(&mut self, expression: &Expression) -> Result<String, String> {
+        match self.evaluate(expression)? {
+            Value::String(text) => Ok(text),
+            value => Err(format!("'{value}' is not a string")),
+        }
+    }
+
+    fn number(&mut self, expression: &Expression) -> Result<f64, String> {
+        let value = self.evaluate(expression)?;
+        value.number().ok_or_else(|| format!("'{value}' is not a number"))
+    }
+
+    /// the locations of the elements `path` leads to, in document order
+    fn resolve(&mut self, path: &PathExpression) -> Result<Vec<Location>, String> {
+        // `None` stands for the document above the root element
+        let mut nodes: Vec<Option<Location>> = vec![if path.absolute { None } else { Some(self.tree.context()) }];
+        for step in &path.steps {
+            nodes = match step {
+                Step::Current => nodes,
+                Step::Parent => {
+                    let mut parents = Vec::new();
+                    for node in nodes {
+                        let parent = node.and_then(|mut location| location.pop().map(|_| location));
+                        if !parents.contains(&parent) {
+                            parents.push(parent);
+                        }
+                    }
+                    parents
+                },
+                Step::Child { name, index } => {
+                    let index = match index {
+                        Some(index) => match self.integer(index)? {
+                            index @ 1.. => Some(usize::try_from(index - 1).unwrap_or(usize::MAX)),
+                            index => return Err(format!("the index {index} in '{path}' is not positive")),
+                        },
+                        None => None,
+                    };
+                    let mut children = Vec::new();
+                    for node in &nodes {
+                        let matching = match node {
+                            None if self.tree.root_name() == name => vec![Vec::new()],
+                            None => Vec::new(),
+                            Some(location) => self.tree.children(location, name),
+                        };
+                        match index {
+                            Some(index) => children.extend(matching.into_iter().nth(index)),
+                            None => children.extend(matching),
+                        }
+                    }
+                    children.into_iter().map(Some).collect()
+                },
+            };
+        }
+        Ok(nodes.into_iter().flatten().collect())
+    }
+
+    /// the location of the single element `path` leads to
+    fn single(&mut self, path: &PathExpression) -> Result<Location, String> {
+        self.resolve(path)?.pop().ok_or_else(|| format!("'{path}' does not lead to an element"))
+    }
+
+    /// the value of the element at `location`, which `path` leads to
+    fn value(&mut self, location: &[usize], path: &PathExpression) -> Result<Value, String> {
+        if let Some(value) = self.environment.calculated(location)? {
+            return Ok(value);
+        }
+        match self.tree.node(location) {
+            Some(Node::Element(Element::SimpleElement(SimpleElement { data: Some(data), .. }))) => Value::from_data(data),
+            Some(Node::Element(Element::SimpleElement(_))) => Err(format!("the element '{path}' leads to has no value")),
+            Some(Node::Pending(_)) => Err(format!("the value of '{path}' is not known yet")),
+            _ => Err(format!("'{path}' does not lead to a simple element")),
+        }
+    }
+
+    /// the argument of a function that has to be a path
+    fn path_argument<'e>(&self, function: Function, argument: &'e Expression) -> Result<&'e PathExpression, String> {
+        match &argument.kind {
+            ExpressionKind::Path(path) => Ok(path),
+            _ => Err(format!("the argument of {} has to be a path", function.name())),
+        }
+    }
+
+    fn call(&mut self, function: Function, arguments: &[Expression]) -> Result<Value, String> {
+        Ok(match function {
+            Function::Count | Function::Exists | Function::Empty => {
+                let count = self.resolve(self.path_argument(function, &arguments[0])?)?.len();
+                match function {
+                    Function::Count => Value::Integer(count as i128),
+                    Function::Exists => Value::Boolean(count > 0),
+                    _ => Value::Boolean(count == 0),
+                }
+            },
+            Function::True => Value::Boolean(true),
+            Function::False => Value::Boolean(false),
+            Function::Not => Value::Boolean(!self.boolean(&arguments[0])?),
+            Function::StringLength => Value::Integer(self.string(&arguments[0])?.chars().count() as i128),
+            Function::Concat => {
+                let mut text = String::new();
+                for argument in arguments {
+                    text += &self.evaluate(argument)?.to_string();
+                }
+                Value::String(text)
+            },
+            Function::Substring => {
+                let text = self.string(&arguments[0])?;
+                // characters are selected by rounded positions, counted from 1
+                let start = round(self.number(&arguments[1])?);
+                let end = match arguments.get(2) {
+                    Some(length) => start + round(self.number(length)?),
+                    None => f64::INFINITY,
+                };
+                Value::String(text.chars().enumerate()
+                    .filter(|(index, _)| { let position = (index + 1) as f64; position >= start && position < end })
+                    .map(|(_, c)| c)
+                    .collect())
+            },
+            Function::UpperCase => Value::String(self.string(&arguments[0])?.to_uppercase()),
+            Function::LowerCase => Value::String(self.string(&arguments[0])?.to_lowercase()),
+            Function::Contains | Function::StartsWith | Function::EndsWith => {
+                let (text, part) = (self.string(&arguments[0])?, self.string(&arguments[1])?);
+                Value::Boolean(match function {
+                    Function::Contains => text.contains(&part),
+                    Function::StartsWith => text.starts_with(&part),
+                    _ => text.ends_with(&part),
+                })
+            },
+            Function::Abs | Function::Ceiling | Function::Floor | Function::Round => match self.evaluate(&arguments[0])? {
+                Value::Integer(value) if function == Function::Abs => Value::Integer(value.checked_abs().ok_or("integer overflow")?),
+                value @ Value::Integer(_) => value,
+                Value::Decimal(value) => Value::Decimal(rounded_decimal(function, &value)),
+                Value::Double(value) => Value::Double(rounded(function, value)),
+                value => return Err(format!("'{value}' is not a number")),
+            },
+            Function::RoundHalfToEven => {
+                let value = self.evaluate(&arguments[0])?;
+                let precision = match arguments.get(1) {
+                    Some(precision) => i32::try_from(self.integer(precision)?).map_err(|_| "the precision is out of range")?,
+                    None => 0,
+                };
+                match value {
+                    Value::Integer(value) if precision >= 0 => Value::Integer(value),
+                    Value::Integer(value) => {
+                        let factor = 10i128.checked_pow(precision.unsigned_abs()).ok_or("the precision is out of range")?;
+                        let (quotient, remainder) = (value.div_euclid(factor), value.rem_euclid(factor));
+                        let up = match (remainder * 2).cmp(&factor) {
+                            Ordering::Less => false,
+                            Ordering::Equal => quotient % 2 != 0,
+                            Ordering::Greater => true,
+                        };
+                        Value::Integer((quotient + i128::from(up)) * factor)
+                    },
+                    Value::Decimal(value) => Value::Decimal(round_decimal(&value, precision, TextNumberRoundingMode::HalfEven)),
+                    Value::Double(value) => Value::Double(round_half_to_even(value, precision)),
+                    value => return Err(format!("'{value}' is not a number")),
+                }
+            },
+            Function::ValueLength | Function::ContentLength => {
+                let path = self.path_argument(function, &arguments[0])?;
+                let location = self.single(path)?;
+                let units = self.string(&arguments[1])?;
+                let length = self.environment.length(&location, function == Function::ValueLength)?;
+                Value::Integer(match units.as_str() {
+                    "bits" => length.bits,
+                    "bytes" => length.bits.div_ceil(8),
+                    "characters" => length.characters.ok_or_else(|| format!("the length of '{path}' in characters is only known for text"))?,
+                    _ => return Err(format!("unknown length units '{units}', expected 'bits', 'bytes' or 'characters'")),
+                }.into())
+            },
+            Function::OccursIndex => {
+                let mut location = self.tree.context();
+                let Some(index) = location.pop() else { return Ok(Value::Integer(1)) };
+                let node = self.tree.node(&[location.as_slice(), &[index]].concat());
+                let name = node.map(|node| self.tree.name(&node)).ok_or("the context element is not known")?;
+                let before = self.tree.children(&location, name).iter().filter(|sibling| sibling.last().is_some_and(|sibling| *sibling < index)).count();
+                Value::Integer(before as i128 + 1)
+            },
+            Function::CheckConstraints => {
+                let path = self.path_argument(function, &arguments[0])?;
+                let location = self.single(path)?;
+                let value = self.value(&location, path)?;
+                let decl = self.tree.decl(&location).ok_or_else(|| format!("'{path}' does not lead to a declared element"))?;
+                match &decl.content {
+                    ElementContent::Simple(simple) => Value::Boolean(satisfies(&value, simple)?),
+                    ElementContent::Complex(_) => Value::Boolean(true),
+                }
+            },
+            Function::TestBit => {
+                let data = u8::try_from(self.integer(&arguments[0])?).map_err(|_| "the data of dfdl:testBit has to be an unsigned byte")?;
+                let position = match self.integer(&arguments[1])? {
+                    position @ 1..=8 => position as u32,
+                    position => return Err(format!("the bit position {position} is not between 1 and 8")),
+                };
+                Value::Boolean(data >> (position - 1) & 1 == 1)
+            },
+            Function::SetBits => {
+                let mut byte = 0;
+                // the first argument is the least significant bit
+                for (index, argument) in arguments.iter().enumerate() {
+                    match self.integer(argument)? {
+                        0 => {},
+                        1 => byte |= 1 << index,
+                        bit => return Err(format!("the bit {bit} is neither 0 nor 1")),
+                    }
+                }
+                Value::Integer(byte)
+            },
+            Function::DecodeDfdlEntities => {
+                let text = self.string(&arguments[0])?;
+                let literal = DfdlLiteral::parse(&text)?;
+                let decoded = literal.parts().iter().map(|part| match part {
+                    LiteralPart::Char(c) => Ok(*c),
+                    LiteralPart::Byte(byte) => Ok(char::from(*byte)),
+                    LiteralPart::Class(_) => Err(format!("'{text}' contains a character class entity, which can not be decoded")),
+                }).collect::<Result<String, String>>()?;
+                Value::String(decoded)
+            },
+            Function::EncodeDfdlEntities => {
+                let text = self.string(&arguments[0])?;
+                Value::String(text.chars().map(|c| match c {
+                    '%' => "%%".to_string(),
+                    c if c.is_control() => format!("%#x{:X};", u32::from(c)),
+                    c => c.to_string(),
+                }).collect())
+            },
+        })
+    }
+}
+
+/// whether `value` satisfies the facets of the simple type `simple`
+fn satisfies(value: &Value, simple: &SimpleType) -> Result<bool, String> {
+    let facets = &simple.facets;
+    let length = match value {
+        Value::String(text) => Some(text.chars().count() as u64),
+        Value::HexBinary(bytes) => Some(bytes.len() as u64),
+        _ => None,
+    };
+    if let Some(length) = length {
+        if simple.max_length.is_some_and(|max_length| length > max_length) || facets.min_length.is_some_and(|min_length| length < min_length) {
+            return Ok(false);
+        }
+    }
+    // patterns match the text of the value
+    let text = value.to_string();
+    for alternatives in &facets.patterns {
+        let regex = Regex::new(&format!("^(?:{})$", alternatives.join("|"))).map_err(|error| error.to_string())?;
+        if !regex.is_match(&text) {
+            return Ok(false);
+        }
+    }
+    // the facets are compared in the value space of the widest type of their kind
+    let ty = match simple.primitive {
+        PrimitiveType::Float => PrimitiveType::Double,
+        primitive if integer_data(primitive, 0).is_some() => PrimitiveType::Integer,
+        primitive => primitive,
+    };
+    let order = |facet: &String| Ok::<_, String>(compare(value, &cast(Value::String(facet.clone()), ty)?));
+    if let Some(enumeration) = &facets.enumeration {
+        let mut found = false;
+        for facet in enumeration {
+            found |= order(facet)? == Some(Ordering::Equal);
+        }
+        if !found {
+            return Ok(false);
+        }
+    }
+    let bounds = [
+        (&facets.min_inclusive, Ordering::is_ge as fn(Ordering) -> bool),
+        (&facets.max_inclusive, Ordering::is_le),
+        (&facets.min_exclusive, Ordering::is_gt),
+        (&facets.max_exclusive, Ordering::is_lt),
+    ];
+    for (facet, allowed) in bounds {
+        if let Some(facet) = facet {
+            // NaN is outside of all bounds
+            if !order(facet)?.is_some_and(allowed) {
+                return Ok(false);
+            }
+        }
+    }
+    if let Some(decimal) = value.decimal() {
+        // the value is digits × 10^exponent without trailing zeros
+        let number = Number::from_decimal(&decimal);
+        let fraction = u64::from(number.exponent.min(0).unsigned_abs());
+        let total = (number.digits.len() as u64 + u64::from(number.exponent.max(0).unsigned_abs())).max(fraction);
+        if facets.total_digits.is_some_and(|digits| total > u64::from(digits)) || facets.fraction_digits.is_some_and(|digits| fraction > u64::from(digits)) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// the order of two values, `None` if they can not be compared or one of them is NaN
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => Some(left.cmp(right)),
+        (Value::Boolean(left), Value::Boolean(right)) => Some(left.cmp(right)),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::HexBinary(left), Value::HexBinary(right)) => Some(left.cmp(right)),
+        (left, right) => match (left.decimal(), right.decimal()) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => left.number()?.partial_cmp(&right.number()?),
+        },
+    }
+}
+
+/// the result of an arithmetic `operator`, which type checking found to be of type `ty`
+fn arithmetic(operator: BinaryOperator, left: Value, right: Value, ty: Type) -> Result<Value, String> {
+    let overflow = || "integer overflow".to_string();
+    if let (Value::Integer(left), Value::Integer(right)) = (&left, &right) {
+        let (left, right) = (*left, *right);
+        let integer = match operator {
+            BinaryOperator::Add => Some(left.checked_add(right).ok_or_else(overflow)?),
+            BinaryOperator::Subtract => Some(left.checked_sub(right).ok_or_else(overflow)?),
+            BinaryOperator::Multiply => Some(left.checked_mul(right).ok_or_else(overflow)?),
+            BinaryOperator::IntegerDivide | BinaryOperator::Modulo if right == 0 => return Err("division by zero".to_string()),
+            BinaryOperator::IntegerDivide => Some(left.checked_div(right).ok_or_else(overflow)?),
+            BinaryOperator::Modulo => Some(left.checked_rem(right).ok_or_else(overflow)?),
+            _ => None,
+        };
+        if let Some(integer) = integer {
+            return Ok(Value::Integer(integer));
+        }
+    }
+    if let (Some(left), Some(right)) = (left.decimal(), right.decimal()) {
+        return decimal_arithmetic(operator, &left, &right);
+    }
+    let number = |value: &Value| value.number().ok_or_else(|| format!("'{value}' is not a number"));
+    let (left, right) = (number(&left)?, number(&right)?);
+    // doubles and floats have values for all divisions by zero but the integer division
+    if right == 0.0 && operator == BinaryOperator::IntegerDivide {
+        return Err("division by zero".to_string());
+    }
+    let value = match operator {
+        BinaryOperator::Add => left + right,
+        BinaryOperator::Subtract => left - right,
+        BinaryOperator::Multiply => left * right,
+        BinaryOperator::Divide => left / right,
+        BinaryOperator::Modulo => left % right,
+        _ => {
+            let quotient = (left / right).trunc();
+            if !quotient.is_finite() {
+                return Err(format!("{left} idiv {right} is not a finite number"));
+            }
+            return Ok(Value::Integer(quotient as i128));
+        },
+    };
+    Ok(match ty {
+        Type::Atomic(PrimitiveType::Float) => Value::Double(f64::from(value as f32)),
+        _ => Value::Double(value),
+    })
+}
+
+/// The most fraction digits of the quotient of two decimals, after which it is truncated
+const DIVISION_DIGITS: u32 = 18;
+
+/// the exact result of an arithmetic `operator` on two decimals, but for the fraction digits of a division
+fn decimal_arithmetic(operator: BinaryOperator, left: &Decimal, right: &Decimal) -> Result<Value, String> {
+    let overflow = || "decimal overflow".to_string();
+    if operator == BinaryOperator::Multiply {
+        let product = left.unscaled().zip(right.unscaled()).and_then(|(left, right)| left.checked_mul(right)).ok_or_else(overflow)?;
+        let scale = left.scale().checked_add(right.scale()).ok_or_else(overflow)?;
+        return Ok(Value::Decimal(Decimal::new(product, scale)));
+    }
+    // both values as integers of the same scale
+    let scale = left.scale().max(right.scale());
+    let rescaled = |decimal: &Decimal| decimal.unscaled()?.checked_mul(10i128.checked_pow(scale - decimal.scale())?);
+    let (left, right) = rescaled(left).zip(rescaled(right)).ok_or_else(overflow)?;
+    if right == 0 && operator != BinaryOperator::Add && operator != BinaryOperator::Subtract {
+        return Err("division by zero".to_string());
+    }
+    Ok(match operator {
+        BinaryOperator::Add => Value::Decimal(Decimal::new(left.checked_add(right).ok_or_else(overflow)?, scale)),
+        BinaryOperator::Subtract => Value::Decimal(Decimal::new(left.checked_sub(right).ok_or_else(overflow)?, scale)),
+        BinaryOperator::Modulo => Value::Decimal(Decimal::new(left.checked_rem(right).ok_or_else(overflow)?, scale)),
+        BinaryOperator::IntegerDivide => Value::Integer(left.checked_div(right).ok_or_else(overflow)?),
+        _ => {
+            let mut quotient = left.checked_div(right).ok_or_else(overflow)?;
+            let mut remainder = left % right;
+            // long division, until the quotient is exact or has no room for more digits
+            let mut digits = 0;
+            while remainder != 0 && digits < DIVISION_DIGITS {
+                let Some(dividend) = remainder.checked_mul(10) else { break };
+                let Some(shifted) = quotient.checked_mul(10).and_then(|quotient| quotient.checked_add(dividend / right)) else { break };
+                (quotient, remainder, digits) = (shifted, dividend % right, digits + 1);
+            }
+            Value::Decimal(Decimal::new(quotient, digits))
+        },
+    })
+}
+
+/// `value` converted to the type `target`, as by the constructor functions like `xs:int`
+fn cast(value: Value, target: PrimitiveType) -> Result<Value, String> {
+    use PrimitiveType as P;
+
+    let invalid = |value: &Value| format!("'{value}' can not be converted to {target:?}");
+    Ok(match target {
+        P::String => Value::String(value.to_string()),
+        P::Boolean => Value::Boolean(match &value {
+            Value::Boolean(value) => *value,
+            Value::Integer(number) => *number != 0,
+            Value::Decimal(number) => !number.is_zero(),
+            Value::Double(number) => *number != 0.0 && !number.is_nan(),
+            Value::String(text) => match text.trim() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(invalid(&value)),
+            },
+            Value::HexBinary(_) => return Err(invalid(&value)),
+        }),
+        P::Decimal => Value::Decimal(match &value {
+            Value::Boolean(value) => Decimal::from(u8::from(*value)),
+            Value::Integer(value) => Decimal::from(*value),
+            Value::Decimal(value) => value.clone(),
+            Value::Double(number) if number.is_finite() => Number::from_float(*number).to_decimal(0),
+            // decimals are written without exponent
+            Value::String(text) => text.trim().parse().map_err(|_| invalid(&value))?,
+            Value::Double(_) | Value::HexBinary(_) => return Err(invalid(&value)),
+        }),
+        P::Float | P::Double => {
+            let number = match &value {
+                Value::Boolean(value) => f64::from(u8::from(*value)),
+                Value::Integer(_) | Value::Decimal(_) | Value::Double(_) => value.number().unwrap_or_default(),
+                Value::String(text) => match text.trim() {
+                    "INF" | "+INF" => f64::INFINITY,
+                    "-INF" => f64::NEG_INFINITY,
+                    "NaN" => f64::NAN,
+                    text if text.chars().any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E')) => return Err(invalid(&value)),
+                    text => text.parse().map_err(|_| invalid(&value))?,
+                },
+                Value::HexBinary(_) => return Err(invalid(&value)),
+            };
+            Value::Double(if target == P::Float { f64::from(number as f32) } else { number })
+        },
+        P::HexBinary => match value {
+            Value::HexBinary(bytes) => Value::HexBinary(bytes),
+            Value::String(text) if text.len().is_multiple_of(2) && text.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Value::HexBinary((0..text.len()).step_by(2).map(|index| u8::from_str_radix(&text[index..index + 2], 16).unwrap_or_default()).collect())
+            },
+            value => return Err(invalid(&value)),
+        },
+        P::DateTime | P::Date | P::Time => return Err("calendar values are not supported in expressions yet".to_string()),
+        _ => {
+            let integer = match &value {
+                Value::Boolean(value) => i128::from(*value),
+                Value::Integer(value) => *value,
+                Value::Decimal(number) => round_decimal(number, 0, TextNumberRoundingMode::Down).to_i128().ok_or_else(|| format!("{value} is out of range for {target:?}"))?,
+                Value::Double(number) if number.is_finite() => number.trunc() as i128,
+                Value::String(text) => text.trim().parse().map_err(|_| invalid(&value))?,
+                _ => return Err(invalid(&value)),
+            };
+            // xs:integer is unbounded, the derived types are checked against their value space
+            let valid = match target {
+                P::Integer => true,
+                P::NonNegativeInteger => integer >= 0,
+                _ => integer_data(target, integer).is_some(),
+            };
+            if !valid {
+                return Err(format!("{integer} is out of range for {target:?}"));
+            }
+            Value::Integer(integer)
+        },
+    })
+}
+
+/// rounds `value` to an integer as `fn:round`, with halves rounded towards positive infinity
+fn round(value: f64) -> f64 {
+    (value + 0.5).floor()
+}
+
+/// applies `fn:abs`, `fn:ceiling`, `fn:floor` or `fn:round` to a floating point number
+fn rounded(function: Function, value: f64) -> f64 {
+    match function {
+        Function::Abs => value.abs(),
+        Function::Ceiling => value.ceil(),
+        Function::Floor => value.floor(),
+        _ if value.is_finite() => round(value),
+        _ => value,
+    }
+}
+
+/// applies `fn:abs`, `fn:ceiling`, `fn:floor` or `fn:round` to a decimal
+fn rounded_decimal(function: Function, value: &Decimal) -> Decimal {
+    let mode = match function {
+        Function::Abs => return Decimal::from_digits(false, value.digits().to_vec(), value.scale()),
+        Function::Ceiling => TextNumberRoundingMode::Ceiling,
+        Function::Floor => TextNumberRoundingMode::Floor,
+        // halves are rounded towards positive infinity
+        _ if value.is_negative() => TextNumberRoundingMode::HalfDown,
+        _ => TextNumberRoundingMode::HalfUp,
+    };
+    round_decimal(value, 0, mode)
+}
+
+/// rounds `value` to `precision` decimal places in `mode`, keeping its scale if it has fewer
+fn round_decimal(value: &Decimal, precision: i32, mode: TextNumberRoundingMode) -> Decimal {
+    // precisions beyond these bounds do not change the result
+    let precision = precision.clamp(i32::MIN / 2, i32::MAX / 2);
+    let mut number = Number::from_decimal(value);
+    number.round(precision, mode);
+    number.to_decimal(value.scale().min(precision.max(0).unsigned_abs()))
+}
+
+/// rounds `value` to `precision` decimal places, with halves rounded to the even neighbour
+fn round_half_to_even(value: f64, precision: i32) -> f64 {
+    if !value.is_finite() {
+        return value;
+    }
+    let scale = 10f64.powi(precision);
+    (value * scale).round_ties_even() / scale
+}
@@ -0,0 +1,360 @@
+use std::borrow::Cow;
+
+use crate::schema::{BitOrder, ByteOrder};
+
+
+/// Problems accessing the data at the bit level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StreamError {
+    /// `needed` more bits were requested at `position`, beyond the end of the available data
+    EndOfData { position: u64, needed: u64 },
+    /// the bit order changed at `position`, which is not on a byte boundary
+    BitOrderChange { position: u64 },
+}
+
+/// a position in a stream, to return to when backtracking
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Mark {
+    position: u64,
+    bit_order: Option<BitOrder>,
+}
+
+/// the value of the bit at `index` within `byte`, counted in `bit_order`
+fn bit(byte: u8, index: u64, bit_order: BitOrder) -> u8 {
+    match bit_order {
+        BitOrder::MostSignificantBitFirst => (byte >> (7 - index)) & 1,
+        BitOrder::LeastSignificantBitFirst => (byte >> index) & 1,
+    }
+}
+
+/// `byte` with the bit at `index`, counted in `bit_order`, set to `value`
+fn with_bit(byte: u8, index: u64, bit_order: BitOrder, value: u8) -> u8 {
+    let mask = match bit_order {
+        BitOrder::MostSignificantBitFirst => 0x80 >> index,
+        BitOrder::LeastSignificantBitFirst => 1 << index,
+    };
+    if value == 0 { byte & !mask } else { byte | mask }
+}
+
+/// Data being parsed, read at bit granularity
+///
+/// Numbers of more than 8 bits are read as follows:
+/// - `mostSignificantBitFirst` and `bigEndian`: the bits in order, the first bit being the most significant
+/// - `mostSignificantBitFirst` and `littleEndian`: groups of 8 bits (the last group possibly shorter),
+///   the first group being the least significant
+/// - `leastSignificantBitFirst`: the bits in order, the first bit being the least significant
+pub(crate) struct InputSource<'i> {
+    data: &'i [u8],
+    /// position of the next unread bit
+    position: u64,
+    /// end of the data available to the component currently being parsed, in bits
+    limit: u64,
+    /// bit order of the last read
+    bit_order: Option<BitOrder>,
+}
+
+impl<'i> InputSource<'i> {
+    pub(crate) fn new(data: &'i [u8]) -> Self {
+        Self { data, position: 0, limit: data.len() as u64 * 8, bit_order: None }
+    }
+
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// the number of whole bytes after the current position
+    pub(crate) fn remaining_bytes(&self) -> u64 {
+        (self.data.len() as u64 * 8 - self.position) / 8
+    }
+
+    /// the number of bits available to the component currently being parsed
+    pub(crate) fn remaining_bits(&self) -> u64 {
+        self.limit.saturating_sub(self.position)
+    }
+
+    /// restricts the available data to end at bit `limit`, returning the previous limit
+    pub(crate) fn set_limit(&mut self, limit: u64) -> u64 {
+        std::mem::replace(&mut self.limit, limit)
+    }
+
+    pub(crate) fn mark(&self) -> Mark {
+        Mark { position: self.position, bit_order: self.bit_order }
+    }
+
+    pub(crate) fn reset(&mut self, mark: Mark) {
+        self.position = mark.position;
+        self.bit_order = mark.bit_order;
+    }
+
+    /// checks that `bits` bits are available, and that reading them with `bit_order` is allowed
+    fn check(&mut self, bits: u64, bit_order: Option<BitOrder>) -> Result<(), StreamError> {
+        if self.position.checked_add(bits).is_none_or(|end| end > self.limit) {
+            return Err(StreamError::EndOfData { position: self.position, needed: bits });
+        }
+        if let Some(bit_order) = bit_order {
+            if self.bit_order.is_some_and(|previous| previous != bit_order) && !self.position.is_multiple_of(8) {
+                return Err(StreamError::BitOrderChange { position: self.position });
+            }
+            self.bit_order = Some(bit_order);
+        }
+        Ok(())
+    }
+
+    /// skips `bits` bits
+    pub(crate) fn skip(&mut self, bits: u64) -> Result<(), StreamError> {
+        self.check(bits, None)?;
+        self.position += bits;
+        Ok(())
+    }
+
+    /// reads up to 8 bits, the first bit read being the most significant
+    fn read_group(&mut self, bits: u64, bit_order: BitOrder) -> u64 {
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self.data[(self.position / 8) as usize];
+            value = value << 1 | u64::from(bit(byte, self.position % 8, bit_order));
+            self.position += 1;
+        }
+        value
+    }
+
+    /// reads an unsigned number of `bits` (at most 64) bits
+    pub(crate) fn read_bits(&mut self, bits: u64, bit_order: BitOrder, byte_order: ByteOrder) -> Result<u64, StreamError> {
+        debug_assert!(bits <= 64);
+        self.check(bits, Some(bit_order))?;
+        let mut value = 0;
+        match (bit_order, byte_order) {
+            (BitOrder::MostSignificantBitFirst, ByteOrder::BigEndian) => {
+                let mut left = bits;
+                while left > 0 {
+                    let group = left.min(8);
+                    value = value << group | self.read_group(group, bit_order);
+                    left -= group;
+                }
+            },
+            (BitOrder::MostSignificantBitFirst, ByteOrder::LittleEndian) => {
+                let mut shift = 0;
+                while shift < bits {
+                    let group = (bits - shift).min(8);
+                    value |= self.read_group(group, bit_order) << shift;
+                    shift += group;
+                }
+            },
+            (BitOrder::LeastSignificantBitFirst, _) => {
+                for index in 0..bits {
+                    let byte = self.data[(self.position / 8) as usize];
+                    value |= u64::from(bit(byte, self.position % 8, bit_order)) << index;
+                    self.position += 1;
+                }
+            },
+        }
+        Ok(value)
+    }
+
+    /// reads `count` bytes, borrowing them from the data if the current position is on a byte boundary
+    pub(crate) fn read_bytes(&mut self, count: u64, bit_order: BitOrder) -> Result<Cow<'i, [u8]>, StreamError> {
+        let bits = count.checked_mul(8).ok_or(StreamError::EndOfData { position: self.position, needed: u64::MAX })?;
+        self.check(bits, Some(bit_order))?;
+        if self.position.is_multiple_of(8) {
+            let start = (self.position / 8) as usize;
+            self.position += bits;
+            return Ok(Cow::Borrowed(&self.data[start..start + count as usize]));
+        }
+        Ok(Cow::Owned((0..count).map(|_| self.read_group(8, bit_order) as u8).collect()))
+    }
+
+    /// the whole bytes available after the current position, without consuming them
+    pub(crate) fn peek_bytes(&self, bit_order: BitOrder) -> Cow<'i, [u8]> {
+        self.peek_bytes_at_most(u64::MAX, bit_order)
+    }
+
+    /// the first `max` of the whole bytes available after the current position, without consuming them
+    pub(crate) fn peek_bytes_at_most(&self, max: u64, bit_order: BitOrder) -> Cow<'i, [u8]> {
+        let mut peek = Self { data: self.data, position: self.position, limit: self.limit, bit_order: self.bit_order };
+        let count = ((self.limit - self.position) / 8).min(max);
+        peek.read_bytes(count, bit_order).unwrap_or_default()
+    }
+}
+
+/// Data being unparsed, written at bit granularity, in the same layout as read by [`InputSource`]
+#[derive(Default)]
+pub(crate) struct OutputSink {
+    data: Vec<u8>,
+    /// number of bits written
+    position: u64,
+    /// bit order of the last write
+    bit_order: Option<BitOrder>,
+}
+
+impl OutputSink {
+    pub(crate) fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub(crate) fn mark(&self) -> Mark {
+        Mark { position: self.position, bit_order: self.bit_order }
+    }
+
+    /// discards everything written after `mark`
+    pub(crate) fn reset(&mut self, mark: Mark) {
+        self.data.truncate(mark.position.div_ceil(8) as usize);
+        if let (Some(last), Some(bit_order)) = (self.data.last_mut(), mark.bit_order) {
+            if !mark.position.is_multiple_of(8) {
+                for index in mark.position % 8..8 {
+                    *last = with_bit(*last, index, bit_order, 0);
+                }
+            }
+        }
+        self.position = mark.position;
+        self.bit_order = mark.bit_order;
+    }
+
+    /// the data written, the last byte being padded with 0 bits
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn check(&mut self, bit_order: BitOrder) -> Result<(), StreamError> {
+        if self.bit_order.is_some_and(|previous| previous != bit_order) && !self.position.is_multiple_of(8) {
+            return Err(StreamError::BitOrderChange { position: self.position });
+        }
+        self.bit_order = Some(bit_order);
+        Ok(())
+    }
+
+    fn write_bit(&mut self, value: u8, bit_order: BitOrder) {
+        let index = self.position % 8;
+        if index == 0 {
+            self.data.push(0);
+        }
+        let last = self.data.last_mut().expect("a byte was pushed for the first bit");
+        *last = with_bit(*last, index, bit_order, value);
+        self.position += 1;
+    }
+
+    /// writes the lowest `bits` bits of `value`, the first bit written being the most significant
+    fn write_group(&mut self, value: u64, bits: u64, bit_order: BitOrder) {
+        for index in (0..bits).rev() {
+            self.write_bit(((value >> index) & 1) as u8, bit_order);
+        }
+    }
+
+    /// writes the lowest `bits` (at most 64) bits of `value`
+    pub(crate) fn write_bits(&mut self, value: u64, bits: u64, bit_order: BitOrder, byte_order: ByteOrder) -> Result<(), StreamError> {
+        debug_assert!(bits <= 64);
+        self.check(bit_order)?;
+        match (bit_order, byte_order) {
+            (BitOrder::MostSignificantBitFirst, ByteOrder::BigEndian) => self.write_group(value, bits, bit_order),
+            (BitOrder::MostSignificantBitFirst, ByteOrder::LittleEndian) => {
+                let mut shift = 0;
+                while shift < bits {
+                    let group = (bits - shift).min(8);
+                    self.write_group(value >> shift, group, bit_order);
+                    shift += group;
+                }
+            },
+            (BitOrder::LeastSignificantBitFirst, _) => {
+                for index in 0..bits {
+                    self.write_bit(((value >> index) & 1) as u8, bit_order);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// writes whole bytes
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8], bit_order: BitOrder) -> Result<(), StreamError> {
+        self.check(bit_order)?;
+        if self.position.is_multiple_of(8) {
+            self.data.extend(bytes);
+            self.position += bytes.len() as u64 * 8;
+        }
+        else {
+            for byte in bytes {
+                self.write_group(u64::from(*byte), 8, bit_order);
+            }
+        }
+        Ok(())
+    }
+
+    /// writes `bits` bits taken from the repeated `fill_byte`, each bit at the same position
+    /// within the output byte as within `fill_byte`
+    pub(crate) fn fill(&mut self, bits: u64, fill_byte: u8, bit_order: BitOrder) {
+        for _ in 0..bits {
+            // a partially written byte is completed in the bit order it was started with
+            let bit_order = match self.bit_order {
+                Some(current) if !self.position.is_multiple_of(8) => current,
+                _ => bit_order,
+            };
+            self.write_bit(bit(fill_byte, self.position % 8, bit_order), bit_order);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MSBF: BitOrder = BitOrder::MostSignificantBitFirst;
+    const LSBF: BitOrder = BitOrder::LeastSignificantBitFirst;
+
+    #[test]
+    fn reads_unaligned_fields() {
+        // 3, 5 and 11 bit fields: 101 | 10011 | 01111000011
+        let data = [0b1011_0011, 0b0111_1000, 0b0110_0000];
+        let mut input = InputSource::new(&data);
+        assert_eq!(input.read_bits(3, MSBF, ByteOrder::BigEndian), Ok(0b101));
+        assert_eq!(input.read_bits(5, MSBF, ByteOrder::BigEndian), Ok(0b10011));
+        assert_eq!(input.read_bits(11, MSBF, ByteOrder::BigEndian), Ok(0b011_1100_0011));
+        assert_eq!(input.read_bits(6, MSBF, ByteOrder::BigEndian), Err(StreamError::EndOfData { position: 19, needed: 6 }));
+
+        // the same fields least significant bit first: 3 bits 0b011, 5 bits 0b10110, 11 bits 0b011_0111_1000
+        let mut input = InputSource::new(&data);
+        assert_eq!(input.read_bits(3, LSBF, ByteOrder::LittleEndian), Ok(0b011));
+        assert_eq!(input.read_bits(5, LSBF, ByteOrder::LittleEndian), Ok(0b10110));
+        assert_eq!(input.read_bits(11, LSBF, ByteOrder::LittleEndian), Ok(0b000_0111_1000));
+    }
+
+    #[test]
+    fn changes_bit_order_only_on_byte_boundaries() {
+        let data = [0xF0, 0x0F];
+        let mut input = InputSource::new(&data);
+        assert_eq!(input.read_bits(4, MSBF, ByteOrder::BigEndian), Ok(0xF));
+        assert_eq!(input.read_bits(4, LSBF, ByteOrder::LittleEndian), Err(StreamError::BitOrderChange { position: 4 }));
+        assert_eq!(input.read_bits(4, MSBF, ByteOrder::BigEndian), Ok(0));
+        assert_eq!(input.read_bits(4, LSBF, ByteOrder::LittleEndian), Ok(0xF));
+
+        let mut output = OutputSink::default();
+        output.write_bits(0b101, 3, MSBF, ByteOrder::BigEndian).unwrap();
+        assert_eq!(output.write_bits(1, 1, LSBF, ByteOrder::LittleEndian), Err(StreamError::BitOrderChange { position: 3 }));
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        for (bit_order, byte_order) in [(MSBF, ByteOrder::BigEndian), (MSBF, ByteOrder::LittleEndian), (LSBF, ByteOrder::LittleEndian)] {
+            let fields = [(0b101, 3), (0b10011, 5), (0x4C3, 11), (0xABCDE, 20), (0, 1)];
+            let mut output = OutputSink::default();
+            for (value, bits) in fields {
+                output.write_bits(value, bits, bit_order, byte_order).unwrap();
+            }
+            let data = output.into_bytes();
+            assert_eq!(data.len(), 5);
+            let mut input = InputSource::new(&data);
+            for (value, bits) in fields {
+                assert_eq!(input.read_bits(bits, bit_order, byte_order), Ok(value));
+            }
+        }
+    }
+
+    #[test]
+    fn resets_to_marks() {
+        let mut output = OutputSink::default();
+        output.write_bits(0b11, 2, MSBF, ByteOrder::BigEndian).unwrap();
+        let mark = output.mark();
+        output.write_bytes(&[0xFF, 0xFF], MSBF).unwrap();
+        output.reset(mark);
+        output.fill(6, 0x00, MSBF);
+        assert_eq!(output.into_bytes(), [0b1100_0000]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Processing data according to a [`CompiledSchema`](crate::schema::CompiledSchema)
+//!
+//! [`parse`] reads data into an [`Infoset`](crate::Infoset), [`unparse`] writes an infoset back out.
+
+use thiserror::Error;
+
+use crate::Data;
+use crate::schema::{Alignment, AlignmentUnits, BinaryNumberRep, BitOrder, ByteOrder, Format, LengthKind, LengthUnits, PrimitiveType, Representation, RuntimeValue};
+
+use encoding::Encoding;
+
+mod delimiter;
+mod encoding;
+mod expression;
+mod io;
+mod number;
+mod parse;
+mod path;
+mod unparse;
+
+pub use parse::{parse, ParseError, RecoverableError};
+pub use unparse::{unparse, UnparseError};
+
+
+/// Problems with the format properties a schema component is processed with
+#[derive(Clone, Debug, Error)]
+pub enum FormatError {
+    /// a property needed to process the component is not set
+    #[error("{component} requires the property '{property}'")]
+    MissingProperty {
+        /// name of the property
+        property: &'static str,
+        /// path of the component
+        component: String,
+    },
+    /// the component uses a combination of properties that is not valid or not supported
+    #[error("{component}: {reason}")]
+    Unsupported {
+        /// path of the component
+        component: String,
+        /// description of the problem
+        reason: String,
+    },
+    /// the bit order changes within a byte
+    #[error("{component}: the bit order changes at bit {position}, which is not on a byte boundary")]
+    BitOrderChange {
+        /// path of the component
+        component: String,
+        /// bit position of the change
+        position: u64,
+    },
+}
+
+impl FormatError {
+    pub(crate) fn unsupported(component: &str, reason: impl Into<String>) -> Self {
+        Self::Unsupported { component: component.to_string(), reason: reason.into() }
+    }
+}
+
+/// the value of the property `property` of the component at `component`, which has to be set
+pub(crate) fn required<'f, T>(value: &'f Option<T>, property: &'static str, component: &str) -> Result<&'f T, FormatError> {
+    value.as_ref().ok_or_else(|| FormatError::MissingProperty { property, component: component.to_string() })
+}
+
+/// the value of the runtime-valued property `property`, which has to be set
+pub(crate) fn runtime_value<'f, T>(value: &'f Option<RuntimeValue<T>>, property: &'static str, component: &str) -> Result<&'f T, FormatError> {
+    match required(value, property, component)? {
+        RuntimeValue::Value(value) => Ok(value),
+        RuntimeValue::Expression(expression) => Err(FormatError::unsupported(component,
+            format!("property '{property}' is given by the expression '{{{}}}', which can not be evaluated here", expression.as_str()))),
+    }
+}
+
+/// the length in bits of the `leadingSkip` or `trailingSkip` of a component, which is given in `alignmentUnits`
+pub(crate) fn skip(format: &Format, skip: &Option<u64>, property: &'static str, component: &str) -> Result<u64, FormatError> {
+    let skip = *required(skip, property, component)?;
+    if skip == 0 {
+        return Ok(0);
+    }
+    match required(&format.alignment_units, "alignmentUnits", component)? {
+        AlignmentUnits::Bytes => Ok(skip.saturating_mul(8)),
+        AlignmentUnits::Bits => Ok(skip),
+    }
+}
+
+/// the number of bits in `bytes` bytes, as given by `property`, which has to fit into 64 bits
+fn bytes_to_bits(bytes: u64, property: &str, component: &str) -> Result<u64, FormatError> {
+    bytes.checked_mul(8).ok_or_else(|| FormatError::unsupported(component, format!("{property} {bytes} bytes is too large")))
+}
+
+/// the character set given by `encoding`
+pub(crate) fn encoding(format: &Format, component: &str) -> Result<Encoding, FormatError> {
+    let name = runtime_value(&format.encoding, "encoding", component)?;
+    Encoding::from_name(name).ok_or_else(|| FormatError::unsupported(component, format!("encoding '{name}' is not supported")))
+}
+
+/// the alignment in bits of a component, `primitive` being the type of simple elements
+pub(crate) fn alignment(format: &Format, primitive: Option<PrimitiveType>, component: &str) -> Result<u64, FormatError> {
+    match required(&format.alignment, "alignment", component)? {
+        Alignment::Fixed(alignment) => match required(&format.alignment_units, "alignmentUnits", component)? {
+            AlignmentUnits::Bytes => bytes_to_bits(*alignment, "alignment", component),
+            AlignmentUnits::Bits => Ok(*alignment),
+        },
+        Alignment::Implicit => Ok(match primitive {
+            None => 1,
+            Some(PrimitiveType::String | PrimitiveType::HexBinary) => 8,
+            Some(primitive) => match required(&format.representation, "representation", component)? {
+                Representation::Binary if is_number(primitive) && format.binary_number.binary_number_rep != Some(BinaryNumberRep::Binary) => 8,
+                Representation::Binary => implicit_binary_length(primitive).unwrap_or(8),
+                Representation::Text => 8,
+            },
+        }),
+    }
+}
+
+/// the `bitOrder` of a component
+pub(crate) fn bit_order(format: &Format, component: &str) -> Result<BitOrder, FormatError> {
+    required(&format.bit_order, "bitOrder", component).copied()
+}
+
+/// the `byteOrder` of a binary number of `bits` bits, checked to be compatible with its `bitOrder`
+pub(crate) fn byte_order(format: &Format, bits: u64, component: &str) -> Result<ByteOrder, FormatError> {
+    let byte_order = *runtime_value(&format.byte_order, "byteOrder", component)?;
+    if bits > 8 && byte_order == ByteOrder::BigEndian && bit_order(format, component)? == BitOrder::LeastSignificantBitFirst {
+        return Err(FormatError::unsupported(component, "bitOrder 'leastSignificantBitFirst' requires byteOrder 'littleEndian'"));
+    }
+    Ok(byte_order)
+}
+
+/// the length in bits of a complex element with `lengthKind="explicit"`
+pub(crate) fn explicit_complex_length(format: &Format, component: &str) -> Result<u64, FormatError> {
+    let length = *runtime_value(&format.length.length, "length", component)?;
+    match required(&format.length.length_units, "lengthUnits", component)? {
+        LengthUnits::Bytes => bytes_to_bits(length, "length", component),
+        LengthUnits::Bits => Ok(length),
+        LengthUnits::Characters => Err(FormatError::unsupported(component, "lengthUnits 'characters' is not supported for complex elements yet")),
+    }
+}
+
+/// the length in bits of `primitive` in binary representation with `lengthKind="implicit"`,
+/// `None` for types without an implicit binary length
+pub(crate) fn implicit_binary_length(primitive: PrimitiveType) -> Option<u64> {
+    match primitive {
+        PrimitiveType::Double | PrimitiveType::Long | PrimitiveType::UnsignedLong => Some(64),
+        PrimitiveType::Float | PrimitiveType::Int | PrimitiveType::UnsignedInt => Some(32),
+        PrimitiveType::Short | PrimitiveType::UnsignedShort => Some(16),
+        PrimitiveType::Byte | PrimitiveType::UnsignedByte => Some(8),
+        PrimitiveType::Boolean => Some(32),
+        _ => None,
+    }
+}
+
+/// the length in bits of a binary simple value of type `primitive`, as given by its `lengthKind`
+pub(crate) fn binary_length(format: &Format, primitive: PrimitiveType, component: &str) -> Result<u64, FormatError> {
+    match required(&format.length.length_kind, "lengthKind", component)? {
+        LengthKind::Implicit => implicit_binary_length(primitive)
+            .ok_or_else(|| FormatError::unsupported(component, "this type has no implicit length in binary representation")),
+        LengthKind::Explicit => {
+            let length = *runtime_value(&format.length.length, "length", component)?;
+            match required(&format.length.length_units, "lengthUnits", component)? {
+                LengthUnits::Bytes => bytes_to_bits(length, "length", component),
+                LengthUnits::Bits => Ok(length),
+                LengthUnits::Characters => Err(FormatError::unsupported(component, "lengthUnits 'characters' is not allowed for binary data")),
+            }
+        },
+        other => Err(FormatError::unsupported(component, format!("lengthKind '{}' is not supported yet", other.as_str()))),
+    }
+}
+
+/// whether `primitive` is a decimal or integer type, whose binary representation is given by `binaryNumberRep`
+pub(crate) fn is_number(primitive: PrimitiveType) -> bool {
+    !matches!(primitive, PrimitiveType::Float | PrimitiveType::Double | PrimitiveType::Boolean | PrimitiveType::DateTime
+        | PrimitiveType::Date | PrimitiveType::Time | PrimitiveType::String | PrimitiveType::HexBinary)
+}
+
+/// whether binary numbers of type `primitive` are signed, which `decimalSigned` decides for decimals
+pub(crate) fn is_signed(format: &Format, primitive: PrimitiveType, component: &str) -> Result<bool, FormatError> {
+    match primitive {
+        PrimitiveType::Decimal => Ok(*required(&format.text_number.decimal_signed, "decimalSigned", component)?),
+        _ => Ok(matches!(primitive, PrimitiveType::Integer | PrimitiveType::Long | PrimitiveType::Int | PrimitiveType::Short | PrimitiveType::Byte)),
+    }
+}
+
+/// whether a simple value of type `primitive` is represented as text
+pub(crate) fn is_text(format: &Format, primitive: PrimitiveType, component: &str) -> Result<bool, FormatError> {
+    match primitive {
+        PrimitiveType::String => Ok(true),
+        PrimitiveType::HexBinary => Ok(false),
+        _ => Ok(*required(&format.representation, "representation", component)? == Representation::Text),
+    }
+}
+
+/// the value of integer infoset data
+pub(crate) fn integer_value(data: &Data<'_>) -> Option<i128> {
+    Some(match *data {
+        Data::Integer(value) | Data::Int(value) => value.into(),
+        Data::NonNegativeInteger(value) | Data::UnsignedInt(value) => value.into(),
+        Data::Long(value) => value.into(),
+        Data::Short(value) => value.into(),
+        Data::Byte(value) => value.into(),
+        Data::UnsignedLong(value) => value.into(),
+        Data::UnsignedShort(value) => value.into(),
+        Data::UnsignedByte(value) => value.into(),
+        _ => return None,
+    })
+}
+
+/// the data variant of the integer type `primitive` holding `value`,
+/// `None` if `value` is out of the range of the type
+pub(crate) fn integer_data<'d>(primitive: PrimitiveType, value: i128) -> Option<Data<'d>> {
+    Some(match primitive {
+        PrimitiveType::Integer => Data::Integer(value.try_into().ok()?),
+        PrimitiveType::Long => Data::Long(value.try_into().ok()?),
+        PrimitiveType::Int => Data::Int(value.try_into().ok()?),
+        PrimitiveType::Short => Data::Short(value.try_into().ok()?),
+        PrimitiveType::Byte => Data::Byte(value.try_into().ok()?),
+        PrimitiveType::NonNegativeInteger => Data::NonNegativeInteger(value.try_into().ok()?),
+        PrimitiveType::UnsignedLong => Data::UnsignedLong(value.try_into().ok()?),
+        PrimitiveType::UnsignedInt => Data::UnsignedInt(value.try_into().ok()?),
+        PrimitiveType::UnsignedShort => Data::UnsignedShort(value.try_into().ok()?),
+        PrimitiveType::UnsignedByte => Data::UnsignedByte(value.try_into().ok()?),
+        _ => return None,
+    })
+}
@@ -0,0 +1,1122 @@
+use crate::schema::{BinaryNumberRep, CheckPolicy, DfdlLiteral, Format, PackedSignCodes, TextNumberRounding, TextNumberRoundingMode, TextZonedSignStyle};
+
+use crate::{Data, Decimal};
+
+use super::{integer_value, required, runtime_value, FormatError};
+
+
+/// The most zeros written out after the digits of a number or between them and the decimal point,
+/// which bounds the exponents accepted in text and in `binaryDecimalVirtualPoint`
+pub(crate) const MAX_ZEROS: u32 = 1000;
+
+/// A finite decimal number: `digits` × 10^`exponent`
+///
+/// The digits have no leading or trailing zeros, zero has no digits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Number {
+    pub(crate) negative: bool,
+    pub(crate) digits: Vec<u8>,
+    pub(crate) exponent: i32,
+}
+
+impl Number {
+    pub(crate) fn from_integer(value: i128) -> Self {
+        let digits = value.unsigned_abs().to_string().bytes().map(|digit| digit - b'0').collect();
+        Self { negative: value < 0, digits, exponent: 0 }.normalized()
+    }
+
+    /// the shortest decimal number converting back to the finite `value`
+    pub(crate) fn from_float(value: f64) -> Self {
+        let text = format!("{:e}", value.abs());
+        let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+        let fraction = mantissa.split_once('.').map_or(0, |(_, fraction)| fraction.len() as i32);
+        let digits = mantissa.bytes().filter(u8::is_ascii_digit).map(|digit| digit - b'0').collect();
+        Self { negative: value.is_sign_negative(), digits, exponent: exponent.parse::<i32>().unwrap_or(0) - fraction }.normalized()
+    }
+
+    pub(crate) fn from_decimal(decimal: &Decimal) -> Self {
+        let exponent = i32::try_from(decimal.scale()).map_or(i32::MIN, |scale| -scale);
+        Self { negative: decimal.is_negative(), digits: decimal.digits().to_vec(), exponent }.normalized()
+    }
+
+    /// the number of infoset data, `None` if it is not a number or the text of a decimal
+    pub(crate) fn from_data(data: &Data<'_>) -> Option<Self> {
+        match data {
+            Data::Decimal(decimal) => Some(Self::from_decimal(decimal)),
+            Data::String(text) => text.trim().parse().ok().map(|decimal| Self::from_decimal(&decimal)),
+            Data::Float(value) => Some(f64::from(*value)).filter(|value| value.is_finite()).map(Self::from_float),
+            Data::Double(value) => Some(*value).filter(|value| value.is_finite()).map(Self::from_float),
+            _ => integer_value(data).map(Self::from_integer),
+        }
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// the value as a decimal with at least `scale` fraction digits,
+    /// whose zeros are bounded by the exponents accepted from text and schemas (see [`MAX_ZEROS`])
+    pub(crate) fn to_decimal(&self, scale: u32) -> Decimal {
+        let scale = scale.max(self.exponent.min(0).unsigned_abs());
+        let zeros = (i64::from(self.exponent) + i64::from(scale)) as usize;
+        let mut digits = self.digits.clone();
+        digits.extend(std::iter::repeat_n(0, zeros));
+        Decimal::from_digits(self.negative, digits, scale)
+    }
+
+    /// the value as an integer, `None` if it has a fraction or is out of range
+    pub(crate) fn to_integer(&self) -> Option<i128> {
+        if self.exponent < 0 {
+            return None;
+        }
+        let mut value = self.digits.iter().try_fold(0i128, |value, &digit| value.checked_mul(10)?.checked_add(digit.into()))?;
+        for _ in 0..self.exponent {
+            value = value.checked_mul(10)?;
+        }
+        Some(if self.negative { -value } else { value })
+    }
+
+    /// the floating point number closest to the value
+    pub(crate) fn to_float(&self) -> f64 {
+        let digits: String = self.digits.iter().map(|digit| char::from(b'0' + digit)).collect();
+        let value: f64 = if digits.is_empty() { 0.0 } else { format!("{digits}e{}", self.exponent).parse().unwrap_or(f64::INFINITY) };
+        if self.negative { -value } else { value }
+    }
+
+    /// multiplies the value by 10^`shift`
+    pub(crate) fn shifted(mut self, shift: i32) -> Self {
+        if !self.is_zero() {
+            self.exponent += shift;
+        }
+        self
+    }
+
+    /// rounds the value to `fraction` digits after the decimal point,
+    /// `false` if it needs rounding but `mode` is `roundUnnecessary`
+    pub(crate) fn round(&mut self, fraction: i32, mode: TextNumberRoundingMode) -> bool {
+        let exponent = -fraction;
+        if self.exponent >= exponent {
+            return true;
+        }
+        let dropped = (exponent - self.exponent) as usize;
+        let kept = self.digits.len().saturating_sub(dropped);
+        let first = if dropped <= self.digits.len() { self.digits[kept] } else { 0 };
+        let rest = self.digits[(kept + 1).min(self.digits.len())..].iter().any(|&digit| digit != 0) || dropped > self.digits.len();
+        let odd = kept > 0 && self.digits[kept - 1] % 2 == 1;
+        let up = match mode {
+            TextNumberRoundingMode::Up => true,
+            TextNumberRoundingMode::Down => false,
+            TextNumberRoundingMode::Ceiling => !self.negative,
+            TextNumberRoundingMode::Floor => self.negative,
+            TextNumberRoundingMode::HalfUp => first >= 5,
+            TextNumberRoundingMode::HalfDown => first > 5 || (first == 5 && rest),
+            TextNumberRoundingMode::HalfEven => first > 5 || (first == 5 && (rest || odd)),
+            TextNumberRoundingMode::Unnecessary => return false,
+        };
+        self.digits.truncate(kept);
+        self.exponent = exponent;
+        if up {
+            match self.digits.iter().rposition(|&digit| digit != 9) {
+                Some(position) => {
+                    self.digits[position] += 1;
+                    self.digits.truncate(position + 1);
+                    self.exponent += (kept - position - 1) as i32;
+                },
+                None => {
+                    self.exponent += self.digits.len() as i32;
+                    self.digits = vec![1];
+                },
+            }
+        }
+        *self = std::mem::take(self).normalized();
+        true
+    }
+
+    /// the number without leading and trailing zero digits
+    fn normalized(mut self) -> Self {
+        let leading = self.digits.iter().take_while(|&&digit| digit == 0).count();
+        self.digits.drain(..leading);
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+            self.exponent += 1;
+        }
+        if self.digits.is_empty() {
+            self.negative = false;
+            self.exponent = 0;
+        }
+        self
+    }
+}
+
+/// Where the padding of a `textNumberPattern` goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PadPosition {
+    BeforePrefix,
+    AfterPrefix,
+    BeforeSuffix,
+    AfterSuffix,
+}
+
+/// The texts around the digits of a number
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Affixes {
+    prefix: String,
+    suffix: String,
+}
+
+/// A parsed ICU style `textNumberPattern`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Pattern {
+    positive: Affixes,
+    /// the affixes of the negative subpattern, if there is one
+    negative: Option<Affixes>,
+    min_integer: usize,
+    min_fraction: usize,
+    max_fraction: usize,
+    /// whether the pattern has a decimal separator
+    decimal: bool,
+    /// the primary and secondary grouping sizes
+    grouping: Option<(usize, usize)>,
+    /// the minimum number of exponent digits, and whether positive exponents get a plus sign
+    exponent: Option<(usize, bool)>,
+    /// the power of ten the value is multiplied with for display, 2 for percent and 3 for per mille
+    multiplier: i32,
+    padding: Option<(char, PadPosition)>,
+    /// the number of characters the padding fills the number up to
+    width: usize,
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Self, String> {
+        let (positive, negative) = split_subpatterns(pattern);
+        let mut parsed = Self::parse_subpattern(positive)?;
+        if let Some(negative) = negative {
+            parsed.negative = Some(Self::parse_subpattern(negative)?.positive);
+        }
+        Ok(parsed)
+    }
+
+    fn parse_subpattern(pattern: &str) -> Result<Self, String> {
+        #[derive(PartialEq)]
+        enum Part { Prefix, Integer, Fraction, Exponent, Suffix }
+        let mut parsed = Self::default();
+        let mut part = Part::Prefix;
+        // the number of digits since the last two grouping separators
+        let mut groups: Vec<usize> = Vec::new();
+        let mut integer = 0;
+        let mut width = 0;
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    let mut quoted = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\'') if chars.peek() == Some(&'\'') => { chars.next(); quoted.push('\''); },
+                            Some('\'') => break,
+                            Some(c) => quoted.push(c),
+                            None => return Err("a quote is not closed".to_string()),
+                        }
+                    }
+                    if quoted.is_empty() {
+                        quoted.push('\'');
+                    }
+                    if part != Part::Prefix {
+                        part = Part::Suffix;
+                    }
+                    let affix = if part == Part::Prefix { &mut parsed.positive.prefix } else { &mut parsed.positive.suffix };
+                    width += quoted.chars().count();
+                    affix.push_str(&quoted);
+                },
+                '*' => {
+                    let pad = chars.next().ok_or("'*' has to be followed by the pad character")?;
+                    if parsed.padding.is_some() {
+                        return Err("only one pad character is allowed".to_string());
+                    }
+                    let position = match part {
+                        Part::Prefix if parsed.positive.prefix.is_empty() => PadPosition::BeforePrefix,
+                        Part::Prefix => PadPosition::AfterPrefix,
+                        Part::Suffix if !parsed.positive.suffix.is_empty() => PadPosition::AfterSuffix,
+                        _ if chars.peek().is_none() => PadPosition::AfterSuffix,
+                        _ => PadPosition::BeforeSuffix,
+                    };
+                    parsed.padding = Some((pad, position));
+                },
+                '#' | '0' if matches!(part, Part::Prefix | Part::Integer) => {
+                    part = Part::Integer;
+                    if c == '0' {
+                        parsed.min_integer += 1;
+                    }
+                    else if parsed.min_integer > 0 {
+                        return Err("'#' can not follow '0' in the integer part".to_string());
+                    }
+                    integer += 1;
+                    if let Some(group) = groups.last_mut() {
+                        *group += 1;
+                    }
+                    width += 1;
+                },
+                ',' if matches!(part, Part::Prefix | Part::Integer) => {
+                    part = Part::Integer;
+                    groups.push(0);
+                    width += 1;
+                },
+                '.' if matches!(part, Part::Prefix | Part::Integer) => {
+                    part = Part::Fraction;
+                    parsed.decimal = true;
+                    width += 1;
+                },
+                '0' if part == Part::Fraction => {
+                    if parsed.max_fraction > parsed.min_fraction {
+                        return Err("'0' can not follow '#' in the fraction part".to_string());
+                    }
+                    parsed.min_fraction += 1;
+                    parsed.max_fraction += 1;
+                    width += 1;
+                },
+                '#' if part == Part::Fraction => {
+                    parsed.max_fraction += 1;
+                    width += 1;
+                },
+                'E' if matches!(part, Part::Integer | Part::Fraction) => {
+                    part = Part::Exponent;
+                    let plus = chars.next_if_eq(&'+').is_some();
+                    let mut digits = 0;
+                    while chars.next_if_eq(&'0').is_some() {
+                        digits += 1;
+                    }
+                    if digits == 0 {
+                        return Err("the exponent needs at least one '0'".to_string());
+                    }
+                    parsed.exponent = Some((digits, plus));
+                    width += 1 + usize::from(plus) + digits;
+                },
+                '1'..='9' | '@' => return Err(format!("'{c}' is not supported")),
+                ';' => return Err("there can be at most two subpatterns".to_string()),
+                c => {
+                    if part != Part::Prefix {
+                        part = Part::Suffix;
+                    }
+                    match c {
+                        '%' => parsed.multiplier = 2,
+                        '\u{2030}' => parsed.multiplier = 3,
+                        _ => {},
+                    }
+                    let affix = if part == Part::Prefix { &mut parsed.positive.prefix } else { &mut parsed.positive.suffix };
+                    affix.push(c);
+                    width += 1;
+                },
+            }
+        }
+        if integer == 0 && parsed.max_fraction == 0 {
+            return Err("there are no digits".to_string());
+        }
+        if parsed.exponent.is_some() && !groups.is_empty() {
+            return Err("grouping separators can not be combined with an exponent".to_string());
+        }
+        parsed.grouping = match groups[..] {
+            [] => None,
+            [.., 0] => return Err("a grouping separator has to be followed by a digit".to_string()),
+            [primary] => Some((primary, primary)),
+            [.., secondary, primary] => Some((primary, if secondary == 0 { primary } else { secondary })),
+        };
+        parsed.width = width;
+        Ok(parsed)
+    }
+
+    /// the affixes of negative numbers
+    fn negative(&self) -> Affixes {
+        self.negative.clone().unwrap_or_else(|| Affixes { prefix: format!("-{}", self.positive.prefix), suffix: self.positive.suffix.clone() })
+    }
+}
+
+/// the positive and the optional negative subpattern of `pattern`, which are separated by an unquoted ';'
+fn split_subpatterns(pattern: &str) -> (&str, Option<&str>) {
+    let mut quoted = false;
+    for (index, c) in pattern.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ';' if !quoted => return (&pattern[..index], Some(&pattern[index + 1..])),
+            _ => {},
+        }
+    }
+    (pattern, None)
+}
+
+/// Numbers in standard text representation, as given by `textNumberPattern` and the `textStandard*` properties
+pub(crate) struct TextNumberFormat<'f> {
+    format: &'f Format,
+    path: &'f str,
+    pattern: Pattern,
+    base: u32,
+}
+
+impl<'f> TextNumberFormat<'f> {
+    pub(crate) fn new(format: &'f Format, path: &'f str) -> Result<Self, FormatError> {
+        let base = *required(&format.text_number.text_standard_base, "textStandardBase", path)?;
+        // the pattern only applies to decimal numbers
+        let pattern = if base == 10 {
+            let pattern = required(&format.text_number.text_number_pattern, "textNumberPattern", path)?;
+            Pattern::parse(pattern).map_err(|reason| FormatError::unsupported(path, format!("textNumberPattern '{pattern}': {reason}")))?
+        }
+        else { Pattern::default() };
+        Ok(Self { format, path, pattern, base })
+    }
+
+    /// parses an integer, `None` if `text` is not one
+    pub(crate) fn parse_integer(&self, text: &str) -> Result<Option<i128>, FormatError> {
+        if self.base != 10 {
+            let (negative, digits) = text.strip_prefix('-').map_or((false, text), |digits| (true, digits));
+            if digits.starts_with(['+', '-']) {
+                return Ok(None);
+            }
+            return Ok(i128::from_str_radix(digits, self.base).ok().map(|value| if negative { -value } else { value }));
+        }
+        Ok(self.parse_number(text)?.and_then(|number| number.to_integer()))
+    }
+
+    /// parses a floating point number, `None` if `text` is not one
+    pub(crate) fn parse_float(&self, text: &str) -> Result<Option<f64>, FormatError> {
+        self.decimal_only("floating point numbers")?;
+        if self.literal(&self.format.text_number.text_standard_nan_rep, "textStandardNaNRep")?.is_some_and(|nan| nan == text) {
+            return Ok(Some(f64::NAN));
+        }
+        let infinity = self.literal(&self.format.text_number.text_standard_infinity_rep, "textStandardInfinityRep")?;
+        for (negative, body) in self.candidates(text)? {
+            if infinity.as_deref() == Some(body) {
+                return Ok(Some(if negative { f64::NEG_INFINITY } else { f64::INFINITY }));
+            }
+        }
+        Ok(self.parse_number(text)?.map(|number| number.to_float()))
+    }
+
+    /// parses a decimal number, `None` if `text` is not one
+    pub(crate) fn parse_number(&self, text: &str) -> Result<Option<Number>, FormatError> {
+        Ok(self.parse_scaled(text)?.map(|(number, _)| number))
+    }
+
+    /// parses a decimal, which keeps the number of fraction digits in `text`, `None` if `text` is not one
+    pub(crate) fn parse_decimal(&self, text: &str) -> Result<Option<Decimal>, FormatError> {
+        Ok(self.parse_scaled(text)?.map(|(number, scale)| number.to_decimal(scale)))
+    }
+
+    /// parses a decimal number along with the number of fraction digits it is written with
+    fn parse_scaled(&self, text: &str) -> Result<Option<(Number, u32)>, FormatError> {
+        self.decimal_only("decimal numbers")?;
+        let text = if self.lax()? { text.trim() } else { text };
+        if self.zero_reps()?.iter().any(|zero| zero == text) {
+            return Ok(Some((Number::default(), 0)));
+        }
+        for (negative, body) in self.candidates(text)? {
+            if let Some((mut number, scale)) = self.parse_digits(body)? {
+                number.negative = negative && !number.is_zero();
+                let scale = scale.saturating_add(self.pattern.multiplier).max(0).unsigned_abs();
+                return Ok(Some((number.shifted(-self.pattern.multiplier), scale)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// the text of an integer
+    pub(crate) fn format_integer(&self, value: i128) -> Result<String, FormatError> {
+        let sign = if value < 0 { "-" } else { "" };
+        let magnitude = value.unsigned_abs();
+        match self.base {
+            2 => Ok(format!("{sign}{magnitude:b}")),
+            8 => Ok(format!("{sign}{magnitude:o}")),
+            16 => Ok(format!("{sign}{magnitude:x}")),
+            _ => self.format_number(Number::from_integer(value)),
+        }
+    }
+
+    /// the text of a floating point number
+    pub(crate) fn format_float(&self, value: f64) -> Result<String, FormatError> {
+        self.decimal_only("floating point numbers")?;
+        let rep = |literal: &Option<DfdlLiteral>, property| self.literal(literal, property)?
+            .ok_or_else(|| FormatError::unsupported(self.path, format!("{property} must be plain text")));
+        if value.is_nan() {
+            return rep(&self.format.text_number.text_standard_nan_rep, "textStandardNaNRep");
+        }
+        if value.is_infinite() {
+            let infinity = rep(&self.format.text_number.text_standard_infinity_rep, "textStandardInfinityRep")?;
+            let affixes = if value < 0.0 { self.pattern.negative() } else { self.pattern.positive.clone() };
+            return Ok(format!("{}{infinity}{}", affixes.prefix, affixes.suffix));
+        }
+        self.format_number(Number::from_float(value))
+    }
+
+    /// the text of a decimal number, rounded as given by `textNumberRounding`
+    pub(crate) fn format_number(&self, number: Number) -> Result<String, FormatError> {
+        self.decimal_only("decimal numbers")?;
+        let pattern = &self.pattern;
+        let mut number = number.shifted(pattern.multiplier);
+        let text_number = &self.format.text_number;
+        let mut mode = rounding_mode(self.format, self.path)?;
+        if text_number.text_number_rounding == Some(TextNumberRounding::Explicit) {
+            let increment = *required(&text_number.text_number_rounding_increment, "textNumberRoundingIncrement", self.path)?;
+            if increment > 0.0 {
+                number = self.round_to_increment(&number, increment, mode)?;
+                // only float noise is left to round away
+                mode = TextNumberRoundingMode::HalfEven;
+            }
+        }
+        if number.is_zero() {
+            if let Some(zero) = self.zero_reps()?.first() {
+                return Ok(zero.clone());
+            }
+        }
+        let (integer, fraction, exponent) = match pattern.exponent {
+            Some(_) => {
+                let integer_digits = pattern.min_integer.max(1) as i32;
+                let mut exponent = if number.is_zero() { 0 } else { number.digits.len() as i32 + number.exponent - integer_digits };
+                let mut scaled = number.clone().shifted(-exponent);
+                self.round(&mut scaled, pattern.max_fraction, mode)?;
+                if !scaled.is_zero() && scaled.digits.len() as i32 + scaled.exponent > integer_digits {
+                    // rounding carried into another integer digit
+                    exponent += 1;
+                    scaled = scaled.shifted(-1);
+                    self.round(&mut scaled, pattern.max_fraction, mode)?;
+                }
+                let (integer, fraction) = split_digits(&scaled).ok_or_else(|| too_many_digits(self.path))?;
+                (integer, fraction, Some(exponent))
+            },
+            None => {
+                self.round(&mut number, pattern.max_fraction, mode)?;
+                let (integer, fraction) = split_digits(&number).ok_or_else(|| too_many_digits(self.path))?;
+                (integer, fraction, None)
+            },
+        };
+        let mut body = String::new();
+        let fraction = format!("{fraction:0<width$}", width = pattern.min_fraction);
+        let integer = format!("{integer:0>width$}", width = pattern.min_integer);
+        let integer = if integer.is_empty() && fraction.is_empty() { "0".to_string() } else { integer };
+        match pattern.grouping {
+            Some((primary, secondary)) if primary > 0 => {
+                let separator = self.grouping_separator()?.unwrap_or_default();
+                let mut groups = Vec::new();
+                let mut rest = integer.as_str();
+                let mut size = primary;
+                while rest.len() > size {
+                    let (head, group) = rest.split_at(rest.len() - size);
+                    groups.push(group);
+                    rest = head;
+                    size = secondary;
+                }
+                groups.push(rest);
+                groups.reverse();
+                body.push_str(&groups.join(&separator));
+            },
+            _ => body.push_str(&integer),
+        }
+        if !fraction.is_empty() || (pattern.decimal && pattern.max_fraction == 0) {
+            body.push_str(&self.decimal_separator()?);
+            body.push_str(&fraction);
+        }
+        if let Some(exponent) = exponent {
+            let (digits, plus) = pattern.exponent.unwrap_or_default();
+            body.push_str(&self.exponent_rep()?.unwrap_or_default());
+            body.push_str(if exponent < 0 { "-" } else if plus { "+" } else { "" });
+            body.push_str(&format!("{:0>digits$}", exponent.unsigned_abs()));
+        }
+        let affixes = if number.negative { pattern.negative() } else { pattern.positive.clone() };
+        let mut text = String::new();
+        let padding = match pattern.padding {
+            Some((pad, position)) => {
+                let length = affixes.prefix.chars().count() + body.chars().count() + affixes.suffix.chars().count();
+                Some((std::iter::repeat_n(pad, pattern.width.saturating_sub(length)).collect::<String>(), position))
+            },
+            None => None,
+        };
+        let pad = |position| padding.as_ref().filter(|(_, at)| *at == position).map_or("", |(pad, _)| pad.as_str());
+        for part in [pad(PadPosition::BeforePrefix), &affixes.prefix, pad(PadPosition::AfterPrefix), &body,
+            pad(PadPosition::BeforeSuffix), &affixes.suffix, pad(PadPosition::AfterSuffix)] {
+            text.push_str(part);
+        }
+        Ok(text)
+    }
+
+    /// the signs and the texts of the digits `text` can be read as, trying the negative affixes first
+    fn candidates<'t>(&self, text: &'t str) -> Result<Vec<(bool, &'t str)>, FormatError> {
+        let pad = self.pattern.padding;
+        let trim = |text: &'t str, position: PadPosition, start: bool| match pad {
+            Some((pad, at)) if at == position => if start { text.trim_start_matches(pad) } else { text.trim_end_matches(pad) },
+            _ => text,
+        };
+        let text = trim(trim(text, PadPosition::BeforePrefix, true), PadPosition::AfterSuffix, false);
+        let mut candidates = Vec::new();
+        for (negative, affixes) in [(true, self.pattern.negative()), (false, self.pattern.positive.clone())] {
+            let body = text.strip_prefix(affixes.prefix.as_str()).and_then(|body| body.strip_suffix(affixes.suffix.as_str()));
+            if let Some(body) = body {
+                candidates.push((negative, trim(trim(body, PadPosition::AfterPrefix, true), PadPosition::BeforeSuffix, false)));
+            }
+        }
+        if self.lax()? {
+            // a plain sign is accepted in place of the affixes
+            if let Some(body) = text.strip_prefix('-') {
+                candidates.push((true, body.trim_start()));
+            }
+            candidates.push((false, text.strip_prefix('+').unwrap_or(text).trim_start()));
+        }
+        Ok(candidates)
+    }
+
+    /// reads the digits, separators and the exponent of a number without sign,
+    /// along with the number of fraction digits it is written with
+    fn parse_digits(&self, text: &str) -> Result<Option<(Number, i32)>, FormatError> {
+        let lax = self.lax()?;
+        let grouping = self.grouping_separator()?.filter(|separator| !separator.is_empty());
+        let mut number = Number::default();
+        let mut rest = text;
+        let mut groups = vec![0];
+        loop {
+            if let Some(digit) = rest.bytes().next().filter(u8::is_ascii_digit) {
+                number.digits.push(digit - b'0');
+                if let Some(group) = groups.last_mut() {
+                    *group += 1;
+                }
+                rest = &rest[1..];
+            }
+            else if let Some(after) = grouping.as_deref().and_then(|separator| rest.strip_prefix(separator)).filter(|_| !number.digits.is_empty()) {
+                groups.push(0);
+                rest = after;
+            }
+            else {
+                break;
+            }
+        }
+        if !lax && groups.len() > 1 {
+            let Some((primary, secondary)) = self.pattern.grouping else { return Ok(None) };
+            let (first, rest) = groups.split_first().unwrap_or((&0, &[]));
+            let (last, middle) = rest.split_last().unwrap_or((&0, &[]));
+            let first_max = if groups.len() > 2 { secondary } else { primary };
+            if *last != primary || middle.iter().any(|&group| group != secondary) || *first == 0 || *first > first_max {
+                return Ok(None);
+            }
+        }
+        let integer = number.digits.len();
+        for separator in self.decimal_separators()? {
+            if let Some(after) = rest.strip_prefix(separator.as_str()).filter(|_| !separator.is_empty()) {
+                rest = after;
+                while let Some(digit) = rest.bytes().next().filter(u8::is_ascii_digit) {
+                    number.digits.push(digit - b'0');
+                    rest = &rest[1..];
+                }
+                break;
+            }
+        }
+        if number.digits.is_empty() {
+            return Ok(None);
+        }
+        let mut exponent: i32 = 0;
+        if !rest.is_empty() {
+            if !lax && self.pattern.exponent.is_none() {
+                return Ok(None);
+            }
+            let Some(rep) = self.exponent_rep()?.filter(|rep| !rep.is_empty()) else { return Ok(None) };
+            let matches = rest.get(..rep.len()).is_some_and(|start| if lax { start.to_lowercase() == rep.to_lowercase() } else { start == rep });
+            if !matches {
+                return Ok(None);
+            }
+            let digits = &rest[rep.len()..];
+            let unsigned = digits.strip_prefix(['+', '-']).unwrap_or(digits);
+            if unsigned.is_empty() || !unsigned.bytes().all(|digit| digit.is_ascii_digit()) {
+                return Ok(None);
+            }
+            let Ok(value) = digits.strip_prefix('+').unwrap_or(digits).parse::<i32>() else { return Ok(None) };
+            if value.unsigned_abs() > MAX_ZEROS {
+                return Ok(None);
+            }
+            exponent = value;
+            rest = "";
+        }
+        if !rest.is_empty() {
+            return Ok(None);
+        }
+        number.exponent = exponent.saturating_sub((number.digits.len() - integer) as i32);
+        let scale = number.exponent.saturating_neg();
+        Ok(Some((number.normalized(), scale)))
+    }
+
+    /// rounds `number` to a multiple of `increment`
+    fn round_to_increment(&self, number: &Number, increment: f64, mode: TextNumberRoundingMode) -> Result<Number, FormatError> {
+        let quotient = number.to_float() / increment;
+        let rounded = match mode {
+            TextNumberRoundingMode::Ceiling => quotient.ceil(),
+            TextNumberRoundingMode::Floor => quotient.floor(),
+            TextNumberRoundingMode::Down => quotient.trunc(),
+            TextNumberRoundingMode::Up => if quotient < 0.0 { quotient.floor() } else { quotient.ceil() },
+            TextNumberRoundingMode::HalfEven => quotient.round_ties_even(),
+            TextNumberRoundingMode::HalfUp => quotient.round(),
+            TextNumberRoundingMode::HalfDown => if (quotient - quotient.trunc()).abs() > 0.5 { quotient.round() } else { quotient.trunc() },
+            TextNumberRoundingMode::Unnecessary if quotient.fract() == 0.0 => quotient,
+            TextNumberRoundingMode::Unnecessary => return Err(self.rounding_needed()),
+        };
+        Ok(Number::from_float(rounded * increment))
+    }
+
+    /// rounds `number` to `fraction` digits after the decimal point
+    fn round(&self, number: &mut Number, fraction: usize, mode: TextNumberRoundingMode) -> Result<(), FormatError> {
+        if number.round(fraction as i32, mode) { Ok(()) } else { Err(self.rounding_needed()) }
+    }
+
+    fn rounding_needed(&self) -> FormatError {
+        FormatError::unsupported(self.path, "the value needs rounding, but textNumberRoundingMode is 'roundUnnecessary'")
+    }
+
+    /// rejects a `textStandardBase` other than 10 for numbers with a fraction
+    fn decimal_only(&self, numbers: &str) -> Result<(), FormatError> {
+        if self.base == 10 { Ok(()) } else { Err(FormatError::unsupported(self.path, format!("{numbers} require a textStandardBase of 10"))) }
+    }
+
+    fn lax(&self) -> Result<bool, FormatError> {
+        Ok(*required(&self.format.text_number.text_number_check_policy, "textNumberCheckPolicy", self.path)? == CheckPolicy::Lax)
+    }
+
+    /// the text of a literal property, `None` if it is not plain text
+    fn literal(&self, literal: &Option<DfdlLiteral>, property: &'static str) -> Result<Option<String>, FormatError> {
+        required(literal, property, self.path).map(DfdlLiteral::as_text)
+    }
+
+    fn zero_reps(&self) -> Result<Vec<String>, FormatError> {
+        Ok(self.format.text_number.text_standard_zero_rep.iter().flatten().filter_map(DfdlLiteral::as_text).filter(|zero| !zero.is_empty()).collect())
+    }
+
+    /// the decimal separators, the first of which is written when unparsing
+    fn decimal_separators(&self) -> Result<Vec<String>, FormatError> {
+        let separators = &self.format.text_number.text_standard_decimal_separator;
+        if separators.is_none() && !self.pattern.decimal {
+            return Ok(Vec::new());
+        }
+        Ok(runtime_value(separators, "textStandardDecimalSeparator", self.path)?.iter().filter_map(DfdlLiteral::as_text).collect())
+    }
+
+    fn decimal_separator(&self) -> Result<String, FormatError> {
+        self.decimal_separators()?.into_iter().next()
+            .ok_or_else(|| FormatError::unsupported(self.path, "textStandardDecimalSeparator must start with a plain text separator"))
+    }
+
+    fn grouping_separator(&self) -> Result<Option<String>, FormatError> {
+        let separator = &self.format.text_number.text_standard_grouping_separator;
+        if separator.is_none() && self.pattern.grouping.is_none() {
+            return Ok(None);
+        }
+        Ok(runtime_value(separator, "textStandardGroupingSeparator", self.path)?.as_text())
+    }
+
+    fn exponent_rep(&self) -> Result<Option<String>, FormatError> {
+        let rep = &self.format.text_number.text_standard_exponent_rep;
+        if rep.is_none() && self.pattern.exponent.is_none() {
+            return Ok(None);
+        }
+        Ok(runtime_value(rep, "textStandardExponentRep", self.path)?.as_text())
+    }
+}
+
+/// the rounding mode given by `textNumberRounding`
+fn rounding_mode(format: &Format, path: &str) -> Result<TextNumberRoundingMode, FormatError> {
+    Ok(match required(&format.text_number.text_number_rounding, "textNumberRounding", path)? {
+        TextNumberRounding::Pattern => TextNumberRoundingMode::HalfEven,
+        TextNumberRounding::Explicit => *required(&format.text_number.text_number_rounding_mode, "textNumberRoundingMode", path)?,
+    })
+}
+
+/// Which digit of a zoned number carries the sign
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SignDigit {
+    First,
+    Last,
+}
+
+/// Numbers in zoned text representation, where the sign is overpunched into the first or last digit
+///
+/// The `textNumberPattern` of a zoned number consists of the digits `0` and `#`, a `V` for the virtual decimal point,
+/// `P` for scaling positions at either end, and a `+` at the start or the end for the digit that carries the sign.
+/// Without a `+` the number is unsigned.
+pub(crate) struct ZonedFormat<'f> {
+    format: &'f Format,
+    path: &'f str,
+    sign: Option<(SignDigit, TextZonedSignStyle)>,
+    /// the number of digits written at least
+    min_digits: usize,
+    /// the power of ten the digits are multiplied with
+    exponent: i32,
+}
+
+impl<'f> ZonedFormat<'f> {
+    pub(crate) fn new(format: &'f Format, path: &'f str) -> Result<Self, FormatError> {
+        let pattern = required(&format.text_number.text_number_pattern, "textNumberPattern", path)?;
+        let invalid = |reason: &str| FormatError::unsupported(path, format!("zoned textNumberPattern '{pattern}': {reason}"));
+        let (sign, digits) = if let Some(digits) = pattern.strip_prefix('+') { (Some(SignDigit::First), digits) }
+            else if let Some(digits) = pattern.strip_suffix('+') { (Some(SignDigit::Last), digits) }
+            else { (None, pattern.as_str()) };
+        let sign = match sign {
+            Some(digit) => Some((digit, *required(&format.text_number.text_zoned_sign_style, "textZonedSignStyle", path)?)),
+            None => None,
+        };
+        let leading = digits.len() - digits.trim_start_matches('P').len();
+        let trailing = digits.len() - digits.trim_end_matches('P').len();
+        let digits = &digits[leading..digits.len() - trailing];
+        if digits.is_empty() || digits.chars().any(|c| !matches!(c, '0' | '#' | 'V')) {
+            return Err(invalid("only '+', '0', '#', 'V' and 'P' are allowed"));
+        }
+        let (integer, fraction) = match digits.split_once('V') {
+            Some((_, fraction)) if fraction.contains('V') => return Err(invalid("there can be at most one 'V'")),
+            Some((_, _)) if leading > 0 || trailing > 0 => return Err(invalid("'V' and 'P' can not be combined")),
+            Some((integer, fraction)) => (integer, fraction),
+            None => (digits, ""),
+        };
+        if leading > 0 && trailing > 0 {
+            return Err(invalid("'P' can only be at one end of the digits"));
+        }
+        let fraction = if leading > 0 { integer.len() + leading } else { fraction.len() };
+        Ok(Self {
+            format,
+            path,
+            sign,
+            min_digits: digits.chars().filter(|&c| c == '0').count(),
+            exponent: trailing as i32 - fraction as i32,
+        })
+    }
+
+    /// the number of fraction digits of the values
+    pub(crate) fn scale(&self) -> u32 {
+        self.exponent.min(0).unsigned_abs()
+    }
+
+    /// parses a zoned number, `None` if `text` is not one
+    pub(crate) fn parse(&self, text: &str) -> Option<Number> {
+        let mut chars: Vec<char> = text.chars().collect();
+        let mut negative = false;
+        if let Some((digit, style)) = self.sign {
+            let index = match digit {
+                SignDigit::First => 0,
+                SignDigit::Last => chars.len().checked_sub(1)?,
+            };
+            let (value, sign) = unpunch(*chars.get(index)?, style)?;
+            chars[index] = char::from(b'0' + value);
+            negative = sign;
+        }
+        if chars.is_empty() || !chars.iter().all(char::is_ascii_digit) {
+            return None;
+        }
+        let digits = chars.iter().map(|&c| c as u8 - b'0').collect();
+        let mut number = Number { negative, digits, exponent: self.exponent }.normalized();
+        number.negative = negative && !number.is_zero();
+        Some(number)
+    }
+
+    /// the text of a zoned number, rounded as given by `textNumberRounding` to the digits of the pattern
+    pub(crate) fn format(&self, number: Number) -> Result<String, FormatError> {
+        let mut number = number.shifted(-self.exponent);
+        if !number.round(0, rounding_mode(self.format, self.path)?) {
+            return Err(FormatError::unsupported(self.path, "the value needs rounding, but textNumberRoundingMode is 'roundUnnecessary'"));
+        }
+        let (digits, _) = split_digits(&number).ok_or_else(|| too_many_digits(self.path))?;
+        let mut chars: Vec<char> = format!("{digits:0>width$}", width = self.min_digits.max(1)).chars().collect();
+        match self.sign {
+            Some((digit, style)) => {
+                let index = if digit == SignDigit::First { 0 } else { chars.len() - 1 };
+                chars[index] = overpunch(chars[index] as u8 - b'0', number.negative, style);
+            },
+            None if number.negative => return Err(FormatError::unsupported(self.path, "negative values need a '+' in the zoned textNumberPattern")),
+            None => {},
+        }
+        Ok(chars.into_iter().collect())
+    }
+}
+
+/// the character for `digit` with the sign overpunched in `style`
+fn overpunch(digit: u8, negative: bool, style: TextZonedSignStyle) -> char {
+    match (style, negative) {
+        (TextZonedSignStyle::AsciiTranslatedEbcdic, false) => if digit == 0 { '{' } else { char::from(b'A' + digit - 1) },
+        (TextZonedSignStyle::AsciiTranslatedEbcdic, true) => if digit == 0 { '}' } else { char::from(b'J' + digit - 1) },
+        (_, false) => char::from(b'0' + digit),
+        (TextZonedSignStyle::AsciiStandard, true) => char::from(b'p' + digit),
+        (TextZonedSignStyle::AsciiCaRealiaModified, true) => char::from(b' ' + digit),
+        (TextZonedSignStyle::AsciiTandemModified, true) => char::from(0x80 + digit),
+    }
+}
+
+/// the digit and whether the number is negative, for a character with the sign overpunched in `style`
+fn unpunch(c: char, style: TextZonedSignStyle) -> Option<(u8, bool)> {
+    if c.is_ascii_digit() {
+        return Some((c as u8 - b'0', false));
+    }
+    let (positive, negative): (&[char], &[char]) = match style {
+        TextZonedSignStyle::AsciiStandard => (&[], &['p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y']),
+        TextZonedSignStyle::AsciiTranslatedEbcdic => (&['{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I'], &['}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R']),
+        TextZonedSignStyle::AsciiCaRealiaModified => (&[], &[' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')']),
+        TextZonedSignStyle::AsciiTandemModified => (&[], &['\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{85}', '\u{86}', '\u{87}', '\u{88}', '\u{89}']),
+    };
+    if let Some(digit) = positive.iter().position(|&p| p == c) {
+        return Some((digit as u8, false));
+    }
+    negative.iter().position(|&n| n == c).map(|digit| (digit as u8, true))
+}
+
+/// Binary coded decimal numbers, as given by `binaryNumberRep`, `binaryPackedSignCodes` and `binaryDecimalVirtualPoint`
+///
+/// Each nibble holds a decimal digit. `packed` numbers end with a sign nibble, `bcd` numbers are unsigned,
+/// and negative `ibm4690Packed` numbers start with a `D` nibble.
+pub(crate) struct PackedFormat {
+    rep: BinaryNumberRep,
+    codes: Option<PackedSignCodes>,
+    /// the number of digits after the decimal point, negative for scaled numbers
+    virtual_point: i32,
+    lax: bool,
+}
+
+impl PackedFormat {
+    pub(crate) fn new(format: &Format, rep: BinaryNumberRep, path: &str) -> Result<Self, FormatError> {
+        let codes = match rep {
+            BinaryNumberRep::Packed => Some(*required(&format.binary_number.binary_packed_sign_codes, "binaryPackedSignCodes", path)?),
+            _ => None,
+        };
+        let virtual_point = virtual_point(format, path)?;
+        let lax = *required(&format.binary_number.binary_number_check_policy, "binaryNumberCheckPolicy", path)? == CheckPolicy::Lax;
+        Ok(Self { rep, codes, virtual_point, lax })
+    }
+
+    /// the number of fraction digits of the values
+    pub(crate) fn scale(&self) -> u32 {
+        self.virtual_point.max(0).unsigned_abs()
+    }
+
+    /// the number in `bytes`, `None` if they do not hold one
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Option<Number> {
+        let mut nibbles: Vec<u8> = bytes.iter().flat_map(|byte| [byte >> 4, byte & 0xF]).collect();
+        let negative = match self.rep {
+            BinaryNumberRep::Packed => {
+                let sign = nibbles.pop()?;
+                let codes = self.codes?;
+                if self.lax {
+                    match sign {
+                        0xB | 0xD => true,
+                        0xA | 0xC | 0xE | 0xF => false,
+                        _ => return None,
+                    }
+                }
+                else if sign == codes.negative {
+                    true
+                }
+                else if [codes.positive, codes.unsigned, codes.zero].contains(&sign) {
+                    false
+                }
+                else {
+                    return None;
+                }
+            },
+            BinaryNumberRep::Ibm4690Packed if nibbles.first() == Some(&0xD) => {
+                nibbles.remove(0);
+                true
+            },
+            _ => false,
+        };
+        if nibbles.iter().any(|&nibble| nibble > 9) {
+            return None;
+        }
+        Some(Number { negative, digits: nibbles, exponent: -self.virtual_point }.normalized())
+    }
+
+    /// the representation of `number` in `length` bytes, or in as few bytes as possible without a length
+    pub(crate) fn encode(&self, number: Number, unsigned: bool, length: Option<usize>) -> Result<Vec<u8>, String> {
+        let mut scaled = number.shifted(self.virtual_point);
+        if !scaled.round(0, TextNumberRoundingMode::Unnecessary) {
+            return Err(format!("the value has more fraction digits than binaryDecimalVirtualPoint allows ({})", self.virtual_point));
+        }
+        let (digits, _) = split_digits(&scaled).ok_or_else(|| format!("the value needs more than {MAX_ZEROS} zeros"))?;
+        let mut nibbles: Vec<u8> = digits.bytes().map(|digit| digit - b'0').collect();
+        match self.rep {
+            BinaryNumberRep::Packed => {
+                let codes = self.codes.ok_or("binaryPackedSignCodes are missing")?;
+                nibbles.push(if unsigned { codes.unsigned } else if scaled.is_zero() { codes.zero } else if scaled.negative { codes.negative } else { codes.positive });
+            },
+            BinaryNumberRep::Ibm4690Packed if scaled.negative => nibbles.insert(0, 0xD),
+            _ if scaled.negative => return Err(format!("negative values can not be represented as {}", self.rep.as_str())),
+            _ => {},
+        }
+        let length = length.unwrap_or(nibbles.len().div_ceil(2).max(1));
+        if nibbles.len() > length * 2 {
+            return Err(format!("the value does not fit into {length} bytes"));
+        }
+        // padding goes after the sign nibble of ibm4690Packed numbers, which is the first one
+        let position = usize::from(self.rep == BinaryNumberRep::Ibm4690Packed && scaled.negative);
+        nibbles.splice(position..position, std::iter::repeat_n(0, length * 2 - nibbles.len()));
+        Ok(nibbles.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+    }
+}
+
+/// the `binaryDecimalVirtualPoint`: the number of digits after the decimal point, negative for scaled numbers
+pub(crate) fn virtual_point(format: &Format, path: &str) -> Result<i32, FormatError> {
+    let virtual_point = *required(&format.binary_number.binary_decimal_virtual_point, "binaryDecimalVirtualPoint", path)?;
+    i32::try_from(virtual_point).ok().filter(|virtual_point| virtual_point.unsigned_abs() <= MAX_ZEROS)
+        .ok_or_else(|| FormatError::unsupported(path, format!("binaryDecimalVirtualPoint {virtual_point} is out of range")))
+}
+
+/// the digits of `number` before and after the decimal point, without leading zeros,
+/// `None` if that takes more than [`MAX_ZEROS`] zeros
+fn split_digits(number: &Number) -> Option<(String, String)> {
+    let digits: String = number.digits.iter().map(|digit| char::from(b'0' + digit)).collect();
+    if number.exponent >= 0 {
+        if digits.is_empty() {
+            return Some((String::new(), String::new()));
+        }
+        let zeros = number.exponent.unsigned_abs();
+        return (zeros <= MAX_ZEROS).then(|| (format!("{digits}{}", "0".repeat(zeros as usize)), String::new()));
+    }
+    let fraction = number.exponent.unsigned_abs() as usize;
+    if fraction >= digits.len() {
+        (fraction - digits.len() <= MAX_ZEROS as usize).then(|| (String::new(), format!("{digits:0>fraction$}")))
+    }
+    else {
+        let (integer, rest) = digits.split_at(digits.len() - fraction);
+        Some((integer.to_string(), rest.to_string()))
+    }
+}
+
+/// the error for a value that can only be written out with more than [`MAX_ZEROS`] zeros
+fn too_many_digits(path: &str) -> FormatError {
+    FormatError::unsupported(path, format!("the value needs more than {MAX_ZEROS} zeros to be written out"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::CompiledSchema;
+
+    fn format(properties: &str) -> Format {
+        let source = format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/"><dfdl:format textStandardNaNRep="NaN" textStandardInfinityRep="Inf"
+                textStandardDecimalSeparator="." textStandardGroupingSeparator="," textStandardExponentRep="E" {properties}/></xs:appinfo></xs:annotation>
+            </xs:schema>"#);
+        CompiledSchema::new(&source).unwrap().default_format().clone()
+    }
+
+    #[test]
+    fn formats_by_pattern() {
+        let cases = [
+            ("#,##0.00", 1234567.891, "1,234,567.89"),
+            ("#,##0.00", -0.005, "0.00"),
+            ("#,##,##0", 1234567.0, "12,34,567"),
+            ("000.###", 1.5, "001.5"),
+            ("#.##;(#.##)", -2.125, "(2.12)"),
+            ("0.00E00", 12345.0, "1.23E04"),
+            ("0.0E+0", 0.000999, "1.0E-3"),
+            ("#0%", 0.256, "26%"),
+            ("'#'#0' units'", 7.0, "#7 units"),
+            ("*x#####0", 42.0, "xxxx42"),
+            ("$*x###0", 42.0, "$xx42"),
+        ];
+        for (pattern, value, expected) in cases {
+            let format = format(&format!(r##"textStandardBase="10" textNumberPattern="{pattern}" textNumberRounding="pattern""##));
+            let number = TextNumberFormat::new(&format, "/value").unwrap();
+            assert_eq!(number.format_float(value).unwrap(), expected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn rounds_explicitly() {
+        let cases = [
+            ("roundHalfEven", 0.0, 2.5, "2"),
+            ("roundHalfUp", 0.0, 2.5, "3"),
+            ("roundHalfDown", 0.0, -2.5, "-2"),
+            ("roundCeiling", 0.0, -2.1, "-2"),
+            ("roundFloor", 0.0, -2.1, "-3"),
+            ("roundUp", 0.0, 2.1, "3"),
+            ("roundDown", 0.0, 2.9, "2"),
+            ("roundHalfUp", 0.05, 1.024, "1.00"),
+            ("roundHalfUp", 0.05, 1.03, "1.05"),
+        ];
+        for (mode, increment, value, expected) in cases {
+            let pattern = if increment == 0.0 { "#0" } else { "#0.00" };
+            let format = format(&format!(r##"textStandardBase="10" textNumberPattern="{pattern}" textNumberRounding="explicit" textNumberRoundingMode="{mode}" textNumberRoundingIncrement="{increment}""##));
+            let number = TextNumberFormat::new(&format, "/value").unwrap();
+            assert_eq!(number.format_float(value).unwrap(), expected, "{mode} {value}");
+        }
+        let format = format(r##"textStandardBase="10" textNumberPattern="#0" textNumberRounding="explicit" textNumberRoundingMode="roundUnnecessary" textNumberRoundingIncrement="0""##);
+        assert!(TextNumberFormat::new(&format, "/value").unwrap().format_float(1.5).is_err());
+    }
+
+    #[test]
+    fn parses_by_check_policy() {
+        let strict = format(r##"textStandardBase="10" textNumberPattern="#,##0.###;(#)" textNumberCheckPolicy="strict" textStandardZeroRep="zero""##);
+        let strict = TextNumberFormat::new(&strict, "/value").unwrap();
+        assert_eq!(strict.parse_float("1,234.5").unwrap(), Some(1234.5));
+        assert_eq!(strict.parse_float("(1,234)").unwrap(), Some(-1234.0));
+        assert_eq!(strict.parse_integer("1234").unwrap(), Some(1234));
+        assert_eq!(strict.parse_integer("zero").unwrap(), Some(0));
+        for invalid in ["12,34", " 1", "+1", "1E3", "1.5x", ",1"] {
+            assert_eq!(strict.parse_float(invalid).unwrap(), None, "{invalid}");
+        }
+        assert_eq!(strict.parse_integer("1.5").unwrap(), None);
+
+        let lax = format(r##"textStandardBase="10" textNumberPattern="#,##0.###" textNumberCheckPolicy="lax" textNumberRounding="pattern""##);
+        let lax = TextNumberFormat::new(&lax, "/value").unwrap();
+        assert_eq!(lax.parse_float(" 12,34 ").unwrap(), Some(1234.0));
+        assert_eq!(lax.parse_float("+1.5e2").unwrap(), Some(150.0));
+        assert_eq!(lax.parse_integer("-7").unwrap(), Some(-7));
+        assert_eq!(lax.parse_decimal("1E1000").unwrap().map(|decimal| decimal.to_string().len()), Some(1001));
+        assert_eq!(lax.parse_decimal("1E1900000000").unwrap(), None);
+        assert_eq!(lax.parse_decimal("1E-1001").unwrap(), None);
+        assert!(lax.format_number(Number::from_decimal(&Decimal::new(1, 5000))).is_ok());
+        assert!(lax.format_number(Number { negative: false, digits: vec![1], exponent: 5000 }).is_err());
+
+        let hex = format(r##"textNumberPattern="#" textStandardBase="16" textNumberCheckPolicy="strict""##);
+        let hex = TextNumberFormat::new(&hex, "/value").unwrap();
+        assert_eq!(hex.parse_integer("ff").unwrap(), Some(255));
+        assert_eq!(hex.format_integer(-255).unwrap(), "-ff");
+        assert!(hex.parse_float("1").is_err());
+    }
+
+    #[test]
+    fn overpunches_zoned_signs() {
+        let cases = [
+            ("0000+", "asciiStandard", -123, "012s"),
+            ("0000+", "asciiTranslatedEBCDIC", 120, "012{"),
+            ("0000+", "asciiTranslatedEBCDIC", -121, "012J"),
+            ("+000", "asciiCARealiaModified", -5, " 05"),
+            ("000+", "asciiTandemModified", -7, "00\u{87}"),
+            ("00V00+", "asciiStandard", 5, "0500"),
+            ("###PP", "asciiStandard", 1200, "12"),
+        ];
+        for (pattern, style, value, text) in cases {
+            let format = format(&format!(r#"textNumberPattern="{pattern}" textZonedSignStyle="{style}" textNumberRounding="pattern""#));
+            let zoned = ZonedFormat::new(&format, "/value").unwrap();
+            assert_eq!(zoned.format(Number::from_integer(value)).unwrap(), text, "{pattern} {style}");
+            assert_eq!(zoned.parse(text).and_then(|number| number.to_integer()), Some(value), "{pattern} {style}");
+        }
+        let format = format(r#"textNumberPattern="000" textNumberRounding="pattern""#);
+        let unsigned = ZonedFormat::new(&format, "/value").unwrap();
+        assert!(unsigned.format(Number::from_integer(-1)).is_err());
+        assert_eq!(unsigned.parse("01p"), None);
+    }
+
+    #[test]
+    fn encodes_packed_decimals() {
+        let strict = format(r#"binaryPackedSignCodes="C D F C" binaryDecimalVirtualPoint="0" binaryNumberCheckPolicy="strict""#);
+        let cases = [
+            (BinaryNumberRep::Packed, -123, false, Some(3), vec![0x00, 0x12, 0x3D]),
+            (BinaryNumberRep::Packed, 0, false, None, vec![0x0C]),
+            (BinaryNumberRep::Packed, 45, true, None, vec![0x04, 0x5F]),
+            (BinaryNumberRep::Bcd, 1234, true, Some(3), vec![0x00, 0x12, 0x34]),
+            (BinaryNumberRep::Ibm4690Packed, -12, false, Some(2), vec![0xD0, 0x12]),
+            (BinaryNumberRep::Ibm4690Packed, 7, false, None, vec![0x07]),
+        ];
+        for (rep, value, unsigned, length, bytes) in cases {
+            let packed = PackedFormat::new(&strict, rep, "/value").unwrap();
+            assert_eq!(packed.encode(Number::from_integer(value), unsigned, length).unwrap(), bytes, "{rep:?} {value}");
+            assert_eq!(packed.decode(&bytes).and_then(|number| number.to_integer()), Some(value), "{rep:?} {value}");
+        }
+        let packed = PackedFormat::new(&strict, BinaryNumberRep::Packed, "/value").unwrap();
+        assert!(packed.encode(Number::from_integer(12345), false, Some(2)).is_err());
+        assert_eq!(packed.decode(&[0x12, 0x3A]), None);
+        assert_eq!(packed.decode(&[0x1A, 0x3C]), None);
+        assert!(PackedFormat::new(&strict, BinaryNumberRep::Bcd, "/value").unwrap().encode(Number::from_integer(-1), false, None).is_err());
+
+        let lax = format(r#"binaryPackedSignCodes="C D F C" binaryDecimalVirtualPoint="2" binaryNumberCheckPolicy="lax""#);
+        let packed = PackedFormat::new(&lax, BinaryNumberRep::Packed, "/value").unwrap();
+        assert_eq!(packed.decode(&[0x12, 0x3A]), Some(Number { negative: false, digits: vec![1, 2, 3], exponent: -2 }));
+        assert_eq!(packed.encode(Number::from_integer(5), false, None).unwrap(), [0x50, 0x0C]);
+    }
+}
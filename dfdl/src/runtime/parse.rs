@@ -0,0 +1,1427 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use thiserror::Error;
+
+use crate::schema::{Assert, AssertFailureType, AssertTestKind, BinaryBooleanTrueRep, BinaryFloatRep, BinaryNumberRep, CompiledSchema, DfdlExpression, DfdlLiteral, Discriminator, ElementContent, ElementDecl, EncodingErrorPolicy, Format, GroupKind, LengthKind, LengthUnits, MaxOccurs, ModelGroup, OccursCountKind, PrimitiveType, RuntimeValue, SeparatorPosition, SeparatorSuppressionPolicy, SimpleType, Term, TextJustification, TextNumberRep, TextTrimKind};
+use crate::{ComplexElement, Data, Element, Infoset, SimpleElement};
+
+use super::delimiter::{self, Delimiters};
+use super::encoding::Encoding;
+use super::io::{InputSource, Mark, StreamError};
+use super::number::{virtual_point, Number, PackedFormat, TextNumberFormat, ZonedFormat};
+use super::expression::{self, Environment, Length, Lengths, Value};
+use super::path::{Context, Frame, Location, Tree};
+use super::{alignment, binary_length, bit_order, byte_order, encoding, integer_data, integer_value, is_number, is_signed, is_text, required, runtime_value, skip, FormatError};
+
+
+/// Errors that can occur while parsing data
+#[derive(Clone, Debug, Error)]
+pub enum ParseError {
+    /// the schema has no global element of the requested name
+    #[error("the schema has no global element '{0}'")]
+    UnknownRoot(String),
+    /// the schema can not be used to parse the data
+    #[error(transparent)]
+    Format(#[from] FormatError),
+    /// the data ended before an element was complete
+    #[error("{element}: {needed} bits needed at bit {position}, but the data ends before")]
+    EndOfData {
+        /// path of the element being parsed
+        element: String,
+        /// bit position the element was read from
+        position: u64,
+        /// number of bits needed
+        needed: u64,
+    },
+    /// the data does not match the format of an element
+    #[error("{element}: invalid data at bit {position}: {reason}")]
+    InvalidData {
+        /// path of the element being parsed
+        element: String,
+        /// bit position the element was read from
+        position: u64,
+        /// description of the problem
+        reason: String,
+    },
+    /// none of the branches of a choice could be parsed
+    #[error("{element}: no branch of the choice matches the data at bit {position}{}", reasons(.branches))]
+    NoMatchingBranch {
+        /// path of the element containing the choice
+        element: String,
+        /// bit position the choice was read from
+        position: u64,
+        /// why each branch failed, in schema order
+        branches: Vec<ParseError>,
+    },
+    /// a `dfdl:assert` with `failureType="processingError"` does not hold
+    #[error("{element}: assertion failed at bit {position}: {message}")]
+    AssertionFailed {
+        /// path of the component carrying the assert
+        element: String,
+        /// bit position the assert was checked at
+        position: u64,
+        /// the message of the assert
+        message: String,
+    },
+    /// a `dfdl:discriminator` does not hold
+    #[error("{element}: discriminator failed at bit {position}: {message}")]
+    DiscriminatorFailed {
+        /// path of the component carrying the discriminator
+        element: String,
+        /// bit position the discriminator was checked at
+        position: u64,
+        /// the message of the discriminator, or why it could not be checked
+        message: String,
+    },
+    /// the root element was parsed successfully, but did not consume all data
+    #[error("{0} bytes of data left after the root element")]
+    TrailingData(u64),
+}
+
+impl ParseError {
+    /// whether this error is caused by the data rather than the schema,
+    /// in which case parsing may continue with a different alternative
+    fn is_processing_error(&self) -> bool {
+        !matches!(self, Self::UnknownRoot(_) | Self::Format(_))
+    }
+
+    fn from_stream(error: StreamError, element: &str) -> Self {
+        match error {
+            StreamError::EndOfData { position, needed } => Self::EndOfData { element: element.to_string(), position, needed },
+            StreamError::BitOrderChange { position } => FormatError::BitOrderChange { component: element.to_string(), position }.into(),
+        }
+    }
+}
+
+/// A `dfdl:assert` with `failureType="recoverableError"` that did not hold
+///
+/// These do not stop parsing, but are collected in the [`Infoset::diagnostics`].
+#[derive(Clone, Debug, Error)]
+#[error("{element}: assertion failed at bit {position}: {message}")]
+pub struct RecoverableError {
+    /// path of the component carrying the assert
+    pub element: String,
+    /// bit position the assert was checked at
+    pub position: u64,
+    /// the message of the assert
+    pub message: String,
+}
+
+/// the number of bytes decoded at first to match a pattern against
+const PATTERN_WINDOW: u64 = 1024;
+
+/// the most bytes a pattern is matched against, matches needing more data are not found
+const MAX_PATTERN_WINDOW: u64 = 1 << 20;
+
+/// lists why each branch of a choice failed, one per line
+fn reasons(branches: &[ParseError]) -> String {
+    branches.iter().enumerate().map(|(index, e)| format!("\n  branch {}: {}", index + 1, e.to_string().replace('\n', "\n  "))).collect()
+}
+
+/// Parses `data` as the global element `root` of `schema`
+///
+/// Strings and binary values in the returned [`Infoset`] borrow from `data` where possible.
+///
+/// # Errors
+///
+/// This function will return an error if `schema` has no global element called `root`,
+/// if the format properties of the schema do not allow parsing the data,
+/// or if the data does not match the schema. Asserts that fail with a recoverable error do not stop parsing,
+/// they are reported by [`Infoset::diagnostics`] instead.
+pub fn parse<'i>(schema: &CompiledSchema, root: &str, data: &'i [u8]) -> Result<Infoset<'i>, ParseError> {
+    let decl = schema.element(root).ok_or_else(|| ParseError::UnknownRoot(root.to_string()))?;
+    let mut parser = Parser {
+        input: InputSource::new(data),
+        root: decl,
+        patterns: HashMap::new(),
+        delimiters: Vec::new(),
+        frames: Vec::new(),
+        lengths: Vec::new(),
+        text: None,
+        points: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let element = parser.parse_element(decl, "", false)?;
+    // the bits after the end of the root element within its last byte are padding
+    match parser.input.remaining_bytes() {
+        0 => {},
+        remaining => return Err(ParseError::TrailingData(remaining)),
+    }
+    Ok(Infoset { dfdl_version: "1.0".to_string(), root_element: Some(element), diagnostics: parser.diagnostics })
+}
+
+
+/// a separator, where it is placed relative to the items of its sequence, and when it is left out
+struct Separator {
+    delimiters: Delimiters,
+    position: SeparatorPosition,
+    policy: SeparatorSuppressionPolicy,
+}
+
+/// the progress through the items of a sequence
+#[derive(Clone, Copy)]
+struct Items {
+    /// whether no item has been parsed yet
+    first: bool,
+    /// whether the content of the last item was empty
+    last_empty: bool,
+    /// whether the items since the last non-empty one were all empty optional occurrences
+    trailing_empty: bool,
+}
+
+impl Items {
+    fn new() -> Self {
+        Self { first: true, last_empty: false, trailing_empty: false }
+    }
+}
+
+/// the state of a single parse
+struct Parser<'s, 'i> {
+    input: InputSource<'i>,
+    /// the declaration of the root element
+    root: &'s ElementDecl,
+    /// the compiled `lengthPattern`s used so far
+    patterns: HashMap<String, Regex>,
+    /// the delimiters of the enclosing components, innermost last
+    delimiters: Vec<Delimiters>,
+    /// the complex elements being parsed, innermost last
+    frames: Vec<Frame<'i>>,
+    /// the lengths of the elements parsed so far, in the order they were completed
+    lengths: Vec<(Location, Lengths)>,
+    /// the lengths of the last text read, with its padding trimmed
+    text: Option<Lengths>,
+    /// the points of uncertainty being parsed, innermost last,
+    /// with whether a discriminator has resolved them
+    points: Vec<bool>,
+    /// the recoverable errors found so far
+    diagnostics: Vec<RecoverableError>,
+}
+
+/// the state of a parse to return to when an alternative fails
+#[derive(Clone, Copy)]
+struct Checkpoint {
+    input: Mark,
+    /// the number of children of the innermost complex element
+    children: usize,
+    lengths: usize,
+    diagnostics: usize,
+}
+
+impl<'s, 'i> Parser<'s, 'i> {
+    /// runs `parse` with the available data limited to the next `length` bits,
+    /// and continues after these bits, regardless of how many `parse` consumed
+    fn bounded<T>(&mut self, length: u64, path: &str, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        let start = self.input.position();
+        let end = start.saturating_add(length);
+        let limit = self.input.set_limit(end);
+        if end > limit {
+            self.input.set_limit(limit);
+            return Err(ParseError::EndOfData { element: path.to_string(), position: start, needed: length });
+        }
+        let result = parse(self);
+        let skipped = result.is_ok().then(|| self.input.skip(end - self.input.position()));
+        self.input.set_limit(limit);
+        let value = result?;
+        skipped.transpose().map_err(|e| ParseError::from_stream(e, path))?;
+        Ok(value)
+    }
+
+    /// skips the next `bits` bits
+    fn skip(&mut self, bits: u64, path: &str) -> Result<(), ParseError> {
+        self.input.skip(bits).map_err(|e| ParseError::from_stream(e, path))
+    }
+
+    /// skips to the next multiple of `alignment` bits, counted from the start of the data
+    fn align(&mut self, alignment: u64, path: &str) -> Result<(), ParseError> {
+        let offset = self.input.position() % alignment;
+        if offset == 0 {
+            return Ok(());
+        }
+        self.skip(alignment - offset, path)
+    }
+
+    fn invalid(&self, path: &str, position: u64, reason: impl Into<String>) -> ParseError {
+        ParseError::InvalidData { element: path.to_string(), position, reason: reason.into() }
+    }
+
+    /// runs `parse` with `delimiters` in scope, so that delimited content ends before them
+    fn delimited<T>(&mut self, delimiters: Option<Delimiters>, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        let Some(delimiters) = delimiters else { return parse(self) };
+        self.delimiters.push(delimiters);
+        let result = parse(self);
+        self.delimiters.pop();
+        result
+    }
+
+    /// consumes one of `delimiters`, which have to be at the current position
+    fn expect_delimiter(&mut self, delimiters: &Delimiters, path: &str) -> Result<(), ParseError> {
+        let position = self.input.position();
+        let length = delimiters.match_at(&self.input.peek_bytes(delimiters.bit_order))
+            .ok_or_else(|| self.invalid(path, position, format!("{} '{delimiters}' not found", delimiters.property)))?;
+        self.input.read_bytes(length as u64, delimiters.bit_order).map_err(|e| ParseError::from_stream(e, path))?;
+        Ok(())
+    }
+
+    /// parses a single item of a sequence with `separator`
+    fn separated<T>(&mut self, separator: Option<&Separator>, items: &mut Items, path: &str, item: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        if let Some(separator) = separator {
+            if separator.position == SeparatorPosition::Prefix || (separator.position == SeparatorPosition::Infix && !items.first) {
+                self.expect_delimiter(&separator.delimiters, path)?;
+            }
+        }
+        let start = self.input.position();
+        let value = item(self)?;
+        items.first = false;
+        items.last_empty = self.input.position() == start;
+        items.trailing_empty = false;
+        if let Some(separator) = separator.filter(|separator| separator.position == SeparatorPosition::Postfix) {
+            self.expect_delimiter(&separator.delimiters, path)?;
+        }
+        Ok(value)
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        Checkpoint { input: self.input.mark(), children: self.children().len(), lengths: self.lengths.len(), diagnostics: self.diagnostics.len() }
+    }
+
+    /// returns to `checkpoint`, dropping what was parsed since, including the recoverable errors
+    fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.input.reset(checkpoint.input);
+        self.children().truncate(checkpoint.children);
+        self.lengths.truncate(checkpoint.lengths);
+        self.diagnostics.truncate(checkpoint.diagnostics);
+    }
+
+    /// parses `alternative` as a point of uncertainty, returning to the state before it if it fails
+    ///
+    /// Returns `None` if the alternative failed and the next one may be tried, after adding its error to `failures`.
+    /// Once a discriminator has resolved the point of uncertainty, its failure is final.
+    fn speculate<T>(&mut self, failures: &mut Vec<ParseError>, alternative: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<Option<T>, ParseError> {
+        let checkpoint = self.checkpoint();
+        self.points.push(false);
+        let result = alternative(self);
+        let resolved = self.points.pop().unwrap_or_default();
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_processing_error() && !resolved => {
+                self.rewind(checkpoint);
+                failures.push(e);
+                Ok(None)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// checks the asserts and the discriminator of a component whose test is of kind `kind`
+    ///
+    /// Pattern tests are checked before their component is parsed, expression tests after it.
+    /// A discriminator that holds resolves the innermost point of uncertainty.
+    fn check(&mut self, asserts: &[Assert], discriminator: Option<&Discriminator>, kind: AssertTestKind, format: &Format, context: Context<'_, 'i>, path: &str) -> Result<(), ParseError> {
+        let position = self.input.position();
+        for assert in asserts.iter().filter(|assert| assert.test_kind == kind) {
+            if let Err(message) = self.test(assert.test.as_ref(), assert.test_pattern.as_deref(), &assert.message, format, context, path)? {
+                let (element, position) = (path.to_string(), position);
+                match assert.failure_type {
+                    AssertFailureType::ProcessingError => return Err(ParseError::AssertionFailed { element, position, message }),
+                    AssertFailureType::RecoverableError => self.diagnostics.push(RecoverableError { element, position, message }),
+                }
+            }
+        }
+        let Some(discriminator) = discriminator.filter(|discriminator| discriminator.test_kind == kind) else { return Ok(()) };
+        if let Err(message) = self.test(discriminator.test.as_ref(), discriminator.test_pattern.as_deref(), &discriminator.message, format, context, path)? {
+            return Err(ParseError::DiscriminatorFailed { element: path.to_string(), position, message });
+        }
+        if let Some(point) = self.points.last_mut() {
+            *point = true;
+        }
+        Ok(())
+    }
+
+    /// evaluates the test of an assert or discriminator, given by either `test` or `pattern`
+    ///
+    /// Returns the message to report if it does not hold, which defaults to a description of the test.
+    fn test(&mut self, test: Option<&DfdlExpression>, pattern: Option<&str>, message: &str, format: &Format, context: Context<'_, 'i>, path: &str) -> Result<Result<(), String>, ParseError> {
+        let message = |default: String| if message.is_empty() { default } else { message.to_string() };
+        if let Some(pattern) = pattern {
+            return Ok(match self.match_pattern(pattern, "testPattern", encoding(format, path)?, format, path)? {
+                Some(_) => Ok(()),
+                None => Err(message(format!("the data does not match '{pattern}'"))),
+            });
+        }
+        let expression = required(&test, "test", path)?;
+        Ok(match self.evaluate(expression, context) {
+            Ok(Value::Boolean(true)) => Ok(()),
+            Ok(_) => Err(message(format!("'{{{}}}' does not hold", expression.as_str()))),
+            Err(reason) => Err(format!("the expression '{{{}}}' can not be evaluated: {reason}", expression.as_str())),
+        })
+    }
+
+    /// evaluates `expression` with `context` as its context element
+    ///
+    /// Fails with a description of the problem.
+    fn evaluate(&self, expression: &DfdlExpression, context: Context<'_, 'i>) -> Result<Value, String> {
+        let tree = Tree { root: self.root, ancestors: self.frames.iter().map(Frame::ancestor).collect(), context };
+        expression::evaluate(expression.expression(), &tree, &mut Parsed(&self.lengths))
+    }
+
+    /// `format` with the values of its runtime-valued properties evaluated with `context` as the context element
+    fn format<'f>(&self, format: &'f Format, context: Context<'_, 'i>, path: &str) -> Result<Cow<'f, Format>, ParseError> {
+        format.with_runtime_values(|expression| self.evaluate(expression, context).map(|value| value.to_string()))
+            .map_err(|reason| self.invalid(path, self.input.position(), reason))
+    }
+
+    /// the location of the next element added to the innermost complex element being parsed
+    fn next_location(&self) -> Location {
+        let mut location: Location = self.frames.iter().skip(1).map(|frame| frame.index).collect();
+        location.extend(self.frames.last().map(|frame| frame.children.len()));
+        location
+    }
+
+    /// the children parsed so far of the innermost complex element being parsed
+    fn children(&mut self) -> &mut Vec<Element<'i>> {
+        &mut self.frames.last_mut().expect("terms are only parsed within complex elements").children
+    }
+
+    /// adds an optional occurrence to the children of the element being parsed, unless its content is empty
+    /// and it has a separator, which makes it absent
+    fn optional(&mut self, element: Element<'i>, separator: Option<&Separator>, items: &mut Items) {
+        if separator.is_some() && items.last_empty {
+            items.trailing_empty = true;
+        }
+        else {
+            self.children().push(element);
+        }
+    }
+
+    /// parses a single occurrence of `decl` within a sequence with `separator`
+    fn occurrence(&mut self, decl: &ElementDecl, parent: &str, array: bool, separator: Option<&Separator>, items: &mut Items) -> Result<Element<'i>, ParseError> {
+        self.separated(separator, items, parent, |parser| parser.parse_element(decl, parent, array))
+    }
+
+    /// parses every occurrence of `decl` and adds them to the children of the element being parsed
+    fn parse_occurrences(&mut self, decl: &ElementDecl, parent: &str, separator: Option<&Separator>, items: &mut Items) -> Result<(), ParseError> {
+        if !decl.is_array() && !decl.is_optional() {
+            let element = self.occurrence(decl, parent, false, separator, items)?;
+            self.children().push(element);
+            return Ok(());
+        }
+        let path = format!("{parent}/{}", decl.name);
+        let array = decl.is_array();
+        let max = match decl.max_occurs {
+            MaxOccurs::Bounded(max) => Some(max),
+            MaxOccurs::Unbounded => None,
+        };
+        match required(&decl.format.occurs.occurs_count_kind, "occursCountKind", &path)? {
+            kind @ (OccursCountKind::Fixed | OccursCountKind::Expression) => {
+                let count = if *kind == OccursCountKind::Fixed {
+                    max.filter(|max| *max == decl.min_occurs)
+                        .ok_or_else(|| FormatError::unsupported(&path, "occursCountKind 'fixed' requires minOccurs and maxOccurs to be equal"))?
+                }
+                else { self.occurs_count(decl, &path)? };
+                for _ in 0..count {
+                    let element = self.occurrence(decl, parent, array, separator, items)?;
+                    self.children().push(element);
+                }
+            },
+            OccursCountKind::Implicit => {
+                // with separators that are never suppressed, every occurrence up to maxOccurs is present, if only as an empty one
+                let never = separator.is_some_and(|separator| separator.policy == SeparatorSuppressionPolicy::Never);
+                if never && max.is_none() {
+                    return Err(FormatError::unsupported(&path, "separatorSuppressionPolicy 'never' requires a bounded maxOccurs").into());
+                }
+                for _ in 0..max.unwrap_or(u64::MAX).min(decl.min_occurs) {
+                    let element = self.occurrence(decl, parent, array, separator, items)?;
+                    self.children().push(element);
+                }
+                if never {
+                    for _ in decl.min_occurs..max.unwrap_or_default() {
+                        let element = self.occurrence(decl, parent, array, separator, items)?;
+                        self.optional(element, separator, items);
+                    }
+                }
+                else {
+                    self.parse_optional_occurrences(decl, parent, array, max.map(|max| max.saturating_sub(decl.min_occurs)), separator, items)?;
+                }
+            },
+            // parsed arrays are only bounded by the data
+            OccursCountKind::Parsed => self.parse_optional_occurrences(decl, parent, array, None, separator, items)?,
+            OccursCountKind::StopValue => {
+                let stop_values = required(&decl.format.occurs.occurs_stop_value, "occursStopValue", &path)?;
+                if !matches!(decl.content, ElementContent::Simple(_)) {
+                    return Err(FormatError::unsupported(&path, "occursCountKind 'stopValue' requires a simple element").into());
+                }
+                // the occurrence holding the stop value ends the array and is not part of it, it follows maxOccurs values at most
+                let mut count = 0;
+                loop {
+                    let position = self.input.position();
+                    let element = self.occurrence(decl, parent, array, separator, items)?;
+                    if is_stop_value(&element, stop_values) {
+                        break;
+                    }
+                    if let Some(max) = max.filter(|max| count >= *max) {
+                        return Err(self.invalid(&path, position, format!("no occursStopValue within maxOccurs {max}")));
+                    }
+                    self.children().push(element);
+                    count += 1;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// parses up to `max` occurrences of `decl`, ending at the first one that can not be parsed
+    fn parse_optional_occurrences(&mut self, decl: &ElementDecl, parent: &str, array: bool, max: Option<u64>, separator: Option<&Separator>, items: &mut Items) -> Result<(), ParseError> {
+        let mut count = 0;
+        while max.is_none_or(|max| count < max) {
+            let (position, before) = (self.input.position(), *items);
+            match self.speculate(&mut Vec::new(), |parser| parser.occurrence(decl, parent, array, separator, items))? {
+                Some(element) => self.optional(element, separator, items),
+                None => {
+                    // the failed occurrence leaves no trace in the progress through the sequence
+                    *items = before;
+                    break;
+                },
+            }
+            // occurrences that consume no data would repeat forever
+            if self.input.position() == position {
+                break;
+            }
+            count += 1;
+        }
+        Ok(())
+    }
+
+    /// the number of occurrences of `decl` given by its `occursCount` expression
+    fn occurs_count(&self, decl: &ElementDecl, path: &str) -> Result<u64, ParseError> {
+        let expression = required(&decl.format.occurs.occurs_count, "occursCount", path)?;
+        let position = self.input.position();
+        match self.evaluate(expression, Context::Next(&decl.name)) {
+            Ok(Value::Integer(count)) => u64::try_from(count)
+                .map_err(|_| self.invalid(path, position, format!("occursCount '{{{}}}' is {count}, which is negative", expression.as_str()))),
+            Ok(value) => Err(self.invalid(path, position, format!("occursCount '{{{}}}' is '{value}', which is not an integer", expression.as_str()))),
+            Err(reason) => Err(self.invalid(path, position, format!("occursCount '{{{}}}': {reason}", expression.as_str()))),
+        }
+    }
+
+    /// parses a single occurrence of `decl`, including its initiator and terminator
+    fn parse_element(&mut self, decl: &ElementDecl, parent: &str, array: bool) -> Result<Element<'i>, ParseError> {
+        let path = format!("{parent}/{}", decl.name);
+        let discriminator = decl.discriminator.as_ref();
+        if let (Some(calculation), ElementContent::Simple(simple)) = (&decl.format.calculation.input_value_calc, &decl.content) {
+            // calculated elements have no representation
+            let position = self.input.position();
+            let data = self.evaluate(calculation, Context::Next(&decl.name)).and_then(|value| value.to_data(simple.primitive))
+                .map_err(|reason| self.invalid(&path, position, format!("inputValueCalc '{{{}}}': {reason}", calculation.as_str())))?;
+            self.lengths.push((self.next_location(), Lengths::unpadded(0)));
+            let element = simple_element(decl, array, data);
+            self.check(&decl.asserts, discriminator, AssertTestKind::Expression, &decl.format, Context::Parsed(&element), &path)?;
+            return Ok(element);
+        }
+        let format = self.format(&decl.format, Context::Next(&decl.name), &path)?;
+        let format = format.as_ref();
+        let primitive = match &decl.content {
+            ElementContent::Simple(simple) => Some(simple.primitive),
+            ElementContent::Complex(_) => None,
+        };
+        self.skip(skip(format, &format.leading_skip, "leadingSkip", &path)?, &path)?;
+        self.align(alignment(format, primitive, &path)?, &path)?;
+        self.check(&decl.asserts, discriminator, AssertTestKind::Pattern, format, Context::Next(&decl.name), &path)?;
+        if let Some(initiator) = Delimiters::new(format, &format.delimiters.initiator, "initiator", &path)? {
+            self.expect_delimiter(&initiator, &path)?;
+        }
+        let terminator = Delimiters::new(format, &format.delimiters.terminator, "terminator", &path)?;
+        let element = self.delimited(terminator.clone(), |parser| parser.parse_content(decl, format, array, &path))?;
+        if let Some(terminator) = &terminator {
+            self.expect_delimiter(terminator, &path)?;
+        }
+        self.skip(skip(format, &format.trailing_skip, "trailingSkip", &path)?, &path)?;
+        self.check(&decl.asserts, discriminator, AssertTestKind::Expression, format, Context::Parsed(&element), &path)?;
+        Ok(element)
+    }
+
+    /// parses the content of a single occurrence of `decl`, the element at `path`, with the properties of `format`
+    fn parse_content(&mut self, decl: &ElementDecl, format: &Format, array: bool, path: &str) -> Result<Element<'i>, ParseError> {
+        let namespace = decl.namespace.clone().unwrap_or_default();
+        let simple = match &decl.content {
+            ElementContent::Simple(simple) => Some(simple),
+            ElementContent::Complex(_) => None,
+        };
+        let length = self.content_length(format, simple, decl.prefix_length_type.as_deref(), path)?;
+        let (location, start) = (self.next_location(), self.input.position());
+        match &decl.content {
+            ElementContent::Simple(simple) => {
+                let length = length.unwrap_or_else(|| self.input.remaining_bits());
+                self.text = None;
+                let data = self.bounded(length, path, |parser| parser.parse_simple(format, simple.primitive, length, path))?;
+                let content = self.input.position() - start;
+                let lengths = self.text.take().map_or(Lengths::unpadded(content), |text| Lengths { content: Length { bits: content, ..text.content }, ..text });
+                self.lengths.push((location, lengths));
+                Ok(simple_element(decl, array, data))
+            },
+            ElementContent::Complex(group) => {
+                let index = self.frames.last().map_or(0, |frame| frame.children.len());
+                self.frames.push(Frame { name: decl.name.clone(), index, children: Vec::new() });
+                let result = match length {
+                    Some(length) => self.bounded(length, path, |parser| parser.parse_group(group, path)),
+                    None => self.parse_group(group, path),
+                };
+                let children = self.frames.pop().map(|frame| frame.children).unwrap_or_default();
+                result?;
+                let content = self.input.position() - start;
+                self.lengths.push((location, Lengths::unpadded(content)));
+                Ok(Element::ComplexElement(ComplexElement {
+                    schema: String::new(),
+                    namespace,
+                    name: decl.name.clone(),
+                    nilled: false,
+                    array,
+                    valid: true,
+                    union_member_schema: String::new(),
+                    children,
+                }))
+            },
+        }
+    }
+
+    /// parses the content of a complex element, `path` being the path of that element
+    fn parse_group(&mut self, group: &ModelGroup, path: &str) -> Result<(), ParseError> {
+        let format = self.format(&group.format, Context::Frame, path)?;
+        let format = format.as_ref();
+        self.skip(skip(format, &format.leading_skip, "leadingSkip", path)?, path)?;
+        self.align(alignment(format, None, path)?, path)?;
+        let discriminator = group.discriminator.as_ref();
+        self.check(&group.asserts, discriminator, AssertTestKind::Pattern, format, Context::Frame, path)?;
+        if let Some(initiator) = Delimiters::new(format, &format.delimiters.initiator, "initiator", path)? {
+            self.expect_delimiter(&initiator, path)?;
+        }
+        let terminator = Delimiters::new(format, &format.delimiters.terminator, "terminator", path)?;
+        self.delimited(terminator.clone(), |parser| parser.parse_group_content(group, format, path))?;
+        if let Some(terminator) = &terminator {
+            self.expect_delimiter(terminator, path)?;
+        }
+        self.skip(skip(format, &format.trailing_skip, "trailingSkip", path)?, path)?;
+        self.check(&group.asserts, discriminator, AssertTestKind::Expression, format, Context::Frame, path)
+    }
+
+    fn parse_group_content(&mut self, group: &ModelGroup, format: &Format, path: &str) -> Result<(), ParseError> {
+        match group.kind {
+            GroupKind::Sequence => {
+                let separator = match Delimiters::new(format, &format.sequence.separator, "separator", path)? {
+                    Some(delimiters) => Some(Separator {
+                        delimiters,
+                        position: *required(&format.sequence.separator_position, "separatorPosition", path)?,
+                        policy: *required(&format.sequence.separator_suppression_policy, "separatorSuppressionPolicy", path)?,
+                    }),
+                    None => None,
+                };
+                self.delimited(separator.as_ref().map(|separator| separator.delimiters.clone()), |parser| {
+                    let mut items = Items::new();
+                    for term in &group.terms {
+                        parser.parse_term(term, path, separator.as_ref(), &mut items)?;
+                    }
+                    if items.trailing_empty && separator.is_some_and(|separator| separator.policy == SeparatorSuppressionPolicy::TrailingEmptyStrict) {
+                        return Err(parser.invalid(path, parser.input.position(), "separatorSuppressionPolicy 'trailingEmptyStrict' does not allow separators of trailing empty occurrences"));
+                    }
+                    Ok(())
+                })
+            },
+            GroupKind::Choice => {
+                let position = self.input.position();
+                let mut branches = Vec::new();
+                for term in &group.terms {
+                    if self.speculate(&mut branches, |parser| parser.parse_term(term, path, None, &mut Items::new()))?.is_some() {
+                        return Ok(());
+                    }
+                }
+                Err(ParseError::NoMatchingBranch { element: path.to_string(), position, branches })
+            },
+        }
+    }
+
+    fn parse_term(&mut self, term: &Term, path: &str, separator: Option<&Separator>, items: &mut Items) -> Result<(), ParseError> {
+        match term {
+            Term::Element(decl) => self.parse_occurrences(decl, path, separator, items),
+            Term::Group(group) => {
+                // the children of a failing group are discarded along with it
+                let count = self.children().len();
+                let result = self.separated(separator, items, path, |parser| parser.parse_group(group, path));
+                if result.is_err() {
+                    self.children().truncate(count);
+                }
+                result
+            },
+        }
+    }
+
+    /// the length in bits of the content of an element as given by its `lengthKind`, `simple` being the type
+    /// of simple elements, `None` if the content determines its own length
+    fn content_length(&mut self, format: &Format, simple: Option<&SimpleType>, prefix: Option<&SimpleType>, path: &str) -> Result<Option<u64>, ParseError> {
+        let units = || required(&format.length.length_units, "lengthUnits", path).copied();
+        match required(&format.length.length_kind, "lengthKind", path)? {
+            LengthKind::Explicit => {
+                let length = *runtime_value(&format.length.length, "length", path)?;
+                self.length_bits(length, units()?, format, simple, path).map(Some)
+            },
+            LengthKind::Prefixed => {
+                let units = units()?;
+                let length = self.parse_prefix(format, prefix, units, path)?;
+                self.length_bits(length, units, format, simple, path).map(Some)
+            },
+            LengthKind::Implicit => match simple {
+                None => Ok(None),
+                Some(simple) => match simple.primitive {
+                    PrimitiveType::String => {
+                        let length = required(&simple.max_length, "maxLength", path)?;
+                        self.length_bits(*length, LengthUnits::Characters, format, Some(simple), path).map(Some)
+                    },
+                    PrimitiveType::HexBinary => Ok(Some(required(&simple.max_length, "maxLength", path)?.saturating_mul(8))),
+                    primitive if is_text(format, primitive, path)? =>
+                        Err(FormatError::unsupported(path, "lengthKind 'implicit' is not allowed for text numbers, booleans and calendars").into()),
+                    primitive => Ok(Some(binary_length(format, primitive, path)?)),
+                },
+            },
+            LengthKind::Pattern => self.pattern_length(format, simple, path).map(Some),
+            LengthKind::EndOfParent => Ok(Some(self.input.remaining_bits())),
+            LengthKind::Delimited => match simple {
+                None => Ok(None),
+                Some(simple) => self.delimited_length(format, simple, path).map(Some),
+            },
+        }
+    }
+
+    /// converts a length of `length` `units` at the current position to bits
+    fn length_bits(&self, length: u64, units: LengthUnits, format: &Format, simple: Option<&SimpleType>, path: &str) -> Result<u64, ParseError> {
+        let text = match simple {
+            Some(simple) => is_text(format, simple.primitive, path)?,
+            None => false,
+        };
+        match units {
+            LengthUnits::Bytes => Ok(length.saturating_mul(8)),
+            LengthUnits::Bits if text => Err(FormatError::unsupported(path, "lengthUnits 'bits' is not allowed for text").into()),
+            LengthUnits::Bits => Ok(length),
+            LengthUnits::Characters if simple.is_none() =>
+                Err(FormatError::unsupported(path, "lengthUnits 'characters' is not supported for complex elements yet").into()),
+            LengthUnits::Characters if !text => Err(FormatError::unsupported(path, "lengthUnits 'characters' is not allowed for binary data").into()),
+            LengthUnits::Characters => {
+                let encoding = encoding(format, path)?;
+                let data = self.input.peek_bytes(bit_order(format, path)?);
+                let bytes = usize::try_from(length).ok().and_then(|length| encoding.char_bytes(&data, length));
+                match bytes {
+                    Some(bytes) => Ok(bytes as u64 * 8),
+                    None => Err(ParseError::EndOfData { element: path.to_string(), position: self.input.position(), needed: length.saturating_mul(8) }),
+                }
+            },
+        }
+    }
+
+    /// parses the length prefix of an element with `lengthKind="prefixed"`, returning the length of its content in `units`
+    fn parse_prefix(&mut self, format: &Format, prefix: Option<&SimpleType>, units: LengthUnits, path: &str) -> Result<u64, ParseError> {
+        let prefix = prefix.ok_or_else(|| FormatError::MissingProperty { property: "prefixLengthType", component: path.to_string() })?;
+        if prefix.format.length.length_kind == Some(LengthKind::Prefixed) {
+            return Err(FormatError::unsupported(path, "the prefixLengthType can not have lengthKind 'prefixed'").into());
+        }
+        let start = self.input.position();
+        let length = self.content_length(&prefix.format, Some(prefix), None, path)?.unwrap_or_else(|| self.input.remaining_bits());
+        let data = self.bounded(length, path, |parser| parser.parse_simple(&prefix.format, prefix.primitive, length, path))?;
+        let value = integer_value(&data).and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| self.invalid(path, start, "the length prefix must be a non-negative integer"))?;
+        if !*required(&format.length.prefix_includes_prefix_length, "prefixIncludesPrefixLength", path)? {
+            return Ok(value);
+        }
+        let prefix_length = match units {
+            LengthUnits::Bits => self.input.position() - start,
+            LengthUnits::Bytes => (self.input.position() - start).div_ceil(8),
+            LengthUnits::Characters => return Err(FormatError::unsupported(path, "prefixIncludesPrefixLength is not supported with lengthUnits 'characters' yet").into()),
+        };
+        value.checked_sub(prefix_length)
+            .ok_or_else(|| self.invalid(path, start, format!("the length prefix {value} is shorter than the prefix itself")))
+    }
+
+    /// the length in bits of the data up to the first in-scope delimiter, or to the end of the available data
+    fn delimited_length(&self, format: &Format, simple: &SimpleType, path: &str) -> Result<u64, ParseError> {
+        // binary data is scanned bytewise
+        let encoding = if is_text(format, simple.primitive, path)? { encoding(format, path)? } else { Encoding::Latin1 };
+        let data = self.input.peek_bytes(bit_order(format, path)?);
+        let length = delimiter::scan(&data, encoding, &self.delimiters).map_or(data.len(), |(offset, _)| offset);
+        Ok(length as u64 * 8)
+    }
+
+    /// the length in bits of the data matched by the `lengthPattern` of `format` at the current position
+    fn pattern_length(&mut self, format: &Format, simple: Option<&SimpleType>, path: &str) -> Result<u64, ParseError> {
+        let pattern = required(&format.length.length_pattern, "lengthPattern", path)?.as_str();
+        // binary data is matched as if it was ISO-8859-1 text
+        let encoding = match simple {
+            Some(simple) if is_text(format, simple.primitive, path)? => encoding(format, path)?,
+            _ => Encoding::Latin1,
+        };
+        Ok(self.match_pattern(pattern, "lengthPattern", encoding, format, path)?.unwrap_or_default() * 8)
+    }
+
+    /// the length in bytes of the match of the regular expression `pattern`, given by the property `property`,
+    /// at the current position, `None` if it does not match
+    fn match_pattern(&mut self, pattern: &str, property: &str, encoding: Encoding, format: &Format, path: &str) -> Result<Option<u64>, ParseError> {
+        if !self.patterns.contains_key(pattern) {
+            let regex = Regex::new(&format!("^(?:{pattern})"))
+                .map_err(|e| FormatError::unsupported(path, format!("invalid {property} '{pattern}': {e}")))?;
+            self.patterns.insert(pattern.to_string(), regex);
+        }
+        let bit_order = bit_order(format, path)?;
+        let regex = &self.patterns[pattern];
+        // only a window of the data is decoded, which grows up to its limit while the match may depend on the data after it
+        let mut window = PATTERN_WINDOW;
+        loop {
+            let data = self.input.peek_bytes_at_most(window, bit_order);
+            let complete = (data.len() as u64) < window;
+            let (text, offsets) = encoding.decode_with_offsets(&data, complete);
+            let end = regex.find(&text).map(|m| m.end());
+            if !complete && window < MAX_PATTERN_WINDOW && end.is_none_or(|end| end == text.len()) {
+                window = (window * 2).min(MAX_PATTERN_WINDOW);
+                continue;
+            }
+            return Ok(end.map(|end| match text[..end].chars().count() {
+                0 => 0,
+                chars => offsets[chars - 1] as u64,
+            }));
+        }
+    }
+
+    /// parses the value of a simple element, taking the next `length` bits
+    fn parse_simple(&mut self, format: &Format, primitive: PrimitiveType, length: u64, path: &str) -> Result<Data<'i>, ParseError> {
+        match primitive {
+            PrimitiveType::String => self.parse_string(format, length, path).map(Data::String),
+            PrimitiveType::HexBinary => {
+                if !length.is_multiple_of(8) {
+                    return Err(FormatError::unsupported(path, "the length of hexBinary values must be a multiple of 8 bits").into());
+                }
+                let bytes = self.input.read_bytes(length / 8, bit_order(format, path)?).map_err(|e| ParseError::from_stream(e, path))?;
+                Ok(Data::HexBinary(bytes))
+            },
+            _ if is_text(format, primitive, path)? => self.parse_text(format, primitive, length, path),
+            _ => self.parse_binary(format, primitive, length, path),
+        }
+    }
+
+    /// parses a number, boolean or calendar value in binary representation
+    fn parse_binary(&mut self, format: &Format, primitive: PrimitiveType, bits: u64, path: &str) -> Result<Data<'i>, ParseError> {
+        if is_number(primitive) {
+            let rep = *required(&format.binary_number.binary_number_rep, "binaryNumberRep", path)?;
+            if rep != BinaryNumberRep::Binary {
+                return self.parse_packed(format, primitive, rep, bits, path);
+            }
+        }
+        if bits > 64 {
+            return Err(FormatError::unsupported(path, "binary numbers and booleans can be at most 64 bits long").into());
+        }
+        let byte_order = byte_order(format, bits, path)?;
+        let start = self.input.position();
+        let value = self.input.read_bits(bits, bit_order(format, path)?, byte_order).map_err(|e| ParseError::from_stream(e, path))?;
+        match primitive {
+            PrimitiveType::Float | PrimitiveType::Double => {
+                if *runtime_value(&format.binary_number.binary_float_rep, "binaryFloatRep", path)? != BinaryFloatRep::Ieee {
+                    return Err(FormatError::unsupported(path, "binaryFloatRep 'ibm390Hex' is not supported").into());
+                }
+                match (primitive, bits) {
+                    (PrimitiveType::Float, 32) => Ok(Data::Float(f32::from_bits(value as u32))),
+                    (PrimitiveType::Double, 64) => Ok(Data::Double(f64::from_bits(value))),
+                    _ => Err(FormatError::unsupported(path, "binary floating point numbers must be 4 (float) or 8 (double) bytes long").into()),
+                }
+            },
+            PrimitiveType::Boolean => {
+                let false_rep = *required(&format.boolean.binary_boolean_false_rep, "binaryBooleanFalseRep", path)?;
+                match required(&format.boolean.binary_boolean_true_rep, "binaryBooleanTrueRep", path)? {
+                    _ if value == false_rep => Ok(Data::Boolean(false)),
+                    BinaryBooleanTrueRep::NotFalse => Ok(Data::Boolean(true)),
+                    BinaryBooleanTrueRep::Value(true_rep) if value == *true_rep => Ok(Data::Boolean(true)),
+                    BinaryBooleanTrueRep::Value(_) => Err(self.invalid(path, start, format!("{value} is neither the true nor the false representation"))),
+                }
+            },
+            PrimitiveType::Decimal => {
+                let virtual_point = virtual_point(format, path)?;
+                let value = if is_signed(format, primitive, path)? { signed_value(value, bits).into() } else { value.into() };
+                let number = Number::from_integer(value).shifted(-virtual_point);
+                Ok(Data::Decimal(number.to_decimal(virtual_point.max(0).unsigned_abs())))
+            },
+            PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time =>
+                Err(FormatError::unsupported(path, "binary calendars are not supported yet").into()),
+            _ => integer(primitive, value, bits).ok_or_else(|| self.invalid(path, start, format!("value out of range for {primitive:?}"))),
+        }
+    }
+
+    /// parses a `packed`, `bcd` or `ibm4690Packed` number, taking the next `bits` bits
+    fn parse_packed(&mut self, format: &Format, primitive: PrimitiveType, rep: BinaryNumberRep, bits: u64, path: &str) -> Result<Data<'i>, ParseError> {
+        let packed = PackedFormat::new(format, rep, path)?;
+        if !bits.is_multiple_of(8) {
+            return Err(FormatError::unsupported(path, format!("the length of {} numbers must be a multiple of 8 bits", rep.as_str())).into());
+        }
+        let start = self.input.position();
+        let bytes = self.input.read_bytes(bits / 8, bit_order(format, path)?).map_err(|e| ParseError::from_stream(e, path))?;
+        let number = packed.decode(&bytes).ok_or_else(|| self.invalid(path, start, format!("data is not a valid {} number", rep.as_str())))?;
+        if primitive == PrimitiveType::Decimal {
+            return Ok(Data::Decimal(number.to_decimal(packed.scale())));
+        }
+        number.to_integer().and_then(|value| integer_data(primitive, value))
+            .ok_or_else(|| self.invalid(path, start, format!("value out of range for {primitive:?}")))
+    }
+
+    /// parses a number or boolean in text representation, taking the next `length` bits
+    fn parse_text(&mut self, format: &Format, primitive: PrimitiveType, length: u64, path: &str) -> Result<Data<'i>, ParseError> {
+        let start = self.input.position();
+        let text = match primitive {
+            PrimitiveType::Boolean => self.read_text(format, length, &format.boolean.text_boolean_pad_character, format.boolean.text_boolean_justification, path)?,
+            _ => self.read_text(format, length, &format.text_number.text_number_pad_character, format.text_number.text_number_justification, path)?,
+        };
+        let invalid = |reason: String| ParseError::InvalidData { element: path.to_string(), position: start, reason };
+        match primitive {
+            PrimitiveType::Boolean => {
+                let matches = |reps: &Option<RuntimeValue<Vec<DfdlLiteral>>>, property| -> Result<bool, FormatError> {
+                    Ok(runtime_value(reps, property, path)?.iter().any(|rep| rep.as_text().is_some_and(|rep| rep == text)))
+                };
+                if matches(&format.boolean.text_boolean_true_rep, "textBooleanTrueRep")? {
+                    Ok(Data::Boolean(true))
+                }
+                else if matches(&format.boolean.text_boolean_false_rep, "textBooleanFalseRep")? {
+                    Ok(Data::Boolean(false))
+                }
+                else {
+                    Err(invalid(format!("'{text}' is neither a true nor a false representation")))
+                }
+            },
+            PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time =>
+                Err(FormatError::unsupported(path, "text calendars are not supported yet").into()),
+            _ => {
+                if required(&format.text_number.text_number_rep, "textNumberRep", path)? == &TextNumberRep::Zoned {
+                    if matches!(primitive, PrimitiveType::Float | PrimitiveType::Double) {
+                        return Err(FormatError::unsupported(path, "floating point numbers can not be zoned").into());
+                    }
+                    let zoned = ZonedFormat::new(format, path)?;
+                    let number = zoned.parse(&text);
+                    let data = match primitive {
+                        PrimitiveType::Decimal => number.map(|number| Data::Decimal(number.to_decimal(zoned.scale()))),
+                        _ => number.and_then(|number| number.to_integer()).and_then(|value| integer_data(primitive, value)),
+                    };
+                    return data.ok_or_else(|| invalid(format!("'{text}' is not a valid zoned {primitive:?}")));
+                }
+                let number = TextNumberFormat::new(format, path)?;
+                match primitive {
+                    PrimitiveType::Float | PrimitiveType::Double => {
+                        let value = number.parse_float(&text)?.ok_or_else(|| invalid(format!("'{text}' is not a number")))?;
+                        Ok(if primitive == PrimitiveType::Float { Data::Float(value as f32) } else { Data::Double(value) })
+                    },
+                    PrimitiveType::Decimal => number.parse_decimal(&text)?.map(Data::Decimal).ok_or_else(|| invalid(format!("'{text}' is not a decimal number"))),
+                    _ => number.parse_integer(&text)?.and_then(|value| integer_data(primitive, value))
+                        .ok_or_else(|| invalid(format!("'{text}' is not a valid {primitive:?}"))),
+                }
+            },
+        }
+    }
+
+    /// parses a string taking the next `length` bits, with its padding removed
+    fn parse_string(&mut self, format: &Format, length: u64, path: &str) -> Result<Cow<'i, str>, ParseError> {
+        self.read_text(format, length, &format.text.text_string_pad_character, format.text.text_string_justification, path)
+    }
+
+    /// reads and decodes the next `length` bits as text, trimming the padding given by `textTrimKind`
+    fn read_text(&mut self, format: &Format, length: u64, pad_character: &Option<DfdlLiteral>, justification: Option<TextJustification>, path: &str) -> Result<Cow<'i, str>, ParseError> {
+        let encoding = encoding(format, path)?;
+        let start = self.input.position();
+        if !length.is_multiple_of(8) {
+            return Err(FormatError::unsupported(path, "the length of text must be a multiple of 8 bits").into());
+        }
+        let bytes = self.input.read_bytes(length / 8, bit_order(format, path)?).map_err(|e| ParseError::from_stream(e, path))?;
+        let policy = format.encoding_error_policy.unwrap_or(EncodingErrorPolicy::Replace);
+        let text = match bytes {
+            Cow::Borrowed(bytes) => encoding.decode(bytes, policy),
+            Cow::Owned(bytes) => encoding.decode(&bytes, policy).map(|text| Cow::Owned(text.into_owned())),
+        };
+        let text = text.ok_or_else(|| self.invalid(path, start, format!("data is not valid {}", encoding.name())))?;
+        let characters = text.chars().count() as u64;
+        let text = trim(text, format, pad_character, justification, path)?;
+        let value = encoding.encode(&text, EncodingErrorPolicy::Replace).map_or(length, |bytes| bytes.len() as u64 * 8);
+        self.text = Some(Lengths {
+            content: Length { bits: length, characters: Some(characters) },
+            value: Length { bits: value, characters: Some(text.chars().count() as u64) },
+        });
+        Ok(text)
+    }
+}
+
+/// the lengths of the elements parsed so far, as the environment of expressions
+struct Parsed<'l>(&'l [(Location, Lengths)]);
+
+impl Environment for Parsed<'_> {
+    fn length(&mut self, location: &[usize], value: bool) -> Result<Length, String> {
+        self.0.iter().rev().find(|(parsed, _)| parsed == location).map(|(_, lengths)| if value { lengths.value } else { lengths.content })
+            .ok_or_else(|| "the length of an element that is not parsed yet is not known".to_string())
+    }
+}
+
+/// a simple element of `decl` holding `data`
+fn simple_element<'i>(decl: &ElementDecl, array: bool, data: Data<'i>) -> Element<'i> {
+    Element::SimpleElement(SimpleElement {
+        schema: String::new(),
+        namespace: decl.namespace.clone().unwrap_or_default(),
+        name: decl.name.clone(),
+        nilled: false,
+        array,
+        valid: true,
+        union_member_schema: String::new(),
+        data: Some(data),
+    })
+}
+
+/// whether the value of `element` is one of `stop_values`
+fn is_stop_value(element: &Element<'_>, stop_values: &[DfdlLiteral]) -> bool {
+    let Element::SimpleElement(SimpleElement { data: Some(data), .. }) = element else { return false };
+    stop_values.iter().filter_map(DfdlLiteral::as_text).any(|stop_value| match data {
+        Data::String(text) => *text == stop_value,
+        Data::Float(value) => stop_value.trim().parse() == Ok(*value),
+        Data::Double(value) => stop_value.trim().parse() == Ok(*value),
+        Data::Boolean(value) => stop_value.trim().parse() == Ok(*value),
+        Data::Decimal(value) => stop_value.trim().parse().as_ref() == Ok(value),
+        _ => stop_value.trim().parse().ok().is_some_and(|value: i128| Some(value) == integer_value(data)),
+    })
+}
+
+/// removes the padding of a string, as given by `textTrimKind`
+fn trim<'t>(text: Cow<'t, str>, format: &Format, pad_character: &Option<DfdlLiteral>, justification: Option<TextJustification>, path: &str) -> Result<Cow<'t, str>, FormatError> {
+    if format.text.text_trim_kind != Some(TextTrimKind::PadChar) {
+        return Ok(text);
+    }
+    let pad = required(pad_character, "pad character", path)?;
+    let pad = pad.as_text()
+        .and_then(|pad| { let mut chars = pad.chars(); chars.next().filter(|_| chars.next().is_none()) })
+        .ok_or_else(|| FormatError::unsupported(path, "pad characters must be a single character"))?;
+    let justification = *required(&justification, "justification", path)?;
+    Ok(match text {
+        Cow::Borrowed(text) => Cow::Borrowed(trim_padding(text, pad, justification)),
+        Cow::Owned(text) => Cow::Owned(trim_padding(&text, pad, justification).to_string()),
+    })
+}
+
+/// `text` without the `pad` characters on the side opposite to `justification`
+fn trim_padding(text: &str, pad: char, justification: TextJustification) -> &str {
+    match justification {
+        TextJustification::Left => text.trim_end_matches(pad),
+        TextJustification::Right => text.trim_start_matches(pad),
+        TextJustification::Center => text.trim_matches(pad),
+    }
+}
+
+/// converts the `bits` bit wide value `raw` to the data variant of `primitive`,
+/// interpreting it as two's complement for signed types
+fn integer<'d>(primitive: PrimitiveType, raw: u64, bits: u64) -> Option<Data<'d>> {
+    let signed = matches!(primitive, PrimitiveType::Integer | PrimitiveType::Long | PrimitiveType::Int | PrimitiveType::Short | PrimitiveType::Byte);
+    let value = if signed { i128::from(signed_value(raw, bits)) } else { i128::from(raw) };
+    integer_data(primitive, value)
+}
+
+/// the two's complement value of the `bits` bits long `raw`
+fn signed_value(raw: u64, bits: u64) -> i64 {
+    if bits == 0 {
+        return 0;
+    }
+    let shift = 64 - bits.min(64);
+    ((raw << shift) as i64) >> shift
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMAT: &str = r##"
+        representation="binary" binaryNumberRep="binary" binaryFloatRep="ieee" byteOrder="bigEndian"
+        lengthKind="implicit" lengthUnits="bytes" encoding="utf-8" encodingErrorPolicy="replace"
+        initiator="" terminator="" separator="" leadingSkip="0" trailingSkip="0" alignment="1"
+        alignmentUnits="bytes" occursCountKind="implicit" textTrimKind="none" bitOrder="mostSignificantBitFirst"
+        binaryBooleanTrueRep="1" binaryBooleanFalseRep="0" ignoreCase="no" separatorPosition="infix" separatorSuppressionPolicy="anyEmpty" textNumberRep="standard"
+        textNumberPattern="#0" textNumberCheckPolicy="strict" textStandardBase="10""##;
+
+    fn schema(elements: &str) -> CompiledSchema {
+        let source = format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/"><dfdl:format {FORMAT}/></xs:appinfo></xs:annotation>
+            {elements}
+            </xs:schema>"#);
+        CompiledSchema::new(&source).unwrap()
+    }
+
+    fn children<'a, 'i>(element: &'a Element<'i>) -> &'a [Element<'i>] {
+        match element {
+            Element::ComplexElement(complex) => &complex.children,
+            Element::SimpleElement(_) => panic!("not a complex element"),
+        }
+    }
+
+    fn value<'a, 'i>(element: &'a Element<'i>) -> &'a Data<'i> {
+        match element {
+            Element::SimpleElement(SimpleElement { data: Some(data), .. }) => data,
+            _ => panic!("not a simple element"),
+        }
+    }
+
+    #[test]
+    fn parses_binary_numbers() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="a" type="xs:int"/>
+            <xs:element name="b" type="xs:short" dfdl:byteOrder="littleEndian"/>
+            <xs:element name="c" type="xs:double"/>
+            <xs:element name="d" type="xs:float" dfdl:byteOrder="littleEndian"/>
+            <xs:element name="e" type="xs:long" dfdl:lengthKind="explicit" dfdl:length="3"/>
+            <xs:element name="f" type="xs:boolean" dfdl:lengthKind="explicit" dfdl:length="1"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let mut data = vec![0, 0, 1, 0, 0xFE, 0xFF];
+        data.extend(2.5f64.to_be_bytes());
+        data.extend((-0.75f32).to_le_bytes());
+        data.extend([0xFF, 0xFF, 0xFD, 1]);
+        let infoset = parse(&schema, "record", &data).unwrap();
+        let root = infoset.root_element.as_ref().unwrap();
+        let children = children(root);
+        assert!(matches!(value(&children[0]), Data::Int(256)));
+        assert!(matches!(value(&children[1]), Data::Short(-2)));
+        assert!(matches!(value(&children[2]), Data::Double(v) if *v == 2.5));
+        assert!(matches!(value(&children[3]), Data::Float(v) if *v == -0.75));
+        assert!(matches!(value(&children[4]), Data::Long(-3)));
+        assert!(matches!(value(&children[5]), Data::Boolean(true)));
+    }
+
+    #[test]
+    fn parses_strings_arrays_and_choices() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="3" dfdl:lengthUnits="characters"/>
+            <xs:element name="body" dfdl:lengthKind="explicit" dfdl:length="4"><xs:complexType><xs:sequence>
+                <xs:element name="item" type="xs:unsignedByte" maxOccurs="unbounded"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:choice>
+                <xs:element name="large" type="xs:unsignedShort"/>
+                <xs:element name="small" type="xs:unsignedByte"/>
+            </xs:choice>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = [b'a', 0xC3, 0xA4, b'c', 1, 2, 3, 4, 9];
+        let infoset = parse(&schema, "record", &data).unwrap();
+        let root = infoset.root_element.as_ref().unwrap();
+        let children = children(root);
+        assert!(matches!(value(&children[0]), Data::String(Cow::Borrowed("aäc"))));
+        let items = super::tests::children(&children[1]);
+        assert_eq!(items.len(), 4);
+        assert!(items.iter().all(Element::is_array));
+        assert_eq!(children[2].name(), "small");
+    }
+
+    #[test]
+    fn matches_length_patterns_in_windows() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="item" type="xs:string" maxOccurs="3" dfdl:lengthKind="pattern" dfdl:lengthPattern="[^;]*;"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let mut data = "a\u{FFFD}b;ä;".as_bytes().to_vec();
+        data.splice(1..4, [0xFF, 0xFE]);
+        data.extend([b'x'; 3000]);
+        data.push(b';');
+        let infoset = parse(&schema, "record", &data).unwrap();
+        let items = children(infoset.root_element.as_ref().unwrap());
+        assert_eq!(items.len(), 3);
+        assert!(matches!(value(&items[0]), Data::String(text) if text == "a\u{FFFD}\u{FFFD}b;"));
+        assert!(matches!(value(&items[1]), Data::String(text) if text == "ä;"));
+        assert!(matches!(value(&items[2]), Data::String(text) if text.len() == 3001));
+        // the data beyond the largest window is not looked at
+        let mut data = vec![b'x'; MAX_PATTERN_WINDOW as usize];
+        data.push(b';');
+        assert!(matches!(parse(&schema, "record", &data), Err(ParseError::TrailingData(_))));
+    }
+
+    #[test]
+    fn parses_every_length_kind() {
+        let schema = schema(r#"<xs:simpleType name="length"><xs:restriction base="xs:unsignedByte"/></xs:simpleType>
+            <xs:simpleType name="code"><xs:restriction base="xs:string"><xs:maxLength value="3"/></xs:restriction></xs:simpleType>
+            <xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="code" type="code"/>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="length" dfdl:prefixIncludesPrefixLength="no"/>
+            <xs:element name="body" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="length" dfdl:prefixIncludesPrefixLength="yes"><xs:complexType><xs:sequence>
+                <xs:element name="item" type="xs:unsignedByte" maxOccurs="unbounded"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:element name="number" type="xs:int" dfdl:representation="text"
+                dfdl:lengthKind="pattern" dfdl:lengthPattern="[0-9]+"/>
+            <xs:element name="rest" type="xs:hexBinary" dfdl:lengthKind="endOfParent"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = b"abc\x02hi\x04\x01\x02\x0342\xDE\xAD";
+        let infoset = parse(&schema, "record", data).unwrap();
+        let root = infoset.root_element.as_ref().unwrap();
+        let children = children(root);
+        assert!(matches!(value(&children[0]), Data::String(Cow::Borrowed("abc"))));
+        assert!(matches!(value(&children[1]), Data::String(Cow::Borrowed("hi"))));
+        let items = super::tests::children(&children[2]);
+        assert_eq!(items.len(), 3);
+        assert!(matches!(value(&items[2]), Data::UnsignedByte(3)));
+        assert!(matches!(value(&children[3]), Data::Int(42)));
+        assert!(matches!(value(&children[4]), Data::HexBinary(bytes) if bytes[..] == [0xDE, 0xAD]));
+
+        // the prefix of body claims more data than there is
+        assert!(matches!(parse(&schema, "record", b"abc\x00\x09\x01"), Err(ParseError::EndOfData { .. })));
+    }
+
+    #[test]
+    fn parses_delimited_text() {
+        let csv = schema(r#"<xs:element name="file"><xs:complexType><xs:sequence>
+            <xs:element name="line" maxOccurs="unbounded" dfdl:lengthKind="delimited" dfdl:terminator="%NL;"><xs:complexType>
+                <xs:sequence dfdl:separator=", ;">
+                    <xs:element name="name" type="xs:string" dfdl:lengthKind="delimited"/>
+                    <xs:element name="count" type="xs:int" dfdl:lengthKind="delimited" dfdl:representation="text"/>
+                    <xs:element name="flag" type="xs:string" dfdl:lengthKind="delimited"/>
+                </xs:sequence>
+            </xs:complexType></xs:element>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&csv, "file", b"ab,12;x\r\ncd,-3,\n").unwrap();
+        let lines = children(infoset.root_element.as_ref().unwrap());
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(value(&children(&lines[0])[0]), Data::String(Cow::Borrowed("ab"))));
+        assert!(matches!(value(&children(&lines[0])[2]), Data::String(Cow::Borrowed("x"))));
+        assert!(matches!(value(&children(&lines[1])[1]), Data::Int(-3)));
+        assert!(matches!(value(&children(&lines[1])[2]), Data::String(Cow::Borrowed(""))));
+        assert!(matches!(parse(&csv, "file", b"ab,12;x"), Err(ParseError::InvalidData { .. })));
+
+        // the delimiters of the enclosing components end the content of the inner ones
+        let edi = schema(r#"<xs:element name="segment" dfdl:initiator="UNH+" dfdl:terminator="'" dfdl:ignoreCase="yes" dfdl:lengthKind="delimited">
+            <xs:complexType><xs:sequence dfdl:separator="+">
+                <xs:element name="reference" type="xs:string" dfdl:lengthKind="delimited"/>
+                <xs:element name="type" dfdl:lengthKind="delimited"><xs:complexType><xs:sequence dfdl:separator=":">
+                    <xs:element name="id" type="xs:string" dfdl:lengthKind="delimited" maxOccurs="unbounded"/>
+                </xs:sequence></xs:complexType></xs:element>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&edi, "segment", b"unh+1+ORDERS:D:96A'").unwrap();
+        let fields = children(infoset.root_element.as_ref().unwrap());
+        assert!(matches!(value(&fields[0]), Data::String(Cow::Borrowed("1"))));
+        let ids = children(&fields[1]);
+        assert_eq!(ids.len(), 3);
+        assert!(matches!(value(&ids[2]), Data::String(Cow::Borrowed("96A"))));
+
+        // a failing optional occurrence does not hide the trailing empty ones before it
+        let strict = schema(r#"<xs:element name="record"><xs:complexType>
+            <xs:sequence dfdl:separator=";" dfdl:separatorPosition="postfix" dfdl:separatorSuppressionPolicy="trailingEmptyStrict">
+                <xs:element name="name" type="xs:string" dfdl:lengthKind="delimited"/>
+                <xs:element name="note" type="xs:string" dfdl:lengthKind="delimited" minOccurs="0" maxOccurs="3"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        assert_eq!(children(parse(&strict, "record", b"x;y;").unwrap().root_element.as_ref().unwrap()).len(), 2);
+        assert!(matches!(parse(&strict, "record", b"x;;"), Err(ParseError::InvalidData { .. })));
+    }
+
+    #[test]
+    fn parses_arrays_by_occurs_count_kind() {
+        let schema = |count: &str| schema(&format!(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="header"><xs:complexType><xs:sequence>
+                <xs:element name="count" type="xs:unsignedByte"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:element name="item" type="xs:unsignedByte" maxOccurs="4" dfdl:occursCountKind="expression" dfdl:occursCount="{{ {count} }}"/>
+            <xs:element name="value" type="xs:byte" maxOccurs="unbounded" dfdl:occursCountKind="stopValue" dfdl:occursStopValue="0 -1"/>
+            <xs:element name="rest" type="xs:unsignedShort" maxOccurs="unbounded" dfdl:occursCountKind="parsed"/>
+            </xs:sequence></xs:complexType></xs:element>"#));
+        let data = [2, 7, 8, 5, 6, 0xFF, 0, 1, 0, 2];
+        for count in ["../header/count", "/record/header/count", "../header[1]/dfdl:count"] {
+            let infoset = parse(&schema(count), "record", &data).unwrap();
+            let children = children(infoset.root_element.as_ref().unwrap());
+            assert_eq!(children.iter().map(Element::name).collect::<Vec<_>>(), ["header", "item", "item", "value", "value", "rest", "rest"], "{count}");
+            assert!(matches!(value(&children[2]), Data::UnsignedByte(8)));
+            assert!(matches!(value(&children[4]), Data::Byte(6)));
+            assert!(matches!(value(&children[6]), Data::UnsignedShort(2)));
+        }
+        // the count exceeds the data
+        assert!(matches!(parse(&schema("../header/count"), "record", &[9, 1, 0]), Err(ParseError::EndOfData { .. })));
+
+        // the stop value follows maxOccurs values at most
+        let bounded = self::schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="value" type="xs:byte" maxOccurs="2" dfdl:occursCountKind="stopValue" dfdl:occursStopValue="0"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        assert_eq!(children(parse(&bounded, "record", &[1, 2, 0]).unwrap().root_element.as_ref().unwrap()).len(), 2);
+        assert!(matches!(parse(&bounded, "record", &[1, 2, 3, 0]), Err(ParseError::InvalidData { position: 16, .. })));
+    }
+
+    #[test]
+    fn backtracks_to_points_of_uncertainty() {
+        let schema = |discriminator: &str| schema(&format!(r#"<xs:element name="record"><xs:complexType><xs:choice>
+            <xs:element name="long"><xs:complexType><xs:sequence>
+                <xs:element name="kind" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="1">
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">{discriminator}</xs:appinfo></xs:annotation>
+                </xs:element>
+                <xs:element name="value" type="xs:int"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:element name="short"><xs:complexType><xs:sequence>
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:discriminator testKind="pattern" testPattern="[A-Z]" message="no kind"/>
+                </xs:appinfo></xs:annotation>
+                <xs:element name="kind" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="1"/>
+                <xs:element name="value" type="xs:short"/>
+            </xs:sequence></xs:complexType></xs:element>
+            </xs:choice></xs:complexType></xs:element>"#));
+        let discriminated = schema(r#"<dfdl:discriminator test="{ . eq 'L' }" message="not a long record"/>"#);
+        let branch = |infoset: &Infoset| children(infoset.root_element.as_ref().unwrap())[0].name().to_string();
+        assert_eq!(branch(&parse(&discriminated, "record", b"L\0\0\0\x01").unwrap()), "long");
+        // a failing discriminator rejects its branch
+        assert_eq!(branch(&parse(&discriminated, "record", b"S\0\x01").unwrap()), "short");
+        // once the discriminator holds, the choice is resolved to its branch, which then fails
+        assert!(matches!(parse(&discriminated, "record", b"L\0\x01"), Err(ParseError::EndOfData { .. })));
+        assert_eq!(branch(&parse(&schema(""), "record", b"L\0\x01").unwrap()), "short");
+
+        // the reasons of all branches are reported
+        let Err(e @ ParseError::NoMatchingBranch { .. }) = parse(&discriminated, "record", b"1\0\x01") else { panic!("expected no matching branch") };
+        let ParseError::NoMatchingBranch { branches, .. } = &e else { unreachable!() };
+        assert!(matches!(branches[..], [ParseError::DiscriminatorFailed { .. }, ParseError::DiscriminatorFailed { .. }]));
+        let message = e.to_string();
+        assert!(message.contains("branch 1: /record/long/kind: discriminator failed at bit 8: not a long record"), "{message}");
+        assert!(message.contains("branch 2: /record/short: discriminator failed at bit 0: no kind"), "{message}");
+    }
+
+    #[test]
+    fn checks_asserts() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="version" type="xs:unsignedByte">
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:assert test="{ . le 2 }" message="unknown version"/>
+                    <dfdl:assert test="{ . eq 2 }" failureType="recoverableError" message="outdated version"/>
+                </xs:appinfo></xs:annotation>
+            </xs:element>
+            <xs:choice>
+                <xs:element name="text" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="2">
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                        <dfdl:assert testKind="pattern" testPattern="[a-z]" failureType="recoverableError"/>
+                        <dfdl:assert testKind="pattern" testPattern="[a-z]{2}"/>
+                    </xs:appinfo></xs:annotation>
+                </xs:element>
+                <xs:element name="number" type="xs:unsignedShort"/>
+            </xs:choice>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&schema, "record", b"\x02ok").unwrap();
+        assert!(infoset.diagnostics().is_empty());
+        let infoset = parse(&schema, "record", b"\x01ok").unwrap();
+        assert_eq!(infoset.diagnostics().len(), 1);
+        assert_eq!(infoset.diagnostics()[0].to_string(), "/record/version: assertion failed at bit 8: outdated version");
+        // the recoverable errors of failed branches are dropped along with them
+        let infoset = parse(&schema, "record", b"\x021a").unwrap();
+        assert_eq!(children(infoset.root_element.as_ref().unwrap())[1].name(), "number");
+        assert!(infoset.diagnostics().is_empty());
+        let Err(ParseError::AssertionFailed { element, message, .. }) = parse(&schema, "record", b"\x03ok") else { panic!("expected a failed assertion") };
+        assert_eq!((element.as_str(), message.as_str()), ("/record/version", "unknown version"));
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="len" type="xs:unsignedByte"/>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="{ ../len }"/>
+            <xs:element name="padded" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="5" dfdl:textTrimKind="padChar"
+                dfdl:textStringPadCharacter="_" dfdl:textStringJustification="left"/>
+            <xs:element name="total" type="xs:int" dfdl:inputValueCalc="{ dfdl:valueLength(../padded, 'bytes') + dfdl:contentLength(../padded, 'characters') * 10 }"/>
+            <xs:element name="flags" type="xs:unsignedByte"/>
+            <xs:element name="bit" type="xs:boolean" dfdl:inputValueCalc="{ dfdl:testBit(../flags, 3) }"/>
+            <xs:element name="number" type="xs:short" dfdl:byteOrder="{ if (../len eq 3) then 'littleEndian' else 'bigEndian' }"/>
+            <xs:element name="item" type="xs:unsignedByte" minOccurs="3" maxOccurs="3" dfdl:occursCountKind="fixed">
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:assert test="{ dfdl:occursIndex() eq 1 or . gt ../item[dfdl:occursIndex() - 1] }" message="items must ascend"/>
+                </xs:appinfo></xs:annotation>
+            </xs:element>
+            <xs:element name="summary" type="xs:string" dfdl:inputValueCalc="{ fn:concat(fn:upper-case(../name), '-', fn:substring('abcdef', 2, 3), '-',
+                fn:count(../item), '-', fn:round-half-to-even(2.5), '-', 7 idiv 2, '-', xs:hexBinary(xs:short(258)), '-', 1e7) }"/>
+            <xs:element name="checks" type="xs:boolean" dfdl:inputValueCalc="{ dfdl:checkConstraints(../name) and dfdl:setBits(1, 0, 1, 0, 0, 0, 0, 0) eq 5
+                and dfdl:decodeDFDLEntities('a%#x42;') eq 'aB' and dfdl:encodeDFDLEntities('5%') eq '5%%' and ../number div 2 eq 0.5 }"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&schema, "record", b"\x03abchi___\x04\x01\x00\x01\x02\x03").unwrap();
+        let children = children(infoset.root_element.as_ref().unwrap());
+        assert_eq!(children.iter().map(Element::name).collect::<Vec<_>>(),
+            ["len", "name", "padded", "total", "flags", "bit", "number", "item", "item", "item", "summary", "checks"]);
+        assert!(matches!(value(&children[1]), Data::String(name) if name == "abc"));
+        assert!(matches!(value(&children[3]), Data::Int(52)));
+        assert!(matches!(value(&children[5]), Data::Boolean(true)));
+        assert!(matches!(value(&children[6]), Data::Short(1)));
+        assert!(matches!(value(&children[10]), Data::String(summary) if summary == "ABC-bcd-3-2-3-0102-1.0E7"));
+        assert!(matches!(value(&children[11]), Data::Boolean(true)));
+        let Err(ParseError::AssertionFailed { element, message, .. }) = parse(&schema, "record", b"\x03abchi___\x04\x01\x00\x01\x03\x02")
+            else { panic!("expected a failed assertion") };
+        assert_eq!((element.as_str(), message.as_str()), ("/record/item", "items must ascend"));
+    }
+
+    #[test]
+    fn evaluates_decimals_exactly() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="price" type="xs:decimal" dfdl:inputValueCalc="{ xs:decimal('1.50') }"/>
+            <xs:element name="sum" type="xs:decimal" dfdl:inputValueCalc="{ 0.1 + 0.2 }"/>
+            <xs:element name="text" type="xs:string" dfdl:inputValueCalc="{ fn:concat(../price * 2, ' ', 1 div 3, ' ', fn:round(-2.5), ' ',
+                fn:round-half-to-even(0.125, 2), ' ', 7.5 idiv 2, ' ', -7.5 mod 2) }"/>
+            <xs:element name="checks" type="xs:boolean" dfdl:inputValueCalc="{ 0.1 + 0.2 eq 0.3 and ../price eq 1.5 and 12345678901234567890.1 gt 12345678901234567890 }"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&schema, "record", b"").unwrap();
+        let children = children(infoset.root_element.as_ref().unwrap());
+        assert!(matches!(value(&children[0]), Data::Decimal(price) if price.to_string() == "1.50"));
+        assert!(matches!(value(&children[1]), Data::Decimal(sum) if sum.to_string() == "0.3"));
+        assert!(matches!(value(&children[2]), Data::String(text) if text == "3 0.333333333333333333 -2 0.12 3 -1.5"));
+        assert!(matches!(value(&children[3]), Data::Boolean(true)));
+    }
+
+    #[test]
+    fn checks_constraints() {
+        let schema = schema(r#"<xs:simpleType name="code"><xs:restriction base="xs:string"><xs:minLength value="2"/><xs:pattern value="[a-z]+"/></xs:restriction></xs:simpleType>
+            <xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="code" dfdl:lengthKind="explicit" dfdl:length="3">
+                <xs:simpleType><xs:restriction base="code"><xs:enumeration value="abc"/><xs:enumeration value="xy"/></xs:restriction></xs:simpleType>
+            </xs:element>
+            <xs:element name="level">
+                <xs:simpleType><xs:restriction base="xs:unsignedByte"><xs:minInclusive value="1"/><xs:maxExclusive value="10"/></xs:restriction></xs:simpleType>
+            </xs:element>
+            <xs:element name="price" dfdl:inputValueCalc="{ ../level div 4 }">
+                <xs:simpleType><xs:restriction base="xs:decimal"><xs:totalDigits value="3"/><xs:fractionDigits value="1"/></xs:restriction></xs:simpleType>
+            </xs:element>
+            <xs:element name="checks" type="xs:string" dfdl:inputValueCalc="{ fn:concat(dfdl:checkConstraints(../code), ' ',
+                dfdl:checkConstraints(../level), ' ', dfdl:checkConstraints(../price)) }"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let checks = |data: &[u8]| {
+            let infoset = parse(&schema, "record", data).unwrap();
+            match value(&children(infoset.root_element.as_ref().unwrap())[3]) {
+                Data::String(checks) => checks.to_string(),
+                data => panic!("unexpected {data:?}"),
+            }
+        };
+        assert_eq!(checks(b"abc\x02"), "true true true");
+        assert_eq!(checks(b"abd\x0a"), "false false true");
+        assert_eq!(checks(b"AB\x00\x01"), "false true false");
+    }
+
+    #[test]
+    fn rejects_alignments_beyond_64_bits() {
+        let schema = schema(r#"<xs:element name="a" type="xs:unsignedByte" dfdl:alignment="4611686018427387904"/>"#);
+        assert!(matches!(parse(&schema, "a", &[0]), Err(ParseError::Format(FormatError::Unsupported { .. }))));
+    }
+
+    #[test]
+    fn parses_bit_fields() {
+        let elements = |units| format!(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="a" type="xs:unsignedByte" dfdl:lengthKind="explicit" dfdl:length="3" dfdl:lengthUnits="bits"/>
+            <xs:element name="b" type="xs:byte" dfdl:lengthKind="explicit" dfdl:length="5" dfdl:lengthUnits="bits" dfdl:alignmentUnits="bits"/>
+            <xs:element name="c" type="xs:unsignedShort" dfdl:lengthKind="explicit" dfdl:length="11" dfdl:lengthUnits="bits" dfdl:alignmentUnits="bits"/>
+            <xs:element name="d" type="xs:short" dfdl:lengthKind="explicit" dfdl:length="11" dfdl:lengthUnits="bits"
+                dfdl:alignment="1" dfdl:alignmentUnits="{units}" dfdl:bitOrder="leastSignificantBitFirst" dfdl:byteOrder="littleEndian"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        // 101 | 10011 | 01111000011 + 5 bits alignment | 11 bits least significant bit first
+        let data = [0b1011_0011, 0b0111_1000, 0b0110_0000, 0b1111_1000, 0b0000_0111];
+        let aligned = schema(&elements("bytes"));
+        let infoset = parse(&aligned, "record", &data).unwrap();
+        let root = infoset.root_element.as_ref().unwrap();
+        let children = children(root);
+        assert!(matches!(value(&children[0]), Data::UnsignedByte(5)));
+        assert!(matches!(value(&children[1]), Data::Byte(-13)));
+        assert!(matches!(value(&children[2]), Data::UnsignedShort(963)));
+        assert!(matches!(value(&children[3]), Data::Short(-8)));
+
+        // without the alignment, the bit order would change in the middle of the third byte
+        let unaligned = schema(&elements("bits"));
+        assert!(matches!(parse(&unaligned, "record", &data), Err(ParseError::Format(FormatError::BitOrderChange { position: 19, .. }))));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let schema = schema(r#"<xs:element name="a" type="xs:int"/>
+            <xs:element name="b" type="xs:decimal"/>"#);
+        assert!(matches!(parse(&schema, "c", &[]), Err(ParseError::UnknownRoot(_))));
+        assert!(matches!(parse(&schema, "a", &[0, 0]), Err(ParseError::EndOfData { position: 0, needed: 32, .. })));
+        assert!(matches!(parse(&schema, "a", &[0; 5]), Err(ParseError::TrailingData(1))));
+        assert!(matches!(parse(&schema, "b", &[0; 4]), Err(ParseError::Format(FormatError::Unsupported { .. }))));
+    }
+}
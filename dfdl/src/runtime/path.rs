@@ -0,0 +1,181 @@
+use crate::schema::{ElementContent, ElementDecl, ModelGroup, Term};
+use crate::{ComplexElement, Element};
+
+
+/// A complex element being parsed, of which only the children parsed so far are known
+pub(crate) struct Frame<'i> {
+    pub(crate) name: String,
+    /// the position of the element among the children of its parent
+    pub(crate) index: usize,
+    pub(crate) children: Vec<Element<'i>>,
+}
+
+impl<'i> Frame<'i> {
+    pub(crate) fn ancestor(&self) -> Ancestor<'_, 'i> {
+        Ancestor { name: &self.name, index: self.index, children: &self.children }
+    }
+}
+
+/// A complex element enclosing the context of an expression
+#[derive(Clone, Copy)]
+pub(crate) struct Ancestor<'a, 'i> {
+    pub(crate) name: &'a str,
+    /// the position of the element among the children of its parent
+    pub(crate) index: usize,
+    /// the children known so far, which are all of them when unparsing
+    pub(crate) children: &'a [Element<'i>],
+}
+
+/// The element a relative path starts at
+#[derive(Clone, Copy)]
+pub(crate) enum Context<'a, 'i> {
+    /// the element of this name about to be processed, which follows the known children of the innermost ancestor
+    Next(&'a str),
+    /// an element that has just been parsed, but is not yet added to the innermost ancestor
+    Parsed(&'a Element<'i>),
+    /// the innermost ancestor, for statements on its model groups
+    Frame,
+    /// the child of the innermost ancestor at this position
+    Child(usize),
+}
+
+/// The location of an element: the positions of the children leading to it from the root element
+pub(crate) type Location = Vec<usize>;
+
+/// an element a [`Location`] leads to
+pub(crate) enum Node<'a, 'i> {
+    /// the ancestor at this depth
+    Ancestor(usize),
+    /// a completely known element
+    Element(&'a Element<'i>),
+    /// the element of a [`Context::Next`], which is not known yet
+    Pending(&'a str),
+}
+
+/// The part of the infoset visible to an expression: the elements enclosing its context,
+/// along with their children known so far
+pub(crate) struct Tree<'a, 'i> {
+    /// the declaration of the root element
+    pub(crate) root: &'a ElementDecl,
+    /// the complex elements enclosing the context, starting at the root element
+    pub(crate) ancestors: Vec<Ancestor<'a, 'i>>,
+    pub(crate) context: Context<'a, 'i>,
+}
+
+impl<'a, 'i> Tree<'a, 'i> {
+    /// the location of the context element
+    pub(crate) fn context(&self) -> Location {
+        let Some(last) = self.ancestors.last() else { return Vec::new() };
+        let mut location: Location = self.ancestors[1..].iter().map(|ancestor| ancestor.index).collect();
+        match self.context {
+            Context::Next(_) | Context::Parsed(_) => location.push(last.children.len()),
+            Context::Frame => {},
+            Context::Child(index) => location.push(index),
+        }
+        location
+    }
+
+    /// the name of the root element
+    pub(crate) fn root_name(&self) -> &'a str {
+        match (self.ancestors.first(), self.context) {
+            (Some(root), _) => root.name,
+            (None, Context::Parsed(element)) => element.name(),
+            (None, _) => &self.root.name,
+        }
+    }
+
+    /// the element at `location`, `None` if there is none or it is not visible yet
+    pub(crate) fn node(&self, location: &[usize]) -> Option<Node<'a, 'i>> {
+        if self.ancestors.is_empty() {
+            // the context is the root element
+            let root = match self.context {
+                Context::Next(name) => Node::Pending(name),
+                Context::Parsed(element) => Node::Element(element),
+                Context::Frame | Context::Child(_) => return None,
+            };
+            return descend(root, location);
+        }
+        let mut depth = 0;
+        while depth + 1 < self.ancestors.len() && location.get(depth) == Some(&self.ancestors[depth + 1].index) {
+            depth += 1;
+        }
+        let Some(&index) = location.get(depth) else { return Some(Node::Ancestor(depth)) };
+        let ancestor = self.ancestors[depth];
+        let node = match ancestor.children.get(index) {
+            Some(child) => Node::Element(child),
+            None if index == ancestor.children.len() && depth + 1 == self.ancestors.len() => match self.context {
+                Context::Next(name) => Node::Pending(name),
+                Context::Parsed(element) => Node::Element(element),
+                Context::Frame | Context::Child(_) => return None,
+            },
+            None => return None,
+        };
+        descend(node, &location[depth + 1..])
+    }
+
+    /// the name of the element `node`
+    pub(crate) fn name(&self, node: &Node<'a, 'i>) -> &'a str {
+        match *node {
+            Node::Ancestor(depth) => self.ancestors[depth].name,
+            Node::Element(element) => element.name(),
+            Node::Pending(name) => name,
+        }
+    }
+
+    /// the locations of the children called `name` of the element at `location`, in document order
+    pub(crate) fn children(&self, location: &[usize], name: &str) -> Vec<Location> {
+        let mut names: Vec<(usize, &str)> = Vec::new();
+        match self.node(location) {
+            Some(Node::Ancestor(depth)) => {
+                let children = self.ancestors[depth].children;
+                names.extend(children.iter().map(Element::name).enumerate());
+                // the next ancestor or the context element may follow the known children
+                let next = match self.ancestors.get(depth + 1) {
+                    Some(next) => (next.index == children.len()).then_some(next.name),
+                    None => match self.context {
+                        Context::Next(name) => Some(name),
+                        Context::Parsed(element) => Some(element.name()),
+                        Context::Frame | Context::Child(_) => None,
+                    },
+                };
+                names.extend(next.map(|next| (children.len(), next)));
+            },
+            Some(Node::Element(Element::ComplexElement(ComplexElement { children, .. }))) => names.extend(children.iter().map(Element::name).enumerate()),
+            _ => {},
+        }
+        names.into_iter().filter(|(_, child)| *child == name).map(|(index, _)| {
+            let mut child = location.to_vec();
+            child.push(index);
+            child
+        }).collect()
+    }
+
+    /// the declaration of the element at `location`
+    pub(crate) fn decl(&self, location: &[usize]) -> Option<&'a ElementDecl> {
+        let mut decl = self.root;
+        for depth in 1..=location.len() {
+            let name = self.name(&self.node(&location[..depth])?);
+            let ElementContent::Complex(group) = &decl.content else { return None };
+            decl = child_decl(group, name)?;
+        }
+        Some(decl)
+    }
+}
+
+/// the element reached from `node` by following the child positions in `location`
+fn descend<'a, 'i>(mut node: Node<'a, 'i>, location: &[usize]) -> Option<Node<'a, 'i>> {
+    for &index in location {
+        let Node::Element(Element::ComplexElement(complex)) = node else { return None };
+        node = Node::Element(complex.children.get(index)?);
+    }
+    Some(node)
+}
+
+/// the declaration of the child element `name` of a complex element with content `group`
+fn child_decl<'a>(group: &'a ModelGroup, name: &str) -> Option<&'a ElementDecl> {
+    group.terms.iter().find_map(|term| match term {
+        Term::Element(decl) if decl.name == name => Some(&**decl),
+        Term::Element(_) => None,
+        Term::Group(group) => child_decl(group, name),
+    })
+}
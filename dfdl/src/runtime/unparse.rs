@@ -0,0 +1,1193 @@
+use std::borrow::Cow;
+use std::io::Write;
+
+use thiserror::Error;
+
+use crate::schema::{BinaryBooleanTrueRep, DfdlExpression, BinaryFloatRep, BinaryNumberRep, CharClass, CompiledSchema, DfdlLiteral, ElementContent, ElementDecl, EncodingErrorPolicy, Format, GroupKind, LengthKind, LengthUnits, LiteralPart, MaxOccurs, ModelGroup, OccursCountKind, PrimitiveType, RuntimeValue, SeparatorPosition, SeparatorSuppressionPolicy, SimpleType, Term, TextJustification, TextNumberRep, TextPadKind};
+use crate::{ComplexElement, Data, Element, Infoset, SimpleElement};
+
+use super::encoding::Encoding;
+use super::expression::{self, Environment, Length, Value};
+use super::io::{Mark, OutputSink, StreamError};
+use super::number::{virtual_point, Number, PackedFormat, TextNumberFormat, ZonedFormat};
+use super::path::{Ancestor, Context, Location, Node, Tree};
+use super::{alignment, binary_length, bit_order, byte_order, encoding, explicit_complex_length, implicit_binary_length, integer_data, is_number, is_signed, is_text, required, runtime_value, skip, FormatError};
+
+
+/// Errors that can occur while unparsing an infoset
+#[derive(Debug, Error)]
+pub enum UnparseError {
+    /// the infoset has no root element
+    #[error("the infoset is empty")]
+    EmptyInfoset,
+    /// the schema has no global element of the requested name
+    #[error("the schema has no global element '{0}'")]
+    UnknownRoot(String),
+    /// the schema can not be used to unparse the infoset
+    #[error(transparent)]
+    Format(#[from] FormatError),
+    /// an element required by the schema is missing from the infoset
+    #[error("{element} is missing from the infoset")]
+    MissingElement {
+        /// path of the missing element
+        element: String,
+    },
+    /// the infoset contains an element the schema does not allow at its position
+    #[error("{element} is not expected at this position")]
+    UnexpectedElement {
+        /// path of the unexpected element
+        element: String,
+    },
+    /// the value of an element can not be represented in the format of the schema
+    #[error("{element}: {reason}")]
+    InvalidValue {
+        /// path of the element
+        element: String,
+        /// description of the problem
+        reason: String,
+    },
+    /// none of the branches of a choice matches the infoset
+    #[error("{element}: no branch of the choice matches the infoset")]
+    NoMatchingBranch {
+        /// path of the element containing the choice
+        element: String,
+    },
+    /// writing the data failed
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl UnparseError {
+    /// whether this error is caused by the infoset rather than the schema,
+    /// in which case unparsing may continue with a different alternative
+    fn is_processing_error(&self) -> bool {
+        matches!(self, Self::MissingElement { .. } | Self::UnexpectedElement { .. } | Self::InvalidValue { .. } | Self::NoMatchingBranch { .. })
+    }
+
+    fn invalid(path: &str, reason: impl Into<String>) -> Self {
+        Self::InvalidValue { element: path.to_string(), reason: reason.into() }
+    }
+
+    fn from_stream(error: StreamError, element: &str) -> Self {
+        match error {
+            StreamError::BitOrderChange { position } => FormatError::BitOrderChange { component: element.to_string(), position }.into(),
+            StreamError::EndOfData { .. } => unreachable!("output is not limited"),
+        }
+    }
+}
+
+/// Writes `infoset` to `output` as the global element `root` of `schema`
+///
+/// The root element of the infoset is unparsed as `root` whatever its own name is,
+/// so infosets created by [`to_infoset`](crate::serde::to_infoset) can be unparsed as any element.
+/// Nothing is written if unparsing fails.
+///
+/// # Errors
+///
+/// This function will return an error if `schema` has no global element called `root`,
+/// if the infoset does not match `schema`, if one of its values can not be represented
+/// in the format of the schema, or if writing to `output` fails.
+pub fn unparse(schema: &CompiledSchema, root: &str, infoset: &Infoset<'_>, mut output: impl Write) -> Result<(), UnparseError> {
+    let decl = schema.element(root).ok_or_else(|| UnparseError::UnknownRoot(root.to_string()))?;
+    let root = infoset.root_element.as_ref().ok_or(UnparseError::EmptyInfoset)?;
+    let mut unparser = Unparser { output: OutputSink::default(), root, schema: decl, location: Vec::new(), pending: Vec::new(), text: None };
+    unparser.unparse_element(decl, root, "")?;
+    output.write_all(&unparser.output.into_bytes())?;
+    Ok(())
+}
+
+
+/// a separator, where it is placed relative to the items of its sequence, and when it is left out
+struct Separator {
+    bytes: Vec<u8>,
+    position: SeparatorPosition,
+    policy: SeparatorSuppressionPolicy,
+}
+
+/// the progress through the items of a sequence
+struct Items {
+    /// whether no item has been written yet
+    first: bool,
+    /// whether the content of the last item was empty
+    last_empty: bool,
+    /// where the empty optional occurrences at the end of the items written so far start
+    trailing_empty: Option<(Mark, bool)>,
+}
+
+impl Items {
+    fn new() -> Self {
+        Self { first: true, last_empty: false, trailing_empty: None }
+    }
+}
+
+/// the state of a single unparse
+struct Unparser<'a, 'i> {
+    /// the data written so far, the last byte being padded with 0 bits
+    output: OutputSink,
+    /// the root element of the infoset
+    root: &'a Element<'i>,
+    /// the declaration of the root element
+    schema: &'a ElementDecl,
+    /// the location of the element being unparsed
+    location: Location,
+    /// the locations of the elements whose lengths or calculated values are being computed, innermost last
+    pending: Vec<Location>,
+    /// the number of characters of the last text written
+    text: Option<u64>,
+}
+
+impl<'a, 'i> Unparser<'a, 'i> {
+    /// writes `bytes` in the bit order of `format`
+    fn write(&mut self, bytes: &[u8], format: &Format, path: &str) -> Result<(), UnparseError> {
+        self.output.write_bytes(bytes, bit_order(format, path)?).map_err(|e| UnparseError::from_stream(e, path))
+    }
+
+    /// writes `bits` bits of the `fillByte` of `format`
+    fn fill(&mut self, bits: u64, format: &Format, path: &str) -> Result<(), FormatError> {
+        if bits > 0 {
+            self.output.fill(bits, *required(&format.fill_byte, "fillByte", path)?, bit_order(format, path)?);
+        }
+        Ok(())
+    }
+
+    /// fills up to the next multiple of `alignment` bits, counted from the start of the data
+    fn align(&mut self, alignment: u64, format: &Format, path: &str) -> Result<(), FormatError> {
+        let offset = self.output.position() % alignment;
+        if offset == 0 {
+            return Ok(());
+        }
+        self.fill(alignment - offset, format, path)
+    }
+
+    /// writes the first delimiter of `delimiters`, if there is one
+    fn delimiter(&mut self, format: &Format, delimiters: &Option<RuntimeValue<Vec<DfdlLiteral>>>, property: &'static str, path: &str) -> Result<(), UnparseError> {
+        if let Some(bytes) = first_delimiter(format, delimiters, property, path)? {
+            self.write(&bytes, format, path)?;
+        }
+        Ok(())
+    }
+
+    /// the part of the infoset visible to expressions on the element being unparsed, called `name`,
+    /// or on its model groups if `frame` is set
+    fn tree<'t>(&self, frame: bool, name: &'t str) -> Tree<'t, 'i> where 'a: 't {
+        let depth = if frame { self.location.len() + 1 } else { self.location.len() };
+        let mut ancestors = Vec::new();
+        let mut element = Some(self.root);
+        for level in 0..depth {
+            let Some(Element::ComplexElement(complex)) = element else { break };
+            let index = if level == 0 { 0 } else { self.location[level - 1] };
+            ancestors.push(Ancestor { name: &complex.name, index, children: &complex.children });
+            element = self.location.get(level).and_then(|index| complex.children.get(*index));
+        }
+        let context = match self.location.last() {
+            _ if frame => Context::Frame,
+            None => Context::Parsed(self.root),
+            // elements that are not part of the infoset follow the children of their parent
+            Some(&index) if ancestors.last().is_some_and(|parent| index < parent.children.len()) => Context::Child(index),
+            Some(_) => Context::Next(name),
+        };
+        Tree { root: self.schema, ancestors, context }
+    }
+
+    /// evaluates `expression` on the element being unparsed, called `name`, or on its model groups if `frame` is set
+    ///
+    /// Fails with a description of the problem.
+    fn evaluate(&mut self, expression: &DfdlExpression, frame: bool, name: &str) -> Result<Value, String> {
+        let tree = self.tree(frame, name);
+        expression::evaluate(expression.expression(), &tree, self)
+    }
+
+    /// `format` with the values of its runtime-valued properties evaluated on the element being unparsed,
+    /// called `name`, or on its model groups if `frame` is set
+    fn format<'f>(&mut self, format: &'f Format, frame: bool, name: &str, path: &str) -> Result<Cow<'f, Format>, UnparseError> {
+        format.with_runtime_values(|expression| self.evaluate(expression, frame, name).map(|value| value.to_string()))
+            .map_err(|reason| UnparseError::invalid(path, reason))
+    }
+
+    /// the element of the infoset at `location`, along with its declaration
+    fn element(&self, location: &[usize]) -> Result<(&'a Element<'i>, &'a ElementDecl), String> {
+        let tree = Tree { root: self.schema, ancestors: Vec::new(), context: Context::Parsed(self.root) };
+        let Some(Node::Element(element)) = tree.node(location) else { return Err("the element is not part of the infoset".to_string()) };
+        let decl = tree.decl(location).ok_or("the element is not declared by the schema")?;
+        Ok((element, decl))
+    }
+
+    /// runs `compute` with the element at `location` as the element being unparsed
+    ///
+    /// Fails if the element is already being computed, as its length or value would depend on itself.
+    fn at<T>(&mut self, location: &[usize], compute: impl FnOnce(&mut Self) -> T) -> Result<T, String> {
+        if self.pending.iter().any(|pending| pending == location) {
+            return Err("the element depends on itself".to_string());
+        }
+        let outer = std::mem::replace(&mut self.location, location.to_vec());
+        self.pending.push(location.to_vec());
+        let result = compute(self);
+        self.pending.pop();
+        self.location = outer;
+        Ok(result)
+    }
+
+    /// writes a single occurrence of `decl`
+    fn unparse_element(&mut self, decl: &ElementDecl, element: &Element<'_>, parent: &str) -> Result<(), UnparseError> {
+        let path = format!("{parent}/{}", decl.name);
+        if decl.format.calculation.input_value_calc.is_some() {
+            // calculated elements have no representation
+            return Ok(());
+        }
+        let format = self.format(&decl.format, false, &decl.name, &path)?;
+        let format = format.as_ref();
+        let primitive = match &decl.content {
+            ElementContent::Simple(simple) => Some(simple.primitive),
+            ElementContent::Complex(_) => None,
+        };
+        self.fill(skip(format, &format.leading_skip, "leadingSkip", &path)?, format, &path)?;
+        self.align(alignment(format, primitive, &path)?, format, &path)?;
+        self.delimiter(format, &format.delimiters.initiator, "initiator", &path)?;
+        if format.length.length_kind == Some(LengthKind::Prefixed) {
+            self.prefixed(decl, format, &path, |unparser| unparser.unparse_content(decl, format, element, &path))?;
+        }
+        else {
+            self.unparse_content(decl, format, element, &path)?;
+        }
+        self.delimiter(format, &format.delimiters.terminator, "terminator", &path)?;
+        self.fill(skip(format, &format.trailing_skip, "trailingSkip", &path)?, format, &path)?;
+        Ok(())
+    }
+
+    /// writes the content of `element` with the properties of `format`, without its delimiters
+    fn unparse_content(&mut self, decl: &ElementDecl, format: &Format, element: &Element<'_>, path: &str) -> Result<(), UnparseError> {
+        if let (Some(calculation), ElementContent::Simple(simple)) = (&format.calculation.output_value_calc, &decl.content) {
+            // the value of the element in the infoset is replaced by the calculated one
+            let data = self.evaluate(calculation, false, &decl.name).and_then(|value| value.to_data(simple.primitive))
+                .map_err(|reason| UnparseError::invalid(path, format!("outputValueCalc '{{{}}}': {reason}", calculation.as_str())))?;
+            return self.unparse_simple(format, simple, &data, path);
+        }
+        match (&decl.content, element) {
+            (ElementContent::Simple(simple), Element::SimpleElement(SimpleElement { data: Some(data), .. })) =>
+                self.unparse_simple(format, simple, data, path),
+            (ElementContent::Simple(_), Element::SimpleElement(_)) => Err(UnparseError::invalid(path, "the element has no value")),
+            (ElementContent::Complex(group), Element::ComplexElement(ComplexElement { children, .. })) => {
+                let start = self.output.position();
+                let mut index = 0;
+                self.unparse_group(group, children, &mut index, path)?;
+                if let Some(extra) = children.get(index) {
+                    return Err(UnparseError::UnexpectedElement { element: format!("{path}/{}", extra.name()) });
+                }
+                // the other lengths are given by the content itself
+                if format.length.length_kind == Some(LengthKind::Explicit) {
+                    let length = explicit_complex_length(format, path)?;
+                    let written = self.output.position() - start;
+                    if written > length {
+                        return Err(UnparseError::invalid(path, format!("the content takes {written} bits, but the length is {length} bits")));
+                    }
+                    self.fill(length - written, format, path)?;
+                }
+                Ok(())
+            },
+            (ElementContent::Simple(_), _) => Err(UnparseError::invalid(path, "expected a simple element")),
+            (ElementContent::Complex(_), _) => Err(UnparseError::invalid(path, "expected a complex element")),
+        }
+    }
+
+    /// the number of bits `write` writes at the current position, discarding them
+    fn measure(&mut self, write: impl FnOnce(&mut Self) -> Result<(), UnparseError>) -> Result<u64, UnparseError> {
+        let (mark, start) = (self.output.mark(), self.output.position());
+        let result = write(self);
+        let bits = self.output.position() - start;
+        self.output.reset(mark);
+        result.map(|()| bits)
+    }
+
+    /// writes the content written by `content` preceded by its length, for elements with `lengthKind="prefixed"`
+    fn prefixed(&mut self, decl: &ElementDecl, format: &Format, path: &str, content: impl Fn(&mut Self) -> Result<(), UnparseError>) -> Result<(), UnparseError> {
+        let prefix = decl.prefix_length_type.as_deref()
+            .ok_or_else(|| FormatError::MissingProperty { property: "prefixLengthType", component: path.to_string() })?;
+        if prefix.format.length.length_kind == Some(LengthKind::Prefixed) {
+            return Err(FormatError::unsupported(path, "the prefixLengthType can not have lengthKind 'prefixed'").into());
+        }
+        let units = *required(&format.length.length_units, "lengthUnits", path)?;
+        let in_units = |bits: u64| -> Result<u64, UnparseError> {
+            match units {
+                LengthUnits::Bits => Ok(bits),
+                LengthUnits::Bytes => Ok(bits.div_ceil(8)),
+                LengthUnits::Characters => match encoding(format, path)?.fixed_width() {
+                    Some(width) => Ok(bits / 8 / width),
+                    None => Err(FormatError::unsupported(path, "prefixed lengths in characters of a variable width encoding are not supported yet").into()),
+                },
+            }
+        };
+        let length = in_units(self.measure(&content)?)?;
+        let value = |length: u64| integer_data(prefix.primitive, length.into())
+            .ok_or_else(|| UnparseError::invalid(path, format!("the length {length} can not be represented as {:?}", prefix.primitive)));
+        let mut prefix_value = length;
+        if *required(&format.length.prefix_includes_prefix_length, "prefixIncludesPrefixLength", path)? {
+            if units == LengthUnits::Characters {
+                return Err(FormatError::unsupported(path, "prefixIncludesPrefixLength is not supported with lengthUnits 'characters' yet").into());
+            }
+            // the length of the prefix may depend on its value, repeat until both agree
+            loop {
+                let data = value(prefix_value)?;
+                let total = length + in_units(self.measure(|unparser| unparser.unparse_simple(&prefix.format, prefix, &data, path))?)?;
+                if total == prefix_value {
+                    break;
+                }
+                prefix_value = total;
+            }
+        }
+        self.unparse_simple(&prefix.format, prefix, &value(prefix_value)?, path)?;
+        content(self)
+    }
+
+    /// writes the content of a complex element from `children`, starting at `index`,
+    /// `path` being the path of that element
+    fn unparse_group(&mut self, group: &ModelGroup, children: &[Element<'_>], index: &mut usize, path: &str) -> Result<(), UnparseError> {
+        let format = self.format(&group.format, true, "", path)?;
+        let format = format.as_ref();
+        self.fill(skip(format, &format.leading_skip, "leadingSkip", path)?, format, path)?;
+        self.align(alignment(format, None, path)?, format, path)?;
+        self.delimiter(format, &format.delimiters.initiator, "initiator", path)?;
+        match group.kind {
+            GroupKind::Sequence => {
+                let separator = match first_delimiter(format, &format.sequence.separator, "separator", path)? {
+                    Some(bytes) => Some(Separator {
+                        bytes,
+                        position: *required(&format.sequence.separator_position, "separatorPosition", path)?,
+                        policy: *required(&format.sequence.separator_suppression_policy, "separatorSuppressionPolicy", path)?,
+                    }),
+                    None => None,
+                };
+                let mut items = Items::new();
+                for term in &group.terms {
+                    self.unparse_term(term, children, index, path, separator.as_ref().map(|separator| (separator, format)), &mut items)?;
+                }
+                // the separators of trailing empty occurrences are suppressed
+                if let Some((mark, _)) = items.trailing_empty {
+                    self.output.reset(mark);
+                }
+            },
+            GroupKind::Choice => {
+                let (mark, start) = (self.output.mark(), *index);
+                let mut empty_branch = None;
+                let mut matched = false;
+                for term in &group.terms {
+                    let mut branch_index = start;
+                    match self.unparse_term(term, children, &mut branch_index, path, None, &mut Items::new()) {
+                        Ok(()) if branch_index > start => {
+                            *index = branch_index;
+                            matched = true;
+                            break;
+                        },
+                        Ok(()) => { empty_branch.get_or_insert(term); },
+                        Err(e) if e.is_processing_error() => {},
+                        Err(e) => return Err(e),
+                    }
+                    self.output.reset(mark);
+                }
+                if !matched {
+                    // no branch consumes elements of the infoset, fall back to the first one that can be empty
+                    let branch = empty_branch.ok_or_else(|| UnparseError::NoMatchingBranch { element: path.to_string() })?;
+                    self.unparse_term(branch, children, index, path, None, &mut Items::new())?;
+                }
+            },
+        }
+        self.delimiter(format, &format.delimiters.terminator, "terminator", path)?;
+        self.fill(skip(format, &format.trailing_skip, "trailingSkip", path)?, format, path)?;
+        Ok(())
+    }
+
+    /// writes the items of `term`, each one separated by `separator`
+    fn unparse_term(&mut self, term: &Term, children: &[Element<'_>], index: &mut usize, path: &str, separator: Option<(&Separator, &Format)>, items: &mut Items) -> Result<(), UnparseError> {
+        match term {
+            Term::Element(decl) => {
+                let count = children[*index..].iter().take_while(|child| child.name() == decl.name).count();
+                let element = || format!("{path}/{}", decl.name);
+                let kind = if decl.is_array() || decl.is_optional() {
+                    Some(*required(&decl.format.occurs.occurs_count_kind, "occursCountKind", &element())?)
+                }
+                else { None };
+                // the number of occurrences of the other kinds is only given by the data
+                let counted = matches!(kind, None | Some(OccursCountKind::Fixed | OccursCountKind::Implicit));
+                if counted && (count as u64) < decl.min_occurs {
+                    return Err(UnparseError::MissingElement { element: element() });
+                }
+                // arrays ended by a stop value are bounded by maxOccurs all the same
+                if let MaxOccurs::Bounded(max) = decl.max_occurs {
+                    if (counted || kind == Some(OccursCountKind::StopValue)) && count as u64 > max {
+                        return Err(UnparseError::UnexpectedElement { element: element() });
+                    }
+                }
+                let policy = separator.map(|(separator, _)| separator.policy);
+                for (occurrence, child) in children[*index..*index + count].iter().enumerate() {
+                    let (mark, first) = (self.output.mark(), items.first);
+                    self.location.push(*index + occurrence);
+                    let result = self.separated(separator, items, path, |unparser| unparser.unparse_element(decl, child, path));
+                    self.location.pop();
+                    result?;
+                    if occurrence as u64 >= decl.min_occurs && items.last_empty {
+                        match policy {
+                            Some(SeparatorSuppressionPolicy::AnyEmpty) => {
+                                self.output.reset(mark);
+                                items.first = first;
+                            },
+                            Some(SeparatorSuppressionPolicy::TrailingEmpty | SeparatorSuppressionPolicy::TrailingEmptyStrict) => {
+                                items.trailing_empty.get_or_insert((mark, first));
+                            },
+                            _ => {},
+                        }
+                    }
+                }
+                *index += count;
+                if kind == Some(OccursCountKind::StopValue) {
+                    // the array ends with an occurrence holding the stop value
+                    let stop = stop_value(decl, &element())?;
+                    // which is not part of the infoset, it follows the last child
+                    self.location.push(children.len());
+                    let result = self.separated(separator, items, path, |unparser| unparser.unparse_element(decl, &stop, path));
+                    self.location.pop();
+                    result?;
+                }
+                if policy == Some(SeparatorSuppressionPolicy::Never) && counted && count as u64 >= decl.min_occurs {
+                    // separators are written for every possible occurrence, those of the absent ones enclosing nothing
+                    let MaxOccurs::Bounded(max) = decl.max_occurs
+                        else { return Err(FormatError::unsupported(&element(), "separatorSuppressionPolicy 'never' requires a bounded maxOccurs").into()) };
+                    for _ in count as u64..max {
+                        self.separated(separator, items, path, |_| Ok(()))?;
+                    }
+                }
+                Ok(())
+            },
+            Term::Group(group) => self.separated(separator, items, path, |unparser| unparser.unparse_group(group, children, index, path)),
+        }
+    }
+
+    /// writes a single item of a sequence with `separator`
+    fn separated(&mut self, separator: Option<(&Separator, &Format)>, items: &mut Items, path: &str, item: impl FnOnce(&mut Self) -> Result<(), UnparseError>) -> Result<(), UnparseError> {
+        if let Some((separator, format)) = separator {
+            if separator.position == SeparatorPosition::Prefix || (separator.position == SeparatorPosition::Infix && !items.first) {
+                self.write(&separator.bytes, format, path)?;
+            }
+        }
+        let start = self.output.position();
+        item(self)?;
+        items.first = false;
+        items.last_empty = self.output.position() == start;
+        if !items.last_empty {
+            items.trailing_empty = None;
+        }
+        if let Some((separator, format)) = separator.filter(|(separator, _)| separator.position == SeparatorPosition::Postfix) {
+            self.write(&separator.bytes, format, path)?;
+        }
+        Ok(())
+    }
+
+    /// writes the value of a simple element
+    fn unparse_simple(&mut self, format: &Format, simple: &SimpleType, data: &Data<'_>, path: &str) -> Result<(), UnparseError> {
+        let primitive = simple.primitive;
+        match (primitive, data) {
+            (PrimitiveType::String, Data::String(text)) => {
+                let padding = padding(format, &format.text.text_string_pad_character, format.text.text_string_justification, path)?;
+                let truncate = format.text.truncate_specified_length_string == Some(true);
+                self.write_text(text, format, simple.max_length, padding, truncate, path)
+            },
+            (PrimitiveType::HexBinary, Data::HexBinary(bytes)) => {
+                let length = match required(&format.length.length_kind, "lengthKind", path)? {
+                    LengthKind::Explicit => binary_length(format, primitive, path)?,
+                    LengthKind::Implicit => required(&simple.max_length, "maxLength", path)?.saturating_mul(8),
+                    // the other lengths are given by the value itself
+                    _ => bytes.len() as u64 * 8,
+                };
+                if !length.is_multiple_of(8) {
+                    return Err(FormatError::unsupported(path, "the length of hexBinary values must be a multiple of 8 bits").into());
+                }
+                if bytes.len() as u64 * 8 > length {
+                    return Err(UnparseError::invalid(path, format!("{} bytes do not fit into the length of {} bytes", bytes.len(), length / 8)));
+                }
+                self.write(bytes, format, path)?;
+                self.fill(length - bytes.len() as u64 * 8, format, path)?;
+                Ok(())
+            },
+            (PrimitiveType::String | PrimitiveType::HexBinary, _) => Err(UnparseError::invalid(path, format!("expected a {primitive:?} value, found {}", data.typename()))),
+            _ if is_text(format, primitive, path)? => self.unparse_text(format, primitive, data, path),
+            _ => self.unparse_binary(format, primitive, data, path),
+        }
+    }
+
+    /// writes a number or boolean in binary representation
+    fn unparse_binary(&mut self, format: &Format, primitive: PrimitiveType, data: &Data<'_>, path: &str) -> Result<(), UnparseError> {
+        if is_number(primitive) {
+            let rep = *required(&format.binary_number.binary_number_rep, "binaryNumberRep", path)?;
+            if rep != BinaryNumberRep::Binary {
+                return self.unparse_packed(format, primitive, rep, data, path);
+            }
+        }
+        let bits = match required(&format.length.length_kind, "lengthKind", path)? {
+            LengthKind::Explicit | LengthKind::Implicit => binary_length(format, primitive, path)?,
+            // the other lengths are given by the value, which is written in as few bytes as possible
+            _ => match primitive {
+                PrimitiveType::Float | PrimitiveType::Double | PrimitiveType::Boolean => implicit_binary_length(primitive).unwrap_or_default(),
+                _ => {
+                    let value = binary_integer(format, primitive, data, path)?;
+                    let signed = is_signed(format, primitive, path)?;
+                    let bytes = (1..8).find(|bytes| if signed { matches!(value >> (bytes * 8 - 1), 0 | -1) } else { value >> (bytes * 8) == 0 });
+                    bytes.unwrap_or(8) * 8
+                },
+            },
+        };
+        if bits > 64 {
+            return Err(FormatError::unsupported(path, "binary numbers and booleans can be at most 64 bits long").into());
+        }
+        let byte_order = byte_order(format, bits, path)?;
+        let value = match primitive {
+            PrimitiveType::Float | PrimitiveType::Double => {
+                if *runtime_value(&format.binary_number.binary_float_rep, "binaryFloatRep", path)? != BinaryFloatRep::Ieee {
+                    return Err(FormatError::unsupported(path, "binaryFloatRep 'ibm390Hex' is not supported").into());
+                }
+                let value = float_value(data).ok_or_else(|| UnparseError::invalid(path, format!("expected a {primitive:?} value, found {}", data.typename())))?;
+                match (primitive, bits) {
+                    (PrimitiveType::Float, 32) => u64::from((value as f32).to_bits()),
+                    (PrimitiveType::Double, 64) => value.to_bits(),
+                    _ => return Err(FormatError::unsupported(path, "binary floating point numbers must be 4 (float) or 8 (double) bytes long").into()),
+                }
+            },
+            PrimitiveType::Boolean => {
+                let Data::Boolean(value) = data
+                    else { return Err(UnparseError::invalid(path, format!("expected a Boolean value, found {}", data.typename()))) };
+                let false_rep = *required(&format.boolean.binary_boolean_false_rep, "binaryBooleanFalseRep", path)?;
+                match required(&format.boolean.binary_boolean_true_rep, "binaryBooleanTrueRep", path)? {
+                    _ if !value => false_rep,
+                    BinaryBooleanTrueRep::Value(true_rep) => *true_rep,
+                    BinaryBooleanTrueRep::NotFalse => !false_rep,
+                }
+            },
+            PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time =>
+                return Err(FormatError::unsupported(path, "binary calendars are not supported yet").into()),
+            _ => {
+                let value = binary_integer(format, primitive, data, path)?;
+                let (min, max) = match (bits, is_signed(format, primitive, path)?) {
+                    (0, _) => (0, 0),
+                    (_, true) => (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1),
+                    (_, false) => (0, (1i128 << bits) - 1),
+                };
+                if value < min || value > max {
+                    return Err(UnparseError::invalid(path, format!("{value} does not fit into {bits} bits")));
+                }
+                value as u64
+            },
+        };
+        self.output.write_bits(value, bits, bit_order(format, path)?, byte_order).map_err(|e| UnparseError::from_stream(e, path))
+    }
+
+    /// writes a `packed`, `bcd` or `ibm4690Packed` number
+    fn unparse_packed(&mut self, format: &Format, primitive: PrimitiveType, rep: BinaryNumberRep, data: &Data<'_>, path: &str) -> Result<(), UnparseError> {
+        let packed = PackedFormat::new(format, rep, path)?;
+        let length = match required(&format.length.length_kind, "lengthKind", path)? {
+            LengthKind::Explicit | LengthKind::Implicit => {
+                let bits = binary_length(format, primitive, path)?;
+                if !bits.is_multiple_of(8) {
+                    return Err(FormatError::unsupported(path, format!("the length of {} numbers must be a multiple of 8 bits", rep.as_str())).into());
+                }
+                Some(usize::try_from(bits / 8).map_err(|_| UnparseError::invalid(path, "length is too large"))?)
+            },
+            // the other lengths are given by the value, which is written in as few bytes as possible
+            _ => None,
+        };
+        let number = number_value(primitive, data, path)?;
+        let bytes = packed.encode(number, !is_signed(format, primitive, path)?, length).map_err(|reason| UnparseError::invalid(path, reason))?;
+        self.write(&bytes, format, path)
+    }
+
+    /// writes a number or boolean in text representation
+    fn unparse_text(&mut self, format: &Format, primitive: PrimitiveType, data: &Data<'_>, path: &str) -> Result<(), UnparseError> {
+        let (text, padding) = match primitive {
+            PrimitiveType::Boolean => {
+                let Data::Boolean(value) = data
+                    else { return Err(UnparseError::invalid(path, format!("expected a Boolean value, found {}", data.typename()))) };
+                let (reps, property) = if *value { (&format.boolean.text_boolean_true_rep, "textBooleanTrueRep") }
+                    else { (&format.boolean.text_boolean_false_rep, "textBooleanFalseRep") };
+                let text = runtime_value(reps, property, path)?.first().and_then(DfdlLiteral::as_text)
+                    .ok_or_else(|| FormatError::unsupported(path, format!("{property} must start with a plain text representation")))?;
+                (text, padding(format, &format.boolean.text_boolean_pad_character, format.boolean.text_boolean_justification, path)?)
+            },
+            PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time =>
+                return Err(FormatError::unsupported(path, "text calendars are not supported yet").into()),
+            _ => {
+                let zoned = required(&format.text_number.text_number_rep, "textNumberRep", path)? == &TextNumberRep::Zoned;
+                let text = match primitive {
+                    PrimitiveType::Float | PrimitiveType::Double if zoned =>
+                        return Err(FormatError::unsupported(path, "floating point numbers can not be zoned").into()),
+                    PrimitiveType::Float | PrimitiveType::Double => {
+                        let value = float_value(data).ok_or_else(|| UnparseError::invalid(path, format!("expected a {primitive:?} value, found {}", data.typename())))?;
+                        TextNumberFormat::new(format, path)?.format_float(value)?
+                    },
+                    _ if zoned => ZonedFormat::new(format, path)?.format(number_value(primitive, data, path)?)?,
+                    PrimitiveType::Decimal => TextNumberFormat::new(format, path)?.format_number(number_value(primitive, data, path)?)?,
+                    _ => TextNumberFormat::new(format, path)?.format_integer(integer_value(primitive, data, path)?)?,
+                };
+                (text, padding(format, &format.text_number.text_number_pad_character, format.text_number.text_number_justification, path)?)
+            },
+        };
+        self.write_text(&text, format, None, padding, false, path)
+    }
+
+    /// writes `text` with the length given by `lengthKind`, padded or truncated as needed,
+    /// `max_length` being the length of strings with `lengthKind="implicit"`
+    fn write_text(&mut self, text: &str, format: &Format, max_length: Option<u64>, padding: Option<(char, TextJustification)>, truncate: bool, path: &str) -> Result<(), UnparseError> {
+        let encoding = encoding(format, path)?;
+        let policy = format.encoding_error_policy.unwrap_or(EncodingErrorPolicy::Replace);
+        let (length, units) = match required(&format.length.length_kind, "lengthKind", path)? {
+            LengthKind::Explicit => (*runtime_value(&format.length.length, "length", path)?, *required(&format.length.length_units, "lengthUnits", path)?),
+            LengthKind::Implicit => match max_length {
+                Some(length) => (length, LengthUnits::Characters),
+                None => return Err(FormatError::unsupported(path, "lengthKind 'implicit' requires a maxLength facet for text").into()),
+            },
+            // the other lengths are given by the text itself
+            _ => {
+                self.text = Some(text.chars().count() as u64);
+                return self.write(&encode(encoding, text, policy, path)?, format, path);
+            },
+        };
+        let length = usize::try_from(length).map_err(|_| UnparseError::invalid(path, "length is too large"))?;
+        let size = |text: &str| -> Result<usize, UnparseError> {
+            match units {
+                LengthUnits::Characters => Ok(text.chars().count()),
+                _ => Ok(encode(encoding, text, policy, path)?.len()),
+            }
+        };
+        if units == LengthUnits::Bits {
+            return Err(FormatError::unsupported(path, "lengthUnits 'bits' is not allowed for text").into());
+        }
+        let mut text = text.to_string();
+        if size(&text)? > length {
+            if !truncate {
+                return Err(UnparseError::invalid(path, format!("'{text}' is longer than the length of {length} {}", units.as_str())));
+            }
+            while size(&text)? > length {
+                text.pop();
+            }
+        }
+        if let Some((pad, justification)) = padding {
+            let missing = (length - size(&text)?) / size(pad.encode_utf8(&mut [0; 4]))?.max(1);
+            let (left, right) = match justification {
+                TextJustification::Left => (0, missing),
+                TextJustification::Right => (missing, 0),
+                TextJustification::Center => (missing / 2, missing - missing / 2),
+            };
+            text = std::iter::repeat_n(pad, left).chain(text.chars()).chain(std::iter::repeat_n(pad, right)).collect();
+        }
+        let bytes = encode(encoding, &text, policy, path)?;
+        let missing = match units {
+            LengthUnits::Characters => length - text.chars().count(),
+            _ => length - bytes.len(),
+        };
+        self.text = Some(text.chars().count() as u64);
+        self.write(&bytes, format, path)?;
+        // space left over by the padding is filled with fillByte
+        self.fill(missing as u64 * 8, format, path)?;
+        Ok(())
+    }
+}
+
+/// the lengths and calculated values of elements are computed on demand,
+/// so that expressions may refer to elements that are not written yet
+impl Environment for Unparser<'_, '_> {
+    /// the length of the element at `location`, measured by unparsing it at the current position
+    ///
+    /// This also works for elements that follow the element being unparsed, as long as their lengths
+    /// do not depend on it in turn. The value of a simple element is measured without its specified length,
+    /// so that the length may be calculated from it.
+    fn length(&mut self, location: &[usize], value: bool) -> Result<Length, String> {
+        let (element, decl) = self.element(location)?;
+        let path = format!("/{}", decl.name);
+        let simple = matches!(decl.content, ElementContent::Simple(_));
+        let text = self.text.take();
+        let result = self.at(location, |unparser| {
+            let mut format = Cow::Borrowed(&decl.format);
+            if value && simple {
+                // the value alone is written with a delimited length, without padding
+                let format = format.to_mut();
+                format.length.length_kind = Some(LengthKind::Delimited);
+                format.length.length = None;
+                format.text.text_pad_kind = None;
+            }
+            let format = unparser.format(&format, false, &decl.name, &path)?;
+            unparser.measure(|unparser| unparser.unparse_content(decl, &format, element, &path))
+        });
+        let characters = std::mem::replace(&mut self.text, text).filter(|_| simple);
+        let bits = result?.map_err(|e| format!("the length can not be measured: {e}"))?;
+        Ok(Length { bits, characters })
+    }
+
+    fn calculated(&mut self, location: &[usize]) -> Result<Option<Value>, String> {
+        let Ok((_, decl)) = self.element(location) else { return Ok(None) };
+        let Some(calculation) = &decl.format.calculation.output_value_calc else { return Ok(None) };
+        self.at(location, |unparser| unparser.evaluate(calculation, false, &decl.name))?.map(Some)
+    }
+}
+
+/// the bytes of the first delimiter of `delimiters`, `None` if it is empty
+fn first_delimiter(format: &Format, delimiters: &Option<RuntimeValue<Vec<DfdlLiteral>>>, property: &'static str, path: &str) -> Result<Option<Vec<u8>>, FormatError> {
+    if delimiters.is_none() {
+        return Ok(None);
+    }
+    match runtime_value(delimiters, property, path)?.first() {
+        Some(delimiter) if !delimiter.is_empty() => literal_bytes(format, delimiter, path).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// the bytes written for `literal`
+fn literal_bytes(format: &Format, literal: &DfdlLiteral, path: &str) -> Result<Vec<u8>, FormatError> {
+    let mut bytes = Vec::new();
+    let mut encoding = None;
+    for part in literal.parts() {
+        let text = match part {
+            LiteralPart::Byte(byte) => {
+                bytes.push(*byte);
+                continue;
+            },
+            LiteralPart::Char(c) => c.to_string(),
+            LiteralPart::Class(CharClass::NewLine) => {
+                let new_line = runtime_value(&format.delimiters.output_new_line, "outputNewLine", path)?;
+                bytes.extend(literal_bytes(format, new_line, path)?);
+                continue;
+            },
+            // the whitespace classes are written as a single space, unless they also match nothing
+            LiteralPart::Class(CharClass::Whitespace | CharClass::WhitespacePlus) => " ".to_string(),
+            LiteralPart::Class(CharClass::WhitespaceStar | CharClass::Empty) => continue,
+        };
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => *encoding.insert(super::encoding(format, path)?),
+        };
+        let encoded = encoding.encode(&text, EncodingErrorPolicy::Error)
+            .ok_or_else(|| FormatError::unsupported(path, format!("'{literal}' can not be written in {}", encoding.name())))?;
+        bytes.extend(encoded.iter());
+    }
+    Ok(bytes)
+}
+
+fn encode<'t>(encoding: Encoding, text: &'t str, policy: EncodingErrorPolicy, path: &str) -> Result<std::borrow::Cow<'t, [u8]>, UnparseError> {
+    encoding.encode(text, policy).ok_or_else(|| UnparseError::invalid(path, format!("'{text}' can not be written in {}", encoding.name())))
+}
+
+/// the pad character and justification of text, if `textPadKind` is `padChar`
+fn padding(format: &Format, pad_character: &Option<DfdlLiteral>, justification: Option<TextJustification>, path: &str) -> Result<Option<(char, TextJustification)>, FormatError> {
+    if format.text.text_pad_kind != Some(TextPadKind::PadChar) {
+        return Ok(None);
+    }
+    let pad = match required(pad_character, "pad character", path)?.parts() {
+        [LiteralPart::Char(pad)] => *pad,
+        _ => return Err(FormatError::unsupported(path, "pad characters must be a single character")),
+    };
+    Ok(Some((pad, *required(&justification, "justification", path)?)))
+}
+
+/// an occurrence of `decl` holding the first of its `occursStopValue`s
+fn stop_value(decl: &ElementDecl, path: &str) -> Result<Element<'static>, FormatError> {
+    let stop_values = required(&decl.format.occurs.occurs_stop_value, "occursStopValue", path)?;
+    let ElementContent::Simple(simple) = &decl.content
+        else { return Err(FormatError::unsupported(path, "occursCountKind 'stopValue' requires a simple element")) };
+    let text = stop_values.first().and_then(DfdlLiteral::as_text)
+        .ok_or_else(|| FormatError::unsupported(path, "occursStopValue has to consist of plain characters"))?;
+    let data = match simple.primitive {
+        PrimitiveType::String => Some(Data::String(text.clone().into())),
+        PrimitiveType::Float => text.trim().parse().ok().map(Data::Float),
+        PrimitiveType::Double => text.trim().parse().ok().map(Data::Double),
+        PrimitiveType::Boolean => text.trim().parse().ok().map(Data::Boolean),
+        PrimitiveType::Decimal => text.trim().parse().ok().map(Data::Decimal),
+        primitive => text.trim().parse().ok().and_then(|value| integer_data(primitive, value)),
+    };
+    let data = data.ok_or_else(|| FormatError::unsupported(path, format!("occursStopValue '{text}' is not a value of the type of the element")))?;
+    Ok(Element::SimpleElement(SimpleElement {
+        schema: String::new(),
+        namespace: String::new(),
+        name: decl.name.clone(),
+        nilled: false,
+        array: true,
+        valid: true,
+        union_member_schema: String::new(),
+        data: Some(data),
+    }))
+}
+
+/// the value of floating point infoset data
+fn float_value(data: &Data<'_>) -> Option<f64> {
+    match *data {
+        Data::Double(value) => Some(value),
+        Data::Float(value) => Some(value.into()),
+        _ => None,
+    }
+}
+
+/// the value of decimal or integer infoset data
+fn number_value(primitive: PrimitiveType, data: &Data<'_>, path: &str) -> Result<Number, UnparseError> {
+    match primitive {
+        PrimitiveType::Decimal => Number::from_data(data)
+            .ok_or_else(|| UnparseError::invalid(path, format!("expected a Decimal value, found {}", data.typename()))),
+        _ => Ok(Number::from_integer(integer_value(primitive, data, path)?)),
+    }
+}
+
+/// the integer written for a binary number: the value of integers,
+/// and the digits of decimals with `binaryDecimalVirtualPoint` fraction digits
+fn binary_integer(format: &Format, primitive: PrimitiveType, data: &Data<'_>, path: &str) -> Result<i128, UnparseError> {
+    if primitive != PrimitiveType::Decimal {
+        return integer_value(primitive, data, path);
+    }
+    let virtual_point = virtual_point(format, path)?;
+    number_value(primitive, data, path)?.shifted(virtual_point).to_integer().ok_or_else(|| UnparseError::invalid(path,
+        format!("the value has more fraction digits than binaryDecimalVirtualPoint allows ({virtual_point}) or is out of range")))
+}
+
+/// the value of integer infoset data, checked against the value space of `primitive`
+fn integer_value(primitive: PrimitiveType, data: &Data<'_>, path: &str) -> Result<i128, UnparseError> {
+    let value = super::integer_value(data)
+        .ok_or_else(|| UnparseError::invalid(path, format!("expected a {primitive:?} value, found {}", data.typename())))?;
+    let (min, max): (i128, i128) = match primitive {
+        PrimitiveType::Integer | PrimitiveType::Int => (i32::MIN.into(), i32::MAX.into()),
+        PrimitiveType::NonNegativeInteger | PrimitiveType::UnsignedInt => (0, u32::MAX.into()),
+        PrimitiveType::Long => (i64::MIN.into(), i64::MAX.into()),
+        PrimitiveType::Short => (i16::MIN.into(), i16::MAX.into()),
+        PrimitiveType::Byte => (i8::MIN.into(), i8::MAX.into()),
+        PrimitiveType::UnsignedLong => (0, u64::MAX.into()),
+        PrimitiveType::UnsignedShort => (0, u16::MAX.into()),
+        PrimitiveType::UnsignedByte => (0, u8::MAX.into()),
+        _ => return Err(UnparseError::invalid(path, format!("{primitive:?} is not an integer type"))),
+    };
+    if value < min || value > max {
+        return Err(UnparseError::invalid(path, format!("{value} is out of range for {primitive:?}")));
+    }
+    Ok(value)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const FORMAT: &str = r##"
+        representation="binary" binaryNumberRep="binary" binaryFloatRep="ieee" byteOrder="bigEndian"
+        lengthKind="implicit" lengthUnits="bytes" encoding="utf-8" encodingErrorPolicy="replace"
+        initiator="" terminator="" separator="" leadingSkip="0" trailingSkip="0" alignment="implicit"
+        alignmentUnits="bytes" occursCountKind="implicit" textTrimKind="none" textPadKind="none" fillByte="%#r00;" bitOrder="mostSignificantBitFirst"
+        binaryBooleanTrueRep="1" binaryBooleanFalseRep="0" textNumberRep="standard" separatorPosition="infix" separatorSuppressionPolicy="anyEmpty" ignoreCase="no"
+        textNumberPattern="#0" textNumberCheckPolicy="strict" textNumberRounding="pattern" textStandardBase="10""##;
+
+    fn schema(elements: &str) -> CompiledSchema {
+        let source = format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/"><dfdl:format {FORMAT}/></xs:appinfo></xs:annotation>
+            {elements}
+            </xs:schema>"#);
+        CompiledSchema::new(&source).unwrap()
+    }
+
+    fn unparsed(schema: &CompiledSchema, infoset: &Infoset<'_>) -> Result<Vec<u8>, UnparseError> {
+        let mut output = Vec::new();
+        let root = infoset.root_element.as_ref().map_or("", Element::name);
+        unparse(schema, root, infoset, &mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn round_trips_binary_data() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="a" type="xs:byte"/>
+            <xs:element name="b" type="xs:int" dfdl:byteOrder="littleEndian"/>
+            <xs:element name="c" type="xs:double"/>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="4"/>
+            <xs:element name="item" type="xs:unsignedShort" maxOccurs="3"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let mut data = vec![0xFF, 0, 0, 0, 0x2A, 0, 0, 0];
+        data.extend(1.5f64.to_be_bytes());
+        data.extend(b"abcd\x00\x01\x00\x02");
+        let infoset = parse(&schema, "record", &data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+        assert!(matches!(unparse(&schema, "other", &infoset, Vec::new()), Err(UnparseError::UnknownRoot(root)) if root == "other"));
+    }
+
+    #[test]
+    fn round_trips_packed_decimals() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="balance" type="xs:long" dfdl:binaryNumberRep="packed" dfdl:lengthKind="explicit" dfdl:length="4"
+                dfdl:binaryPackedSignCodes="C D F C" dfdl:binaryDecimalVirtualPoint="0" dfdl:binaryNumberCheckPolicy="lax"/>
+            <xs:element name="cents" type="xs:unsignedInt" dfdl:binaryNumberRep="packed" dfdl:lengthKind="explicit" dfdl:length="2"
+                dfdl:binaryPackedSignCodes="C D F C" dfdl:binaryDecimalVirtualPoint="-2" dfdl:binaryNumberCheckPolicy="strict"/>
+            <xs:element name="code" type="xs:int" dfdl:binaryNumberRep="bcd" dfdl:lengthKind="explicit" dfdl:length="2"
+                dfdl:binaryDecimalVirtualPoint="0" dfdl:binaryNumberCheckPolicy="strict"/>
+            <xs:element name="delta" type="xs:short" dfdl:binaryNumberRep="ibm4690Packed" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="length"
+                dfdl:prefixIncludesPrefixLength="no" dfdl:binaryDecimalVirtualPoint="0" dfdl:binaryNumberCheckPolicy="strict"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:simpleType name="length"><xs:restriction base="xs:unsignedByte"/></xs:simpleType>"#);
+        let data = [0x00, 0x01, 0x23, 0x4B, 0x12, 0x3F, 0x09, 0x87, 0x02, 0xD1, 0x23];
+        let infoset = parse(&schema, "record", &data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), [0x00, 0x01, 0x23, 0x4D, 0x12, 0x3F, 0x09, 0x87, 0x02, 0xD1, 0x23]);
+        assert!(parse(&schema, "record", &[0x00, 0x01, 0x23, 0x4D, 0x12, 0x3A, 0x09, 0x87, 0x01, 0x01]).is_err());
+    }
+
+    #[test]
+    fn round_trips_bit_fields() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="a" type="xs:unsignedByte" dfdl:lengthKind="explicit" dfdl:length="3" dfdl:lengthUnits="bits"/>
+            <xs:element name="b" type="xs:byte" dfdl:lengthKind="explicit" dfdl:length="5" dfdl:lengthUnits="bits" dfdl:alignment="1" dfdl:alignmentUnits="bits"/>
+            <xs:element name="c" type="xs:unsignedShort" dfdl:lengthKind="explicit" dfdl:length="11" dfdl:lengthUnits="bits" dfdl:alignment="1" dfdl:alignmentUnits="bits"/>
+            <xs:element name="d" type="xs:short" dfdl:lengthKind="explicit" dfdl:length="11" dfdl:lengthUnits="bits"
+                dfdl:alignment="1" dfdl:bitOrder="leastSignificantBitFirst" dfdl:byteOrder="littleEndian"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = [0b1011_0011, 0b0111_1000, 0b0110_0000, 0b1111_1000, 0b0000_0111];
+        let infoset = parse(&schema, "record", &data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+    }
+
+    #[test]
+    fn fills_alignment_and_skips() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence dfdl:trailingSkip="1" dfdl:fillByte="%#rDD;">
+            <xs:element name="a" type="xs:unsignedByte"/>
+            <xs:element name="b" type="xs:unsignedInt" dfdl:fillByte="%#rEE;"/>
+            <xs:element name="c" type="xs:unsignedByte" dfdl:leadingSkip="2" dfdl:trailingSkip="1" dfdl:fillByte="%#rEE;"/>
+            <xs:element name="d" type="xs:unsignedShort" dfdl:leadingSkip="3" dfdl:alignmentUnits="bits"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = [1, 0, 0, 0, 0, 0, 0, 2, 0, 0, 3, 0, 0, 0, 0, 4, 0];
+        let infoset = parse(&schema, "record", &data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), [1, 0xEE, 0xEE, 0xEE, 0, 0, 0, 2, 0xEE, 0xEE, 3, 0xEE, 0, 0, 0, 4, 0xDD]);
+    }
+
+    #[test]
+    fn rejects_lengths_beyond_64_bits() {
+        let infoset = parse(&schema(r#"<xs:element name="a" type="xs:unsignedByte"/>"#), "a", &[0]).unwrap();
+        for properties in [r#"dfdl:alignment="4611686018427387904""#, r#"dfdl:lengthKind="explicit" dfdl:length="4611686018427387904""#] {
+            let schema = schema(&format!(r#"<xs:element name="a" type="xs:unsignedByte" {properties}/>"#));
+            assert!(matches!(unparsed(&schema, &infoset), Err(UnparseError::Format(FormatError::Unsupported { .. }))), "{properties}");
+        }
+    }
+
+    #[test]
+    fn round_trips_every_length_kind() {
+        let schema = schema(r#"<xs:simpleType name="length"><xs:restriction base="xs:unsignedByte"/></xs:simpleType>
+            <xs:simpleType name="code"><xs:restriction base="xs:string"><xs:maxLength value="4"/></xs:restriction></xs:simpleType>
+            <xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="code" type="code" dfdl:textPadKind="padChar" dfdl:textTrimKind="padChar"
+                dfdl:textStringPadCharacter="%SP;" dfdl:textStringJustification="left"/>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="length" dfdl:prefixIncludesPrefixLength="no"/>
+            <xs:element name="body" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="length" dfdl:prefixIncludesPrefixLength="yes"><xs:complexType><xs:sequence>
+                <xs:element name="item" type="xs:unsignedByte" maxOccurs="unbounded"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:element name="number" type="xs:int" dfdl:representation="text" dfdl:lengthKind="pattern" dfdl:lengthPattern="[0-9]+"/>
+            <xs:element name="rest" type="xs:hexBinary" dfdl:lengthKind="endOfParent"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = b"ab  \x02hi\x04\x01\x02\x0342\xDE\xAD";
+        let infoset = parse(&schema, "record", data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+    }
+
+    #[test]
+    fn writes_delimiters_and_padding() {
+        let schema = schema(r#"<xs:element name="record" dfdl:initiator="[" dfdl:terminator="]%NL;" dfdl:outputNewLine="%LF;"><xs:complexType>
+            <xs:sequence dfdl:separator=", ;">
+                <xs:element name="a" type="xs:int" dfdl:representation="text" dfdl:lengthKind="delimited"/>
+                <xs:element name="b" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="4"
+                    dfdl:textPadKind="padChar" dfdl:textStringPadCharacter="%SP;" dfdl:textStringJustification="right"/>
+                <xs:element name="c" type="xs:boolean" dfdl:representation="text" dfdl:lengthKind="delimited"
+                    dfdl:textBooleanTrueRep="yes y" dfdl:textBooleanFalseRep="no"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let element = |name: &str, data| Element::SimpleElement(SimpleElement {
+            schema: String::new(), namespace: String::new(), name: name.to_string(), nilled: false, array: false,
+            valid: true, union_member_schema: String::new(), data: Some(data),
+        });
+        let infoset = Infoset { dfdl_version: "1.0".to_string(), diagnostics: Vec::new(), root_element: Some(Element::ComplexElement(ComplexElement {
+            schema: String::new(), namespace: String::new(), name: "record".to_string(), nilled: false, array: false,
+            valid: true, union_member_schema: String::new(),
+            children: vec![element("a", Data::Int(-12)), element("b", Data::String("xy".into())), element("c", Data::Boolean(true))],
+        })) };
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), b"[-12,  xy,yes]\n");
+    }
+
+    #[test]
+    fn round_trips_delimited_text() {
+        let schema = schema(r#"<xs:element name="file"><xs:complexType><xs:sequence>
+            <xs:element name="line" maxOccurs="unbounded" dfdl:lengthKind="delimited" dfdl:terminator="%NL;" dfdl:outputNewLine="%CR;%LF;">
+                <xs:complexType><xs:sequence dfdl:separator=", ;" dfdl:initiator="&gt;">
+                    <xs:element name="name" type="xs:string" dfdl:lengthKind="delimited"/>
+                    <xs:element name="count" type="xs:int" dfdl:lengthKind="delimited" dfdl:representation="text"/>
+                </xs:sequence></xs:complexType>
+            </xs:element>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = b">ab,12\r\n>cd,-3\r\n";
+        let infoset = parse(&schema, "file", data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+    }
+
+    #[test]
+    fn formats_text_numbers_by_pattern() {
+        let schema = schema(r##"<xs:element name="record"><xs:complexType><xs:sequence dfdl:separator="|">
+            <xs:element name="amount" type="xs:double" dfdl:representation="text" dfdl:lengthKind="delimited" dfdl:textNumberPattern="#,##0.00;(#)"
+                dfdl:textNumberCheckPolicy="lax" dfdl:textStandardDecimalSeparator="." dfdl:textStandardGroupingSeparator=","
+                dfdl:textStandardNaNRep="NaN" dfdl:textStandardInfinityRep="Inf"/>
+            <xs:element name="count" type="xs:int" dfdl:representation="text" dfdl:lengthKind="explicit" dfdl:length="3" dfdl:textNumberPattern="000"/>
+            <xs:element name="ratio" type="xs:float" dfdl:representation="text" dfdl:lengthKind="delimited" dfdl:textNumberPattern="0.0E0"
+                dfdl:textStandardDecimalSeparator="." dfdl:textStandardExponentRep="E" dfdl:textStandardNaNRep="NaN" dfdl:textStandardInfinityRep="Inf"/>
+            <xs:element name="flags" type="xs:unsignedShort" dfdl:representation="text" dfdl:lengthKind="delimited" dfdl:textStandardBase="16"/>
+            </xs:sequence></xs:complexType></xs:element>"##);
+        let infoset = parse(&schema, "record", b"-1234.567|007|2.5E-3|ff").unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), b"(1,234.57)|007|2.5E-3|ff");
+        assert!(parse(&schema, "record", b"1|007|2.5e-3|ff").is_err());
+    }
+
+    #[test]
+    fn round_trips_zoned_numbers() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="balance" type="xs:long" dfdl:representation="text" dfdl:lengthKind="explicit" dfdl:length="6"
+                dfdl:textNumberRep="zoned" dfdl:textNumberPattern="000000+" dfdl:textZonedSignStyle="asciiTranslatedEBCDIC"/>
+            <xs:element name="count" type="xs:short" dfdl:representation="text" dfdl:lengthKind="explicit" dfdl:length="3"
+                dfdl:textNumberRep="zoned" dfdl:textNumberPattern="+000" dfdl:textZonedSignStyle="asciiStandard"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = b"001231p42";
+        let infoset = parse(&schema, "record", data).unwrap();
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), b"00123Ap42");
+    }
+
+    #[test]
+    fn round_trips_decimals() {
+        let schema = schema(r##"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="amount" type="xs:decimal" dfdl:representation="text" dfdl:lengthKind="explicit" dfdl:length="8"
+                dfdl:textNumberPattern="#0.00" dfdl:textStandardDecimalSeparator="." dfdl:textStandardGroupingSeparator=","/>
+            <xs:element name="price" type="xs:decimal" dfdl:representation="text" dfdl:lengthKind="explicit" dfdl:length="6"
+                dfdl:textNumberRep="zoned" dfdl:textNumberPattern="0000V00+" dfdl:textZonedSignStyle="asciiStandard"/>
+            <xs:element name="rate" type="xs:decimal" dfdl:lengthKind="explicit" dfdl:length="4" dfdl:binaryDecimalVirtualPoint="3" dfdl:decimalSigned="yes"/>
+            <xs:element name="total" type="xs:decimal" dfdl:binaryNumberRep="packed" dfdl:lengthKind="explicit" dfdl:length="3"
+                dfdl:binaryPackedSignCodes="C D F C" dfdl:binaryDecimalVirtualPoint="2" dfdl:binaryNumberCheckPolicy="strict" dfdl:decimalSigned="yes"/>
+            <xs:element name="level" type="xs:decimal" dfdl:lengthKind="explicit" dfdl:length="1" dfdl:binaryDecimalVirtualPoint="1" dfdl:decimalSigned="no"/>
+            </xs:sequence></xs:complexType></xs:element>"##);
+        let data = b"-1234.5001234u\xFF\xFF\xFE\x0C\x12\x34\x5C\xFF";
+        let infoset = parse(&schema, "record", data).unwrap();
+        let Some(Element::ComplexElement(record)) = &infoset.root_element else { panic!("expected a complex element") };
+        let values: Vec<String> = record.children.iter().map(|child| match child {
+            Element::SimpleElement(SimpleElement { data: Some(Data::Decimal(value)), .. }) => value.to_string(),
+            _ => panic!("expected a decimal"),
+        }).collect();
+        // unsigned binary decimals have no sign bit
+        assert_eq!(values, ["-1234.50", "-123.45", "-0.500", "123.45", "25.5"]);
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+        // the values have to fit the virtual point of binary numbers
+        let mut infoset = infoset;
+        let Some(Element::ComplexElement(record)) = &mut infoset.root_element else { panic!("expected a complex element") };
+        let Element::SimpleElement(rate) = &mut record.children[2] else { panic!("expected a simple element") };
+        rate.data = Some(Data::Decimal("0.0005".parse().unwrap()));
+        assert!(unparsed(&schema, &infoset).is_err());
+        // nor do unsigned ones take negative values
+        let mut infoset = parse(&schema, "record", data).unwrap();
+        let Some(Element::ComplexElement(record)) = &mut infoset.root_element else { panic!("expected a complex element") };
+        let Element::SimpleElement(level) = &mut record.children[4] else { panic!("expected a simple element") };
+        level.data = Some(Data::Decimal("-0.1".parse().unwrap()));
+        assert!(unparsed(&schema, &infoset).is_err());
+    }
+
+    #[test]
+    fn suppresses_separators_by_policy() {
+        let schema = |position: &str, policy: &str| schema(&format!(r#"<xs:element name="segment"><xs:complexType>
+            <xs:sequence dfdl:separator="*" dfdl:separatorPosition="{position}" dfdl:separatorSuppressionPolicy="{policy}">
+                <xs:element name="a" type="xs:string" dfdl:lengthKind="delimited"/>
+                <xs:element name="b" type="xs:string" dfdl:lengthKind="delimited" minOccurs="0" maxOccurs="3"/>
+                <xs:element name="c" type="xs:string" dfdl:lengthKind="delimited" minOccurs="0"/>
+            </xs:sequence></xs:complexType></xs:element>"#));
+        let element = |name: &str, text: &'static str| Element::SimpleElement(SimpleElement {
+            schema: String::new(), namespace: String::new(), name: name.to_string(), nilled: false, array: name == "b",
+            valid: true, union_member_schema: String::new(), data: Some(Data::String(text.into())),
+        });
+        let segment = |children| Infoset { dfdl_version: "1.0".to_string(), diagnostics: Vec::new(), root_element: Some(Element::ComplexElement(ComplexElement {
+            schema: String::new(), namespace: String::new(), name: "segment".to_string(), nilled: false, array: false,
+            valid: true, union_member_schema: String::new(), children,
+        })) };
+        let infoset = segment(vec![element("a", "ST"), element("b", ""), element("b", "x"), element("b", "")]);
+        for (position, policy, expected) in [
+            ("infix", "never", &b"ST**x**"[..]),
+            ("infix", "anyEmpty", b"ST*x"),
+            ("infix", "trailingEmpty", b"ST**x"),
+            ("infix", "trailingEmptyStrict", b"ST**x"),
+            ("postfix", "anyEmpty", b"ST*x*"),
+            ("prefix", "trailingEmpty", b"*ST**x"),
+        ] {
+            let schema = schema(position, policy);
+            let data = unparsed(&schema, &infoset).unwrap();
+            assert_eq!(data, expected, "{position} {policy}");
+            // the empty occurrences are absent when parsing the data back
+            let parsed = parse(&schema, "segment", &data).unwrap();
+            let Some(Element::ComplexElement(root)) = &parsed.root_element else { panic!("expected a complex element") };
+            assert_eq!(root.children.iter().map(Element::name).collect::<Vec<_>>(), ["a", "b"], "{position} {policy}");
+        }
+        assert!(matches!(parse(&schema("infix", "trailingEmptyStrict"), "segment", b"ST**x*"), Err(crate::ParseError::InvalidData { .. })));
+        assert!(parse(&schema("infix", "trailingEmpty"), "segment", b"ST**x*").is_ok());
+    }
+
+    #[test]
+    fn round_trips_arrays_by_occurs_count_kind() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="count" type="xs:unsignedByte"/>
+            <xs:element name="item" type="xs:unsignedByte" maxOccurs="4" dfdl:occursCountKind="expression" dfdl:occursCount="{ ../count }"/>
+            <xs:element name="value" type="xs:string" maxOccurs="unbounded" dfdl:occursCountKind="stopValue" dfdl:occursStopValue="END"
+                dfdl:lengthKind="explicit" dfdl:length="3" dfdl:lengthUnits="bytes" dfdl:encoding="ascii"/>
+            <xs:element name="rest" type="xs:unsignedByte" maxOccurs="unbounded" dfdl:occursCountKind="parsed"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let data = b"\x02\x07\x08abcEND\x00\x01";
+        let infoset = parse(&schema, "record", data).unwrap();
+        // the stop value is not part of the infoset, but written after the array
+        let Some(Element::ComplexElement(root)) = &infoset.root_element else { panic!("expected a complex element") };
+        assert_eq!(root.children.iter().map(Element::name).collect::<Vec<_>>(), ["count", "item", "item", "value", "rest", "rest"]);
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), data);
+
+        let bounded = self::schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="value" type="xs:byte" maxOccurs="2" dfdl:occursCountKind="stopValue" dfdl:occursStopValue="0"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let mut infoset = parse(&bounded, "record", &[1, 2, 0]).unwrap();
+        assert_eq!(unparsed(&bounded, &infoset).unwrap(), [1, 2, 0]);
+        let Some(Element::ComplexElement(root)) = &mut infoset.root_element else { panic!("expected a complex element") };
+        root.children.push(root.children[0].clone());
+        assert!(matches!(unparsed(&bounded, &infoset), Err(UnparseError::UnexpectedElement { .. })));
+    }
+
+    #[test]
+    fn calculates_output_values() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="len" type="xs:unsignedByte" dfdl:outputValueCalc="{ dfdl:valueLength(../name, 'bytes') }"/>
+            <xs:element name="name" type="xs:string" dfdl:lengthKind="explicit" dfdl:length="{ ../len }"/>
+            <xs:element name="count" type="xs:unsignedByte" dfdl:outputValueCalc="{ fn:count(../item) }"/>
+            <xs:element name="item" type="xs:unsignedByte" maxOccurs="4" dfdl:occursCountKind="expression" dfdl:occursCount="{ ../count }"/>
+            <xs:element name="kind" type="xs:string" dfdl:inputValueCalc="{ if (../count gt 1) then 'many' else 'few' }"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let mut infoset = parse(&schema, "record", b"\x03abc\x02\x07\x08").unwrap();
+        let Some(Element::ComplexElement(root)) = &mut infoset.root_element else { panic!("expected a complex element") };
+        assert!(matches!(&root.children[5], Element::SimpleElement(SimpleElement { data: Some(Data::String(kind)), .. }) if kind == "many"));
+        // the length and the count are calculated from the values they describe, the calculated kind is not written
+        let Element::SimpleElement(name) = &mut root.children[1] else { panic!("expected a simple element") };
+        name.data = Some(Data::String("hello".into()));
+        root.children.remove(3);
+        assert_eq!(unparsed(&schema, &infoset).unwrap(), b"\x05hello\x01\x08");
+
+        let schema = self::schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="loop" type="xs:unsignedByte" dfdl:outputValueCalc="{ dfdl:valueLength(., 'bytes') }"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let infoset = parse(&schema, "record", b"\x01").unwrap();
+        let Err(UnparseError::InvalidValue { element, reason }) = unparsed(&schema, &infoset) else { panic!("expected an invalid value") };
+        assert_eq!(element, "/record/loop");
+        assert!(reason.contains("depends on itself"), "{reason}");
+    }
+
+    #[test]
+    fn rejects_infosets_not_matching_the_schema() {
+        let schema = schema(r#"<xs:element name="record"><xs:complexType><xs:sequence>
+            <xs:element name="a" type="xs:unsignedByte"/>
+            </xs:sequence></xs:complexType></xs:element>"#);
+        let record = |children| Infoset { dfdl_version: "1.0".to_string(), diagnostics: Vec::new(), root_element: Some(Element::ComplexElement(ComplexElement {
+            schema: String::new(), namespace: String::new(), name: "record".to_string(), nilled: false, array: false,
+            valid: true, union_member_schema: String::new(), children,
+        })) };
+        let a = |data| Element::SimpleElement(SimpleElement {
+            schema: String::new(), namespace: String::new(), name: "a".to_string(), nilled: false, array: false,
+            valid: true, union_member_schema: String::new(), data: Some(data),
+        });
+        assert!(matches!(unparsed(&schema, &record(vec![])), Err(UnparseError::MissingElement { .. })));
+        assert!(matches!(unparsed(&schema, &record(vec![a(Data::Int(1)), a(Data::Int(2))])), Err(UnparseError::UnexpectedElement { .. })));
+        assert!(matches!(unparsed(&schema, &record(vec![a(Data::Int(300))])), Err(UnparseError::InvalidValue { .. })));
+        assert_eq!(unparsed(&schema, &record(vec![a(Data::Int(200))])).unwrap(), [200]);
+    }
+}
@@ -0,0 +1,983 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use roxmltree::{ExpandedName, Node};
+
+use crate::{Decimal, DFDL_ANNOTATION_SOURCE, DFDL_NAMESPACE, XSD_NAMESPACE};
+
+use super::document::{self, SchemaDocument};
+use super::expression::{self, Expected, Scope};
+use super::{
+    Assert, AssertFailureType, AssertTestKind, CompiledSchema, DfdlExpression, DfdlRegex, Diagnostic, Discriminator, ElementContent, ElementDecl, Facets, Format, GroupKind, LengthKind, MaxOccurs, ModelGroup, PrimitiveType, PropertySet,
+    SchemaDefinitionError, SchemaError, Severity, SimpleType, SourceLocation, Term,
+};
+
+
+fn is_xsd(node: Node, name: &'static str) -> bool {
+    node.tag_name() == ExpandedName::from_static(XSD_NAMESPACE, name)
+}
+
+/// iterates over the child elements of `node` that are part of the XML Schema namespace
+fn xsd_children<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|c| c.is_element() && c.tag_name().namespace() == Some(XSD_NAMESPACE))
+}
+
+/// iterates over the DFDL annotation elements (e.g. `dfdl:format`) attached to the schema component `node`
+fn dfdl_annotations<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    xsd_children(node)
+        .filter(|c| is_xsd(*c, "annotation"))
+        .flat_map(|annotation| xsd_children(annotation))
+        // dfdl annotation in non-short form is inside an appinfo block
+        .filter(|appinfo| is_xsd(*appinfo, "appinfo") && appinfo.attribute("source") == Some(DFDL_ANNOTATION_SOURCE))
+        .flat_map(|appinfo| appinfo.children())
+        .filter(|c| c.is_element() && c.tag_name().namespace() == Some(DFDL_NAMESPACE))
+}
+
+/// iterates over the `xs:appinfo` blocks of `node` that contain DFDL annotations but are ignored,
+/// as their `source` is not the DFDL annotation source
+fn ignored_appinfos<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    xsd_children(node)
+        .filter(|c| is_xsd(*c, "annotation"))
+        .flat_map(|annotation| xsd_children(annotation))
+        .filter(|appinfo| is_xsd(*appinfo, "appinfo") && appinfo.attribute("source") != Some(DFDL_ANNOTATION_SOURCE))
+        .filter(|appinfo| appinfo.children().any(|c| c.is_element() && c.tag_name().namespace() == Some(DFDL_NAMESPACE)))
+}
+
+/// A qualified name, as used to refer to named schema components and format definitions
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct QName {
+    namespace: Option<String>,
+    local: String,
+}
+
+impl QName {
+    /// resolves the prefix of `qname` using the namespace declarations in scope at `node`
+    ///
+    /// Names without a namespace used in a chameleon include take on `chameleon`,
+    /// the namespace of the including document.
+    fn resolve(node: Node, qname: &str, chameleon: Option<&str>) -> Result<Self, SchemaError> {
+        let (prefix, local) = match qname.split_once(':') {
+            Some((prefix, local)) => (Some(prefix), local),
+            None => (None, qname),
+        };
+        let namespace = node.lookup_namespace_uri(prefix);
+        if let (Some(prefix), None) = (prefix, namespace) {
+            return Err(SchemaError::UnknownPrefix(prefix.to_string()));
+        }
+        Ok(Self { namespace: namespace.or(chameleon).map(str::to_string), local: local.to_string() })
+    }
+}
+
+impl std::fmt::Display for QName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{{{namespace}}}{}", self.local),
+            None => write!(f, "{}", self.local),
+        }
+    }
+}
+
+/// a schema document together with the settings that apply to the components defined in it
+struct DocumentContext<'a, 'input> {
+    root: Node<'a, 'input>,
+    /// location of the document, for diagnostics
+    file: Option<String>,
+    target_namespace: Option<String>,
+    qualified_locals: bool,
+    /// the properties of the document's `dfdl:format` annotation
+    defaults: PropertySet,
+}
+
+/// A global definition, together with the index of the document it is defined in
+#[derive(Clone, Copy)]
+struct Global<'a, 'input> {
+    doc: usize,
+    node: Node<'a, 'input>,
+}
+
+/// Compiles the components of a set of schema documents
+///
+/// Schema definition errors do not stop compilation: they are recorded, the offending
+/// property or component is skipped, and compilation continues to find further errors.
+pub(super) struct Compiler<'a, 'input> {
+    documents: Vec<DocumentContext<'a, 'input>>,
+    /// the `dfdl:format` element of every `dfdl:defineFormat` in the schema
+    format_definitions: HashMap<QName, Node<'a, 'input>>,
+    /// the global element declarations
+    elements: HashMap<QName, Global<'a, 'input>>,
+    /// the global simple and complex type definitions, which share a symbol space
+    types: HashMap<QName, Global<'a, 'input>>,
+    /// the global model group definitions
+    groups: HashMap<QName, Global<'a, 'input>>,
+    /// the global definitions currently being compiled, to detect recursive definitions
+    active: RefCell<Vec<(&'static str, QName)>>,
+    /// the global elements referenced by other elements, whose problems as roots of an infoset are only warnings
+    referenced: RefCell<HashSet<QName>>,
+    /// the errors and warnings found so far
+    diagnostics: RefCell<Vec<Diagnostic>>,
+}
+
+impl<'a, 'input> Compiler<'a, 'input> {
+    /// `trees` are the parsed `documents`, with the root document first
+    pub(super) fn new(documents: &[SchemaDocument], trees: &'a [roxmltree::Document<'input>]) -> Self {
+        let mut compiler = Self {
+            documents: documents.iter().zip(trees).map(|(document, tree)| {
+                let root = tree.root_element();
+                DocumentContext {
+                    root,
+                    file: document.location.clone(),
+                    target_namespace: document.target_namespace.clone(),
+                    qualified_locals: root.attribute("elementFormDefault") == Some("qualified"),
+                    defaults: PropertySet::default(),
+                }
+            }).collect(),
+            format_definitions: HashMap::new(),
+            elements: HashMap::new(),
+            types: HashMap::new(),
+            groups: HashMap::new(),
+            active: RefCell::default(),
+            referenced: RefCell::default(),
+            diagnostics: RefCell::default(),
+        };
+
+        let mut format_definitions = HashMap::new();
+        for doc in &compiler.documents {
+            for definition in dfdl_annotations(doc.root).filter(|c| c.tag_name().name() == "defineFormat") {
+                let Some(name) = definition.attribute("name") else {
+                    compiler.error(definition, "xs:schema", SchemaError::MissingAttribute { attribute: "name", component: "dfdl:defineFormat" });
+                    continue;
+                };
+                let path = format!("dfdl:defineFormat '{name}'");
+                let Some(format) = definition.children().find(|c| c.tag_name() == ExpandedName::from_static(DFDL_NAMESPACE, "format")) else {
+                    compiler.error(definition, &path, SchemaError::Unsupported(format!("dfdl:defineFormat '{name}' without dfdl:format")));
+                    continue;
+                };
+                let qname = QName { namespace: doc.target_namespace.clone(), local: name.to_string() };
+                if format_definitions.contains_key(&qname) {
+                    compiler.error(definition, &path, SchemaError::DuplicateDefinition(qname.to_string()));
+                    continue;
+                }
+                format_definitions.insert(qname, format);
+            }
+        }
+        compiler.format_definitions = format_definitions;
+
+        let (mut elements, mut types, mut groups) = (HashMap::new(), HashMap::new(), HashMap::new());
+        for (index, doc) in compiler.documents.iter().enumerate() {
+            for definition in xsd_children(doc.root) {
+                let (kind, definitions) = match definition.tag_name().name() {
+                    "element" => ("element", &mut elements),
+                    "complexType" | "simpleType" => ("type", &mut types),
+                    "group" => ("group", &mut groups),
+                    _ => continue,
+                };
+                let Some(name) = definition.attribute("name") else {
+                    compiler.error(definition, "xs:schema", SchemaError::MissingAttribute { attribute: "name", component: "a global definition" });
+                    continue;
+                };
+                let qname = QName { namespace: doc.target_namespace.clone(), local: name.to_string() };
+                if definitions.contains_key(&qname) {
+                    compiler.error(definition, "xs:schema", SchemaError::DuplicateDefinition(format!("{kind} {qname}")));
+                    continue;
+                }
+                definitions.insert(qname, Global { doc: index, node: definition });
+            }
+        }
+        (compiler.elements, compiler.types, compiler.groups) = (elements, types, groups);
+
+        for i in 0..compiler.documents.len() {
+            let root = compiler.documents[i].root;
+            compiler.warn_ignored_annotations(root, "xs:schema");
+            let mut defaults = None;
+            for annotation in dfdl_annotations(root) {
+                match annotation.tag_name().name() {
+                    "format" if defaults.is_none() => defaults = Some(compiler.resolve_format(annotation, "xs:schema", &mut Vec::new())),
+                    "format" => compiler.error(annotation, "xs:schema", SchemaError::DuplicateAnnotation { annotation: "dfdl:format", component: "xs:schema" }),
+                    "defineFormat" | "defineEscapeScheme" | "defineVariable" => {},
+                    other => compiler.error(annotation, "xs:schema", SchemaError::MisplacedAnnotation { annotation: format!("dfdl:{other}"), component: "xs:schema" }),
+                }
+            }
+            compiler.documents[i].defaults = defaults.unwrap_or_default();
+        }
+        compiler
+    }
+
+    /// the position of `node`, which is part of one of the schema documents
+    fn locate(&self, node: Node) -> SourceLocation {
+        document::node_location(self.file_of(node), node)
+    }
+
+    /// the position of the attribute `attr` of `node`
+    fn locate_attribute(&self, node: Node, attr: &roxmltree::Attribute) -> SourceLocation {
+        document::text_location(self.file_of(node), node, attr.range().start)
+    }
+
+    fn file_of(&self, node: Node) -> Option<&str> {
+        self.document_of(node).and_then(|doc| doc.file.as_deref())
+    }
+
+    fn document_of(&self, node: Node) -> Option<&DocumentContext<'a, 'input>> {
+        self.documents.iter().find(|doc| std::ptr::eq(doc.root.document(), node.document()))
+    }
+
+    /// resolves the QName `qname` used at `node`
+    fn qname(&self, node: Node, qname: &str) -> Result<QName, SchemaError> {
+        // a document without target namespace included by one with a target namespace is a chameleon include
+        let chameleon = self.document_of(node)
+            .filter(|doc| doc.root.attribute("targetNamespace").is_none())
+            .and_then(|doc| doc.target_namespace.as_deref());
+        QName::resolve(node, qname, chameleon)
+    }
+
+    /// records `diagnostic`, unless the same problem has already been recorded,
+    /// as happens for problems in formats shared by several components
+    fn report(&self, diagnostic: Diagnostic) {
+        let mut diagnostics = self.diagnostics.borrow_mut();
+        let known = diagnostics.iter().any(|d| d.severity == diagnostic.severity
+            && d.location == diagnostic.location
+            && d.kind.to_string() == diagnostic.kind.to_string());
+        if !known { diagnostics.push(diagnostic); }
+    }
+
+    /// records a schema definition error at `node`, found while compiling the component at `path`
+    fn error(&self, node: Node, path: &str, kind: SchemaError) {
+        self.error_at(self.locate(node), path, kind);
+    }
+
+    fn error_at(&self, location: SourceLocation, path: &str, kind: SchemaError) {
+        self.report(Diagnostic::error(kind).at(Some(location)).in_component(path));
+    }
+
+    fn warn(&self, node: Node, path: &str, kind: SchemaError) {
+        self.report(Diagnostic::warning(kind).at(Some(self.locate(node))).in_component(path));
+    }
+
+    /// warns about DFDL annotations of `node` that are ignored because of the `source` of their `xs:appinfo`
+    fn warn_ignored_annotations(&self, node: Node, path: &str) {
+        for appinfo in ignored_appinfos(node) {
+            let source = appinfo.attribute("source").unwrap_or_default();
+            self.warn(appinfo, path, SchemaError::IgnoredAnnotation(
+                format!("xs:appinfo has source '{source}', DFDL annotations require '{DFDL_ANNOTATION_SOURCE}'")
+            ));
+        }
+    }
+
+    /// computes the properties of the DFDL annotation `element`, including those inherited via its `ref` attribute
+    ///
+    /// Properties may be given as attributes or as `dfdl:property` child elements,
+    /// it is a schema definition error to give the same property in both forms.
+    ///
+    /// `chain` holds the format definitions currently being resolved, to detect circular references
+    fn resolve_format(&self, element: Node, path: &str, chain: &mut Vec<QName>) -> PropertySet {
+        let mut local = PropertySet::default();
+        for attr in element.attributes().filter(|attr| attr.namespace().is_none() && attr.name() != "ref") {
+            let location = self.locate_attribute(element, &attr);
+            if let Err(e) = local.insert_at(attr.name(), attr.value(), location.clone()) {
+                self.error_at(location, path, e);
+            }
+        }
+        // element form, e.g. <dfdl:property name="initiator"><![CDATA["]]></dfdl:property>
+        for property in element.children().filter(Node::is_element) {
+            if property.tag_name() != ExpandedName::from_static(DFDL_NAMESPACE, "property") {
+                self.error(property, path, SchemaError::MisplacedAnnotation {
+                    annotation: property.tag_name().name().to_string(),
+                    component: "a DFDL annotation",
+                });
+                continue;
+            }
+            let Some(name) = property.attribute("name") else {
+                self.error(property, path, SchemaError::MissingAttribute { attribute: "name", component: "dfdl:property" });
+                continue;
+            };
+            let value: String = property.children().filter(Node::is_text).filter_map(|t| t.text()).collect();
+            if let Err(e) = local.insert_at(name, &value, self.locate(property)) {
+                self.error(property, path, e);
+            }
+        }
+        let Some(reference) = element.attribute("ref") else { return local };
+        self.resolve_format_ref(element, reference, local, path, chain)
+    }
+
+    /// resolves the format definition `reference` made at `element` and overrides its properties with `local`
+    fn resolve_format_ref(&self, element: Node, reference: &str, local: PropertySet, path: &str, chain: &mut Vec<QName>) -> PropertySet {
+        let name = match self.qname(element, reference) {
+            Ok(name) => name,
+            Err(e) => {
+                self.error(element, path, e);
+                return local;
+            },
+        };
+        if chain.contains(&name) {
+            self.error(element, path, SchemaError::CircularFormatReference(name.to_string()));
+            return local;
+        }
+        let Some(definition) = self.format_definitions.get(&name).copied() else {
+            self.error(element, path, SchemaError::UndefinedFormat(name.to_string()));
+            return local;
+        };
+        chain.push(name);
+        let inherited = self.resolve_format(definition, path, chain);
+        chain.pop();
+        // properties given locally take precedence over those of the referenced format
+        inherited.merge(local)
+    }
+
+    /// collects the properties placed directly on the schema component `node`
+    ///
+    /// These are the properties of its DFDL annotation, which has to be a `dfdl:<kind>` annotation,
+    /// and its short form `dfdl:` attributes. It is a schema definition error to set a property in both.
+    fn local_properties(&self, node: Node, kind: &'static str, path: &str) -> PropertySet {
+        let (component, annotation_kind) = match kind {
+            "simpleType" => ("xs:simpleType", "dfdl:simpleType"),
+            "sequence" => ("xs:sequence", "dfdl:sequence"),
+            "choice" => ("xs:choice", "dfdl:choice"),
+            "group" => ("xs:group", "dfdl:group"),
+            _ => ("xs:element", "dfdl:element"),
+        };
+        self.warn_ignored_annotations(node, path);
+        let mut long_form = None;
+        for annotation in dfdl_annotations(node) {
+            match annotation.tag_name().name() {
+                name if name == kind => {
+                    if long_form.is_some() {
+                        self.error(annotation, path, SchemaError::DuplicateAnnotation { annotation: annotation_kind, component });
+                        continue;
+                    }
+                    long_form = Some(self.resolve_format(annotation, path, &mut Vec::new()));
+                },
+                "element" | "simpleType" | "sequence" | "choice" | "group" | "format"
+                | "defineFormat" | "defineEscapeScheme" | "defineVariable" => {
+                    self.error(annotation, path, SchemaError::MisplacedAnnotation { annotation: format!("dfdl:{}", annotation.tag_name().name()), component });
+                },
+                // statement annotations, e.g. asserts
+                _ => {},
+            }
+        }
+
+        let mut short_form = PropertySet::default();
+        let mut reference = None;
+        for attr in node.attributes().filter(|attr| attr.namespace() == Some(DFDL_NAMESPACE)) {
+            if attr.name() == "ref" {
+                reference = Some(attr.value());
+                continue;
+            }
+            let location = self.locate_attribute(node, &attr);
+            if let Err(e) = short_form.insert_at(attr.name(), attr.value(), location.clone()) {
+                self.error_at(location, path, e);
+            }
+        }
+        if let Some(reference) = reference {
+            short_form = self.resolve_format_ref(node, reference, short_form, path, &mut Vec::new());
+        }
+
+        match long_form {
+            Some(long_form) => self.combine(long_form, short_form, node, path),
+            None => short_form,
+        }
+    }
+
+    /// combines two property sets of equal precedence of the component at `node`,
+    /// reporting every property set in both
+    fn combine(&self, properties: PropertySet, other: PropertySet, node: Node, path: &str) -> PropertySet {
+        for (name, _) in other.iter().filter(|(name, _)| properties.get(name).is_some()) {
+            let location = other.location(name).cloned().unwrap_or_else(|| self.locate(node));
+            self.error_at(location, path, SchemaError::DuplicateProperty(name.to_string()));
+        }
+        properties.merge(other)
+    }
+
+    /// interprets the local `properties` of the component `node` on top of the defaults of `doc`,
+    /// reporting every invalid property
+    fn format(&self, doc: &DocumentContext, properties: PropertySet, node: Node, path: &str) -> Format {
+        let properties = doc.defaults.clone().merge(properties);
+        Format::from_properties_reporting(&properties, |name, e| {
+            let location = properties.location(name).cloned().unwrap_or_else(|| self.locate(node));
+            self.error_at(location, path, e);
+        })
+    }
+
+    pub(super) fn compile(self) -> Result<CompiledSchema, SchemaDefinitionError> {
+        // the root document is always the first, `load_documents` never returns an empty set
+        let root = &self.documents[0];
+        let target_namespace = root.target_namespace.clone();
+        let default_format = self.format(root, PropertySet::default(), root.root, "xs:schema");
+
+        let mut elements = Vec::new();
+        for doc in &self.documents {
+            for c in xsd_children(doc.root) {
+                match c.tag_name().name() {
+                    "element" => elements.extend(self.compile_element(doc, c, true, "")),
+                    // named definitions are only compiled where they are used
+                    "annotation" | "complexType" | "simpleType" | "group" | "include" | "import" => {},
+                    other => self.error(c, "xs:schema", SchemaError::Unsupported(format!("xs:{other}"))),
+                }
+            }
+        }
+
+        // expressions can only be checked once the elements they refer to are known, starting at
+        // the root of the infoset; every global element may be the root of one
+        let referenced = self.referenced.take();
+        for decl in &mut elements {
+            let start = self.diagnostics.borrow().len();
+            let root = Scope::new(decl);
+            let path = format!("/{}", decl.name);
+            self.check_element(decl, &root, &mut Vec::new(), &path);
+            // referenced elements may refer to the elements around their references, which are checked there;
+            // as roots, where those elements are missing, their problems are only warnings
+            if referenced.contains(&QName { namespace: decl.namespace.clone(), local: decl.name.clone() }) {
+                for diagnostic in &mut self.diagnostics.borrow_mut()[start..] {
+                    diagnostic.severity = Severity::Warning;
+                }
+            }
+        }
+
+        let diagnostics = self.diagnostics.into_inner();
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            return Err(SchemaDefinitionError::new(diagnostics));
+        }
+        Ok(CompiledSchema { target_namespace, default_format, elements, warnings: diagnostics })
+    }
+
+    /// compiles the element declaration or reference `node`, a child of the component at `parent`
+    ///
+    /// Returns `None` if the element can not be compiled, after reporting why.
+    fn compile_element(&self, doc: &DocumentContext, node: Node, global: bool, parent: &str) -> Option<ElementDecl> {
+        if let Some(reference) = node.attribute("ref") {
+            return self.compile_element_ref(node, reference, parent);
+        }
+        let Some(name) = node.attribute("name") else {
+            self.error(node, &format!("{parent}/xs:element"), SchemaError::MissingAttribute { attribute: "name", component: "xs:element" });
+            return None;
+        };
+        let path = format!("{parent}/{name}");
+        let qualified = global || match node.attribute("form") {
+            Some(form) => form == "qualified",
+            None => doc.qualified_locals,
+        };
+        let (min_occurs, max_occurs) = self.occurs(node, &path);
+        let nillable = self.nillable(node, &path);
+        let properties = self.local_properties(node, "element", &path);
+        let (format, content) = self.compile_element_body(doc, node, properties, &path)?;
+        let prefix_length_type = self.prefix_length_type(node, &format, &path);
+        let (asserts, discriminator) = self.statements(node, "xs:element", &path);
+        Some(ElementDecl {
+            name: name.to_string(),
+            namespace: if qualified { doc.target_namespace.clone() } else { None },
+            min_occurs,
+            max_occurs,
+            nillable,
+            format,
+            content,
+            prefix_length_type,
+            asserts,
+            discriminator,
+        })
+    }
+
+    /// compiles the reference `node` to the global element `reference`
+    ///
+    /// The properties given on the reference are combined with those of the global declaration,
+    /// so every reference yields its own [`ElementDecl`].
+    fn compile_element_ref(&self, node: Node, reference: &str, parent: &str) -> Option<ElementDecl> {
+        let name = self.qname(node, reference)
+            .map_err(|e| self.error(node, &format!("{parent}/{reference}"), e))
+            .ok()?;
+        let path = format!("{parent}/{}", name.local);
+        let Some(global) = self.elements.get(&name).copied() else {
+            self.error(node, &path, SchemaError::UnresolvedReference { kind: "element", name: name.to_string() });
+            return None;
+        };
+        self.referenced.borrow_mut().insert(name.clone());
+        let (min_occurs, max_occurs) = self.occurs(node, &path);
+        let reference_properties = self.local_properties(node, "element", &path);
+        self.guarded("element", &name, node, &path, || {
+            let declaration = self.local_properties(global.node, "element", &path);
+            let properties = self.combine(declaration, reference_properties, node, &path);
+            let (format, content) = self.compile_element_body(&self.documents[global.doc], global.node, properties, &path)?;
+            let prefix_length_type = self.prefix_length_type(node, &format, &path);
+            // the statements of a reference and the declaration it refers to are combined,
+            // but they may not both have a discriminator
+            let (mut asserts, reference_discriminator) = self.statements(node, "xs:element", &path);
+            let (declaration_asserts, declaration_discriminator) = self.statements(global.node, "xs:element", &path);
+            asserts.extend(declaration_asserts);
+            let discriminator = match (reference_discriminator, declaration_discriminator) {
+                (Some(_), Some(_)) => {
+                    self.error(node, &path, SchemaError::DuplicateAnnotation { annotation: "dfdl:discriminator", component: "xs:element" });
+                    None
+                },
+                (reference, declaration) => reference.or(declaration),
+            };
+            Some(ElementDecl {
+                name: name.local.clone(),
+                namespace: name.namespace.clone(),
+                min_occurs,
+                max_occurs,
+                nillable: self.nillable(global.node, &path),
+                format,
+                content,
+                prefix_length_type,
+                asserts,
+                discriminator,
+            })
+        })
+    }
+
+    /// type checks the expressions of `decl`, found at `path`, and of its descendants
+    ///
+    /// `context` holds the indices of the children leading from the element `root` to `decl`.
+    fn check_element(&self, decl: &mut ElementDecl, root: &Scope, context: &mut Vec<usize>, path: &str) {
+        self.check_statements(&mut decl.format, &mut decl.asserts, decl.discriminator.as_mut(), root, context, path);
+        if let ElementContent::Complex(group) = &mut decl.content {
+            self.check_group(group, root, context, &mut 0, &format!("{path}/{}[1]", group_kind(group)));
+        }
+    }
+
+    /// type checks the expressions of `group`, found at `path`, and of its terms
+    ///
+    /// The elements of `group` are the children of the element at `context`, starting at the index `next`.
+    fn check_group(&self, group: &mut ModelGroup, root: &Scope, context: &mut Vec<usize>, next: &mut usize, path: &str) {
+        self.check_statements(&mut group.format, &mut group.asserts, group.discriminator.as_mut(), root, context, path);
+        for (index, term) in group.terms.iter_mut().enumerate() {
+            match term {
+                Term::Element(decl) => {
+                    context.push(*next);
+                    *next += 1;
+                    let path = format!("{path}/{}", decl.name);
+                    self.check_element(decl, root, context, &path);
+                    context.pop();
+                },
+                Term::Group(group) => {
+                    let path = format!("{path}/{}[{}]", group_kind(group), index + 1);
+                    self.check_group(group, root, context, next, &path);
+                },
+            }
+        }
+    }
+
+    /// type checks the expressions of the properties `format` and the statements of a component at `path`
+    fn check_statements(&self, format: &mut Format, asserts: &mut [Assert], discriminator: Option<&mut Discriminator>, root: &Scope, context: &[usize], path: &str) {
+        let statements = asserts.iter_mut().map(|assert| ("dfdl:assert", &mut assert.test))
+            .chain(discriminator.map(|discriminator| ("dfdl:discriminator", &mut discriminator.test)))
+            .filter_map(|(annotation, test)| Some((annotation, Expected::Boolean, test.as_mut()?)));
+        for (property, expected, test) in format.expressions_mut().into_iter().chain(statements) {
+            if let Err(error) = expression::check(test.expression_mut(), root, context, expected) {
+                let mut diagnostic = Diagnostic::error(SchemaError::InvalidExpression { expression: test.as_str().to_string(), error }).in_component(path);
+                diagnostic.property = Some(property.to_string());
+                self.report(diagnostic);
+            }
+        }
+    }
+
+    /// the `minOccurs` and `maxOccurs` of the particle `node`
+    fn occurs(&self, node: Node, path: &str) -> (u64, MaxOccurs) {
+        let min_occurs = match node.attribute("minOccurs") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                self.error(node, path, SchemaError::InvalidAttribute { attribute: "minOccurs", value: value.to_string() });
+                1
+            }),
+            None => 1,
+        };
+        let max_occurs = match node.attribute("maxOccurs") {
+            Some("unbounded") => MaxOccurs::Unbounded,
+            Some(value) => MaxOccurs::Bounded(value.parse().unwrap_or_else(|_| {
+                self.error(node, path, SchemaError::InvalidAttribute { attribute: "maxOccurs", value: value.to_string() });
+                1
+            })),
+            None => MaxOccurs::Bounded(1),
+        };
+        (min_occurs, max_occurs)
+    }
+
+    fn nillable(&self, node: Node, path: &str) -> bool {
+        match node.attribute("nillable") {
+            Some("true" | "1") => true,
+            Some("false" | "0") | None => false,
+            Some(value) => {
+                self.error(node, path, SchemaError::InvalidAttribute { attribute: "nillable", value: value.to_string() });
+                false
+            },
+        }
+    }
+
+    /// compiles the type of the element declaration `node` of `doc`, whose own DFDL properties are `properties`,
+    /// and the element's format, which includes the properties of its simple type
+    fn compile_element_body(&self, doc: &DocumentContext, node: Node, properties: PropertySet, path: &str) -> Option<(Format, ElementContent)> {
+        let definition = match node.attribute("type") {
+            Some(type_name) => self.resolve_type(node, type_name, path)?,
+            None => match xsd_children(node).find(|c| is_xsd(*c, "complexType") || is_xsd(*c, "simpleType")) {
+                Some(definition) => TypeDefinition::Defined(doc, definition),
+                None => {
+                    self.error(node, path, SchemaError::MissingAttribute { attribute: "type", component: "xs:element" });
+                    return None;
+                },
+            },
+        };
+        match definition {
+            TypeDefinition::Builtin(primitive) => {
+                let simple_type = SimpleType { name: None, primitive, format: self.format(doc, PropertySet::default(), node, path), max_length: None, facets: Facets::default() };
+                Some((self.format(doc, properties, node, path), ElementContent::Simple(simple_type)))
+            },
+            TypeDefinition::Defined(type_doc, definition) if is_xsd(definition, "simpleType") => {
+                let (simple_type, type_properties) = self.compile_simple_type(type_doc, definition, path)?;
+                // the properties of an element and its simple type are combined
+                let properties = self.combine(properties, type_properties, node, path);
+                Some((self.format(doc, properties, node, path), ElementContent::Simple(simple_type)))
+            },
+            TypeDefinition::Defined(type_doc, definition) => {
+                let format = self.format(doc, properties, node, path);
+                let group = self.compile_complex_type(type_doc, definition, path)?;
+                Some((format, ElementContent::Complex(group)))
+            },
+        }
+    }
+
+    /// compiles the simple type named by `prefixLengthType`, if the element `node` has `lengthKind="prefixed"`
+    fn prefix_length_type(&self, node: Node, format: &Format, path: &str) -> Option<Box<SimpleType>> {
+        if format.length.length_kind != Some(LengthKind::Prefixed) {
+            return None;
+        }
+        let Some(type_name) = &format.length.prefix_length_type else {
+            self.error(node, path, SchemaError::MissingAttribute { attribute: "dfdl:prefixLengthType", component: "xs:element" });
+            return None;
+        };
+        match self.resolve_type(node, type_name, path)? {
+            TypeDefinition::Defined(type_doc, definition) if is_xsd(definition, "simpleType") => {
+                let (simple_type, _) = self.compile_simple_type(type_doc, definition, path)?;
+                Some(Box::new(simple_type))
+            },
+            _ => {
+                self.error(node, path, SchemaError::UnresolvedType(format!("{type_name} is not a simple type definition")));
+                None
+            },
+        }
+    }
+
+    /// the `dfdl:assert` and `dfdl:discriminator` annotations of the schema component `node`, of kind `component`
+    fn statements(&self, node: Node, component: &'static str, path: &str) -> (Vec<Assert>, Option<Discriminator>) {
+        let mut asserts = Vec::new();
+        let mut discriminator = None;
+        for annotation in dfdl_annotations(node) {
+            match annotation.tag_name().name() {
+                "assert" => {
+                    let Some((test_kind, test, test_pattern)) = self.test(annotation, "dfdl:assert", path) else { continue };
+                    let failure_type = match annotation.attribute("failureType") {
+                        None | Some("processingError") => AssertFailureType::ProcessingError,
+                        Some("recoverableError") => AssertFailureType::RecoverableError,
+                        Some(value) => {
+                            self.error(annotation, path, SchemaError::InvalidAttribute { attribute: "failureType", value: value.to_string() });
+                            continue;
+                        },
+                    };
+                    let message = annotation.attribute("message").unwrap_or_default().to_string();
+                    asserts.push(Assert { test_kind, test, test_pattern, message, failure_type });
+                },
+                "discriminator" => {
+                    if discriminator.is_some() {
+                        self.error(annotation, path, SchemaError::DuplicateAnnotation { annotation: "dfdl:discriminator", component });
+                        continue;
+                    }
+                    let Some((test_kind, test, test_pattern)) = self.test(annotation, "dfdl:discriminator", path) else { continue };
+                    let message = annotation.attribute("message").unwrap_or_default().to_string();
+                    discriminator = Some(Discriminator { test_kind, test, test_pattern, message });
+                },
+                _ => {},
+            }
+        }
+        (asserts, discriminator)
+    }
+
+    /// the test of the assert or discriminator `annotation`: its kind, and its expression or regular expression
+    ///
+    /// Either is given by its attribute, `test` or `testPattern`, or the content of the annotation.
+    fn test(&self, annotation: Node, kind: &'static str, path: &str) -> Option<(AssertTestKind, Option<DfdlExpression>, Option<String>)> {
+        let content = annotation.text().map(str::trim).filter(|text| !text.is_empty());
+        match annotation.attribute("testKind") {
+            None | Some("expression") => {
+                let Some(test) = annotation.attribute("test").or(content) else {
+                    self.error(annotation, path, SchemaError::MissingAttribute { attribute: "test", component: kind });
+                    return None;
+                };
+                let Some(expression) = test.strip_prefix('{').and_then(|test| test.strip_suffix('}')) else {
+                    self.error(annotation, path, SchemaError::InvalidAttribute { attribute: "test", value: test.to_string() });
+                    return None;
+                };
+                match DfdlExpression::new(expression.trim()) {
+                    Ok(expression) => Some((AssertTestKind::Expression, Some(expression), None)),
+                    Err(error) => {
+                        self.error(annotation, path, SchemaError::InvalidExpression { expression: expression.trim().to_string(), error });
+                        None
+                    },
+                }
+            },
+            Some("pattern") => {
+                let Some(pattern) = annotation.attribute("testPattern").or(content) else {
+                    self.error(annotation, path, SchemaError::MissingAttribute { attribute: "testPattern", component: kind });
+                    return None;
+                };
+                if DfdlRegex::new(pattern).is_err() {
+                    self.error(annotation, path, SchemaError::InvalidAttribute { attribute: "testPattern", value: pattern.to_string() });
+                    return None;
+                }
+                Some((AssertTestKind::Pattern, None, Some(pattern.to_string())))
+            },
+            Some(value) => {
+                self.error(annotation, path, SchemaError::InvalidAttribute { attribute: "testKind", value: value.to_string() });
+                None
+            },
+        }
+    }
+
+    /// resolves the QName `type_name`, used at `node`, to a builtin type or a global type definition
+    fn resolve_type(&self, node: Node, type_name: &str, path: &str) -> Option<TypeDefinition<'_, 'a, 'input>> {
+        let name = self.qname(node, type_name).map_err(|e| self.error(node, path, e)).ok()?;
+        if name.namespace.as_deref() == Some(XSD_NAMESPACE) {
+            if let Some(primitive) = PrimitiveType::from_name(&name.local) {
+                return Some(TypeDefinition::Builtin(primitive));
+            }
+        }
+        match self.types.get(&name) {
+            Some(global) => Some(TypeDefinition::Defined(&self.documents[global.doc], global.node)),
+            None => {
+                self.error(node, path, SchemaError::UnresolvedType(name.to_string()));
+                None
+            },
+        }
+    }
+
+    /// runs `compile` for the global definition `name` of the given `kind`, referenced at `node`,
+    /// reporting a schema definition error instead if the definition is recursive
+    fn guarded<T>(&self, kind: &'static str, name: &QName, node: Node, path: &str, compile: impl FnOnce() -> Option<T>) -> Option<T> {
+        let key = (kind, name.clone());
+        if self.active.borrow().contains(&key) {
+            self.error(node, path, SchemaError::CircularReference(format!("{kind} {name}")));
+            return None;
+        }
+        self.active.borrow_mut().push(key);
+        let compiled = compile();
+        self.active.borrow_mut().pop();
+        compiled
+    }
+
+    /// compiles the complex type `node` of `doc`
+    fn compile_complex_type(&self, doc: &DocumentContext, node: Node, path: &str) -> Option<ModelGroup> {
+        let compile = || {
+            let mut group = None;
+            for c in xsd_children(node) {
+                match c.tag_name().name() {
+                    "annotation" => {},
+                    kind @ ("sequence" | "choice") if group.is_none() => {
+                        group = Some(Some(self.compile_model_group(doc, c, PropertySet::default(), &format!("{path}/{kind}[1]"))));
+                    },
+                    "group" if group.is_none() => group = Some(self.compile_group_ref(c, &format!("{path}/group[1]"))),
+                    other => self.error(c, path, SchemaError::Unsupported(format!("xs:{other} in xs:complexType"))),
+                }
+            }
+            if group.is_none() {
+                self.error(node, path, SchemaError::Unsupported("xs:complexType without a model group".to_string()));
+            }
+            group.flatten()
+        };
+        match node.attribute("name") {
+            Some(name) => self.guarded("type", &QName { namespace: doc.target_namespace.clone(), local: name.to_string() }, node, path, compile),
+            None => compile(),
+        }
+    }
+
+    /// compiles the sequence or choice `node`, found at `path`
+    ///
+    /// `reference_properties` are the properties of the `xs:group` reference `node` is compiled for, if any.
+    fn compile_model_group(&self, doc: &DocumentContext, node: Node, reference_properties: PropertySet, path: &str) -> ModelGroup {
+        let (kind, properties) = if is_xsd(node, "sequence") {
+            (GroupKind::Sequence, self.local_properties(node, "sequence", path))
+        }
+        else {
+            (GroupKind::Choice, self.local_properties(node, "choice", path))
+        };
+        let properties = self.combine(properties, reference_properties, node, path);
+        let format = self.format(doc, properties, node, path);
+        let mut terms = Vec::new();
+        for (index, c) in xsd_children(node).filter(|c| !is_xsd(*c, "annotation")).enumerate() {
+            match c.tag_name().name() {
+                "element" => terms.extend(self.compile_element(doc, c, false, path).map(|e| Term::Element(Box::new(e)))),
+                kind @ ("sequence" | "choice") => {
+                    let group = self.compile_model_group(doc, c, PropertySet::default(), &format!("{path}/{kind}[{}]", index + 1));
+                    terms.push(Term::Group(Box::new(group)));
+                },
+                "group" => {
+                    let group = self.compile_group_ref(c, &format!("{path}/group[{}]", index + 1));
+                    terms.extend(group.map(|group| Term::Group(Box::new(group))));
+                },
+                other => self.error(c, path, SchemaError::Unsupported(format!("xs:{other} in model group"))),
+            }
+        }
+        let (asserts, discriminator) = self.statements(node, if kind == GroupKind::Sequence { "xs:sequence" } else { "xs:choice" }, path);
+        ModelGroup { kind, format, terms, asserts, discriminator }
+    }
+
+    /// compiles the `xs:group` reference `node`, combining its properties with those of the referenced group's model group
+    fn compile_group_ref(&self, node: Node, path: &str) -> Option<ModelGroup> {
+        let Some(reference) = node.attribute("ref") else {
+            self.error(node, path, SchemaError::MissingAttribute { attribute: "ref", component: "xs:group" });
+            return None;
+        };
+        let name = self.qname(node, reference).map_err(|e| self.error(node, path, e)).ok()?;
+        let Some(global) = self.groups.get(&name).copied() else {
+            self.error(node, path, SchemaError::UnresolvedReference { kind: "group", name: name.to_string() });
+            return None;
+        };
+        let reference_properties = self.local_properties(node, "group", path);
+        self.guarded("group", &name, node, path, || {
+            let Some(group) = xsd_children(global.node).find(|c| is_xsd(*c, "sequence") || is_xsd(*c, "choice")) else {
+                self.error(global.node, path, SchemaError::Unsupported("xs:group without a sequence or choice".to_string()));
+                return None;
+            };
+            Some(self.compile_model_group(&self.documents[global.doc], group, reference_properties, path))
+        })
+    }
+
+    /// compiles the simple type `node` of `doc`
+    ///
+    /// Returns the compiled type together with its DFDL properties, which are its own properties
+    /// combined with those of the simple types it is derived from.
+    fn compile_simple_type(&self, doc: &DocumentContext, node: Node, path: &str) -> Option<(SimpleType, PropertySet)> {
+        let compile = || {
+            let own = self.local_properties(node, "simpleType", path);
+            let Some(restriction) = xsd_children(node).find(|c| is_xsd(*c, "restriction")) else {
+                self.error(node, path, SchemaError::Unsupported("xs:simpleType without xs:restriction".to_string()));
+                return None;
+            };
+            let Some(base) = restriction.attribute("base") else {
+                self.error(restriction, path, SchemaError::MissingAttribute { attribute: "base", component: "xs:restriction" });
+                return None;
+            };
+            let (primitive, inherited, max_length, facets) = match self.resolve_type(restriction, base, path)? {
+                TypeDefinition::Builtin(primitive) => (primitive, PropertySet::default(), None, Facets::default()),
+                TypeDefinition::Defined(base_doc, definition) if is_xsd(definition, "simpleType") => {
+                    let (base_type, properties) = self.compile_simple_type(base_doc, definition, path)?;
+                    (base_type.primitive, properties, base_type.max_length, base_type.facets)
+                },
+                TypeDefinition::Defined(..) => {
+                    self.error(restriction, path, SchemaError::UnresolvedType(format!("{base} is not a simple type")));
+                    return None;
+                },
+            };
+            let (max_length, facets) = self.compile_facets(restriction, primitive, max_length, facets, path);
+            let properties = self.combine(inherited, own, node, path);
+            let simple_type = SimpleType {
+                name: node.attribute("name").map(str::to_string),
+                primitive,
+                format: self.format(doc, properties.clone(), node, path),
+                max_length,
+                facets,
+            };
+            Some((simple_type, properties))
+        };
+        match node.attribute("name") {
+            Some(name) => self.guarded("type", &QName { namespace: doc.target_namespace.clone(), local: name.to_string() }, node, path, compile),
+            None => compile(),
+        }
+    }
+
+    /// compiles the facets of `restriction` on top of `max_length` and `facets` of its base type
+    fn compile_facets(&self, restriction: Node, primitive: PrimitiveType, mut max_length: Option<u64>, mut facets: Facets, path: &str) -> (Option<u64>, Facets) {
+        let (mut patterns, mut enumeration) = (Vec::new(), Vec::new());
+        for facet in xsd_children(restriction).filter(|c| !is_xsd(*c, "annotation")) {
+            let name = facet.tag_name().name();
+            let Some(value) = facet.attribute("value") else {
+                self.error(facet, path, SchemaError::MissingAttribute { attribute: "value", component: "facet" });
+                continue;
+            };
+            let invalid = || self.error(facet, path, SchemaError::InvalidAttribute { attribute: "value", value: value.to_string() });
+            let unsupported = || self.error(facet, path, SchemaError::Unsupported(format!("xs:{name} on a type derived from {primitive:?}")));
+            match name {
+                "length" | "minLength" | "maxLength" => match value.parse() {
+                    Ok(length) => {
+                        if name != "maxLength" {
+                            facets.min_length = Some(length);
+                        }
+                        if name != "minLength" {
+                            max_length = Some(length);
+                        }
+                    },
+                    Err(_) => invalid(),
+                },
+                "pattern" => match Regex::new(&format!("^(?:{value})$")) {
+                    Ok(_) => patterns.push(value.to_string()),
+                    Err(_) => invalid(),
+                },
+                "enumeration" => match ordered_value(primitive, value) {
+                    Some(false) => invalid(),
+                    // calendar values can not be compared yet
+                    _ if matches!(primitive, PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time) => unsupported(),
+                    _ => enumeration.push(value.to_string()),
+                },
+                "minInclusive" | "maxInclusive" | "minExclusive" | "maxExclusive" => match ordered_value(primitive, value) {
+                    Some(true) => {
+                        let bound = Some(value.trim().to_string());
+                        match name {
+                            "minInclusive" => facets.min_inclusive = bound,
+                            "maxInclusive" => facets.max_inclusive = bound,
+                            "minExclusive" => facets.min_exclusive = bound,
+                            _ => facets.max_exclusive = bound,
+                        }
+                    },
+                    Some(false) => invalid(),
+                    None => unsupported(),
+                },
+                "totalDigits" | "fractionDigits" if !is_decimal(primitive) => unsupported(),
+                "totalDigits" => match value.parse() {
+                    Ok(digits @ 1..) => facets.total_digits = Some(digits),
+                    _ => invalid(),
+                },
+                "fractionDigits" => match value.parse() {
+                    Ok(digits) => facets.fraction_digits = Some(digits),
+                    Err(_) => invalid(),
+                },
+                other => self.error(facet, path, SchemaError::Unsupported(format!("xs:{other} in xs:restriction"))),
+            }
+        }
+        // the patterns of one derivation step are alternatives, those of the steps all apply
+        if !patterns.is_empty() {
+            facets.patterns.push(patterns);
+        }
+        if !enumeration.is_empty() {
+            facets.enumeration = Some(enumeration);
+        }
+        (max_length, facets)
+    }
+}
+
+/// whether values of `primitive` are decimal numbers, whose digits `xs:totalDigits` and `xs:fractionDigits` restrict
+fn is_decimal(primitive: PrimitiveType) -> bool {
+    ordered_value(primitive, "0").is_some() && !matches!(primitive, PrimitiveType::Float | PrimitiveType::Double)
+}
+
+/// whether `value` is in the lexical space of the numeric type `primitive`,
+/// `None` if `primitive` is not a numeric type
+fn ordered_value(primitive: PrimitiveType, value: &str) -> Option<bool> {
+    let value = value.trim();
+    Some(match primitive {
+        PrimitiveType::Float | PrimitiveType::Double => matches!(value, "INF" | "+INF" | "-INF" | "NaN")
+            || (!value.chars().any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E')) && value.parse::<f64>().is_ok()),
+        PrimitiveType::Decimal => value.parse::<Decimal>().is_ok(),
+        PrimitiveType::String | PrimitiveType::HexBinary | PrimitiveType::Boolean | PrimitiveType::DateTime | PrimitiveType::Date | PrimitiveType::Time => return None,
+        _ => value.parse::<i128>().is_ok(),
+    })
+}
+
+/// The definition a type reference resolves to
+enum TypeDefinition<'c, 'a, 'input> {
+    /// one of the builtin XML Schema types
+    Builtin(PrimitiveType),
+    /// a simple or complex type definition, in the given document
+    Defined(&'c DocumentContext<'a, 'input>, Node<'a, 'input>),
+}
+
+/// the name of the kind of `group`, as used in the paths of components
+fn group_kind(group: &ModelGroup) -> &'static str {
+    match group.kind {
+        GroupKind::Sequence => "sequence",
+        GroupKind::Choice => "choice",
+    }
+}
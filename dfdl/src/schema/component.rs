@@ -0,0 +1,211 @@
+use super::{Assert, Discriminator, Format};
+
+
+/// A compiled `xs:element` declaration
+#[derive(Clone, Debug)]
+pub struct ElementDecl {
+    /// local name of the element
+    pub name: String,
+    /// namespace of the element, if it has one
+    pub namespace: Option<String>,
+    /// `minOccurs` of the element
+    pub min_occurs: u64,
+    /// `maxOccurs` of the element
+    pub max_occurs: MaxOccurs,
+    /// whether the element is `nillable`
+    pub nillable: bool,
+    /// the effective format properties of the element
+    pub format: Format,
+    /// the content of the element
+    pub content: ElementContent,
+    /// the type of the length prefix, for elements with `lengthKind="prefixed"`
+    pub prefix_length_type: Option<Box<SimpleType>>,
+    /// the `dfdl:assert`s of the element
+    pub asserts: Vec<Assert>,
+    /// the `dfdl:discriminator` of the element
+    pub discriminator: Option<Discriminator>,
+}
+
+impl ElementDecl {
+    /// Returns `true` if the element may occur more than once
+    #[must_use]
+    pub fn is_array(&self) -> bool {
+        match self.max_occurs {
+            MaxOccurs::Bounded(max) => max > 1,
+            MaxOccurs::Unbounded => true,
+        }
+    }
+    /// Returns `true` if the element may be absent
+    #[must_use]
+    pub fn is_optional(&self) -> bool {
+        self.min_occurs == 0 && self.max_occurs == MaxOccurs::Bounded(1)
+    }
+}
+
+/// Value of the `maxOccurs` attribute
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaxOccurs {
+    /// the element occurs at most the given number of times
+    Bounded(u64),
+    /// `maxOccurs="unbounded"`
+    Unbounded,
+}
+
+/// Content of an [`ElementDecl`]
+#[derive(Clone, Debug)]
+pub enum ElementContent {
+    /// the element has a simple type
+    Simple(SimpleType),
+    /// the element has a complex type, consisting of a single model group
+    Complex(ModelGroup),
+}
+
+/// A compiled simple type
+#[derive(Clone, Debug)]
+pub struct SimpleType {
+    /// name of the simple type, if it is not anonymous
+    pub name: Option<String>,
+    /// the builtin type this simple type is derived from
+    pub primitive: PrimitiveType,
+    /// the format properties defined on the simple type
+    pub format: Format,
+    /// the `xs:maxLength` (or `xs:length`) facet, which gives the length of strings
+    /// and hexBinary values with `lengthKind="implicit"`
+    pub max_length: Option<u64>,
+    /// the other facets of the simple type and the types it is derived from
+    pub facets: Facets,
+}
+
+/// The constraining facets of a [`SimpleType`] besides `xs:maxLength`, checked by `dfdl:checkConstraints`
+///
+/// Values are kept as written in the schema and interpreted in the value space of the type.
+#[derive(Clone, Debug, Default)]
+pub struct Facets {
+    /// the `xs:minLength` (or `xs:length`) facet
+    pub min_length: Option<u64>,
+    /// the `xs:pattern` facets of each derivation step, of which a value has to match
+    /// one per step
+    pub patterns: Vec<Vec<String>>,
+    /// the `xs:enumeration` facets, the values the type is restricted to
+    pub enumeration: Option<Vec<String>>,
+    /// the `xs:minInclusive` facet
+    pub min_inclusive: Option<String>,
+    /// the `xs:maxInclusive` facet
+    pub max_inclusive: Option<String>,
+    /// the `xs:minExclusive` facet
+    pub min_exclusive: Option<String>,
+    /// the `xs:maxExclusive` facet
+    pub max_exclusive: Option<String>,
+    /// the `xs:totalDigits` facet
+    pub total_digits: Option<u32>,
+    /// the `xs:fractionDigits` facet
+    pub fraction_digits: Option<u32>,
+}
+
+/// The builtin XML Schema types supported by DFDL
+///
+/// Values of each type are held in the infoset as the data variant of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimitiveType {
+    /// `xs:double`
+    Double,
+    /// `xs:float`
+    Float,
+    /// `xs:decimal`
+    Decimal,
+    /// `xs:integer`
+    Integer,
+    /// `xs:nonNegativeInteger`
+    NonNegativeInteger,
+    /// `xs:long`
+    Long,
+    /// `xs:int`
+    Int,
+    /// `xs:short`
+    Short,
+    /// `xs:byte`
+    Byte,
+    /// `xs:unsignedLong`
+    UnsignedLong,
+    /// `xs:unsignedInt`
+    UnsignedInt,
+    /// `xs:unsignedShort`
+    UnsignedShort,
+    /// `xs:unsignedByte`
+    UnsignedByte,
+    /// `xs:string`
+    String,
+    /// `xs:dateTime`
+    DateTime,
+    /// `xs:date`
+    Date,
+    /// `xs:time`
+    Time,
+    /// `xs:hexBinary`
+    HexBinary,
+    /// `xs:boolean`
+    Boolean,
+}
+
+impl PrimitiveType {
+    /// Looks up a builtin type by its local name in the XML Schema namespace
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        let primitive = match name {
+            "double" => Self::Double,
+            "float" => Self::Float,
+            "decimal" => Self::Decimal,
+            "integer" => Self::Integer,
+            "nonNegativeInteger" => Self::NonNegativeInteger,
+            "long" => Self::Long,
+            "int" => Self::Int,
+            "short" => Self::Short,
+            "byte" => Self::Byte,
+            "unsignedLong" => Self::UnsignedLong,
+            "unsignedInt" => Self::UnsignedInt,
+            "unsignedShort" => Self::UnsignedShort,
+            "unsignedByte" => Self::UnsignedByte,
+            "string" => Self::String,
+            "dateTime" => Self::DateTime,
+            "date" => Self::Date,
+            "time" => Self::Time,
+            "hexBinary" => Self::HexBinary,
+            "boolean" => Self::Boolean,
+            _ => return None,
+        };
+        Some(primitive)
+    }
+}
+
+/// A compiled model group (`xs:sequence` or `xs:choice`)
+#[derive(Clone, Debug)]
+pub struct ModelGroup {
+    /// whether this is a sequence or a choice
+    pub kind: GroupKind,
+    /// the effective format properties of the group
+    pub format: Format,
+    /// the particles of the group, in schema order
+    pub terms: Vec<Term>,
+    /// the `dfdl:assert`s of the group
+    pub asserts: Vec<Assert>,
+    /// the `dfdl:discriminator` of the group
+    pub discriminator: Option<Discriminator>,
+}
+
+/// Kind of a [`ModelGroup`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupKind {
+    /// `xs:sequence`
+    Sequence,
+    /// `xs:choice`
+    Choice,
+}
+
+/// A particle inside a [`ModelGroup`]
+#[derive(Clone, Debug)]
+pub enum Term {
+    /// a (local or referenced) element
+    Element(Box<ElementDecl>),
+    /// a nested model group
+    Group(Box<ModelGroup>),
+}
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use roxmltree::{ExpandedName, Node};
+
+use crate::XSD_NAMESPACE;
+
+use super::{Diagnostic, SchemaDefinitionError, SchemaError, SchemaResolver, SourceLocation};
+
+
+/// A single schema document taking part in a schema
+pub(super) struct SchemaDocument {
+    /// canonical location of the document, `None` for a root document given as a string
+    pub(super) location: Option<String>,
+    pub(super) source: String,
+    /// the namespace the components of this document belong to,
+    /// which for a chameleon include is the namespace of the including document
+    pub(super) target_namespace: Option<String>,
+}
+
+/// Loads the document `source` and all documents it (transitively) includes or imports
+///
+/// The root document is always the first document of the returned set,
+/// every other document is only loaded once, no matter how often it is referenced.
+pub(super) fn load_documents(location: Option<String>, source: String, resolver: &dyn SchemaResolver) -> Result<Vec<SchemaDocument>, SchemaDefinitionError> {
+    let target_namespace = schema_namespace(location.as_deref(), &source)?;
+    let mut visited: HashSet<String> = location.iter().cloned().collect();
+    // a root document given as a string has no location, so a referenced document with its source is taken to be the root
+    let root_source = location.is_none().then(|| source.clone());
+    let mut documents = Vec::new();
+    let mut pending = vec![SchemaDocument { location, source, target_namespace }];
+
+    while let Some(document) = pending.pop() {
+        let tree = parse(document.location.as_deref(), &document.source)?;
+        let references = tree.root_element().children()
+            .filter(|c| c.tag_name() == ExpandedName::from_static(XSD_NAMESPACE, "include")
+                || c.tag_name() == ExpandedName::from_static(XSD_NAMESPACE, "import"));
+        // reverse, so documents are visited in the order they are referenced
+        let references: Vec<_> = references.collect();
+        for reference in references.into_iter().rev() {
+            let is_import = reference.tag_name().name() == "import";
+            let at_reference = |kind: SchemaError| Diagnostic::error(kind).at(Some(node_location(document.location.as_deref(), reference)));
+            let schema_location = match reference.attribute("schemaLocation") {
+                Some(schema_location) => schema_location,
+                // an import of just a namespace leaves its components to other imports
+                None if is_import => continue,
+                None => return Err(at_reference(SchemaError::MissingAttribute { attribute: "schemaLocation", component: "xs:include" }).into()),
+            };
+            let canonical = resolver.resolve(schema_location, document.location.as_deref()).map_err(at_reference)?;
+            if !visited.insert(canonical.clone()) { continue; }
+
+            let source = resolver.load(&canonical).map_err(at_reference)?.into_owned();
+            if root_source.as_ref() == Some(&source) { continue; }
+            let declared = schema_namespace(Some(&canonical), &source)?;
+            let target_namespace = if is_import {
+                let expected = reference.attribute("namespace").map(str::to_string);
+                if declared != expected || declared == document.target_namespace {
+                    return Err(at_reference(SchemaError::NamespaceMismatch { location: canonical, expected, found: declared }).into());
+                }
+                declared
+            }
+            else {
+                if declared.is_some() && declared != document.target_namespace {
+                    return Err(at_reference(SchemaError::NamespaceMismatch { location: canonical, expected: document.target_namespace.clone(), found: declared }).into());
+                }
+                // chameleon include: the included components take on the namespace of the includer
+                document.target_namespace.clone()
+            };
+            pending.push(SchemaDocument { location: Some(canonical), source, target_namespace });
+        }
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+/// checks that `source`, the document at `file`, is a schema document and returns its target namespace
+fn schema_namespace(file: Option<&str>, source: &str) -> Result<Option<String>, SchemaDefinitionError> {
+    let tree = parse(file, source)?;
+    let root = tree.root_element();
+    if root.tag_name() != ExpandedName::from_static(XSD_NAMESPACE, "schema") {
+        return Err(Diagnostic::error(SchemaError::NotASchema).at(Some(node_location(file, root))).into());
+    }
+    Ok(root.attribute("targetNamespace").map(str::to_string))
+}
+
+/// parses `source`, the document at `file`, reporting malformed XML at the position of the problem
+pub(super) fn parse<'input>(file: Option<&str>, source: &'input str) -> Result<roxmltree::Document<'input>, SchemaDefinitionError> {
+    roxmltree::Document::parse(source).map_err(|e| {
+        let pos = e.pos();
+        Diagnostic::error(SchemaError::XmlError(e))
+            .at(Some(SourceLocation { file: file.map(str::to_string), line: pos.row, column: pos.col }))
+            .into()
+    })
+}
+
+/// the position of `node` in the document at `file`
+pub(super) fn node_location(file: Option<&str>, node: Node) -> SourceLocation {
+    text_location(file, node, node.range().start)
+}
+
+/// the position of the byte offset `pos` in the document of `node`, which is at `file`
+pub(super) fn text_location(file: Option<&str>, node: Node, pos: usize) -> SourceLocation {
+    let pos = node.document().text_pos_at(pos);
+    SourceLocation { file: file.map(str::to_string), line: pos.row, column: pos.col }
+}
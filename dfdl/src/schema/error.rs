@@ -0,0 +1,273 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use super::ExpressionError;
+
+
+/// Errors occuring while loading and compiling a DFDL schema
+#[derive(Clone, Debug, Error)]
+pub enum SchemaError {
+    /// the schema document is not well-formed XML
+    #[error("xml error: {0}")]
+    XmlError(#[from] roxmltree::Error),
+    /// the root of the document is not an `xs:schema` element
+    #[error("xml document is not a schema document")]
+    NotASchema,
+    /// a DFDL annotation uses a property that is not known
+    #[error("unknown dfdl property: {0}")]
+    UnknownProperty(String),
+    /// a property holds a value that is not valid for it
+    #[error("invalid value '{value}' for property '{property}': {reason}")]
+    InvalidPropertyValue{
+        /// name of the property
+        property: String,
+        /// the offending value
+        value: String,
+        /// description of the valid values
+        reason: String,
+    },
+    /// an attribute holds a value that is not valid for it
+    #[error("invalid value '{value}' for attribute '{attribute}'")]
+    InvalidAttribute{
+        /// name of the attribute
+        attribute: &'static str,
+        /// the offending value
+        value: String,
+    },
+    /// a required attribute is missing on a schema component
+    #[error("missing attribute '{attribute}' on {component}")]
+    MissingAttribute{
+        /// name of the attribute
+        attribute: &'static str,
+        /// kind of component the attribute is missing on
+        component: &'static str,
+    },
+    /// a type reference could not be resolved
+    #[error("unresolved type: {0}")]
+    UnresolvedType(String),
+    /// a reference to a global element or group could not be resolved
+    #[error("unresolved {kind} reference: {name}")]
+    UnresolvedReference{
+        /// kind of the referenced definition
+        kind: &'static str,
+        /// the referenced name
+        name: String,
+    },
+    /// a global definition refers to itself, directly or indirectly
+    #[error("recursive definition of {0}")]
+    CircularReference(String),
+    /// a property is set more than once on the same component
+    #[error("property '{0}' is defined more than once")]
+    DuplicateProperty(String),
+    /// a component carries more than one format annotation
+    #[error("more than one {annotation} annotation on {component}")]
+    DuplicateAnnotation{
+        /// kind of the annotation
+        annotation: &'static str,
+        /// kind of the annotated component
+        component: &'static str,
+    },
+    /// a DFDL annotation is placed on a component that does not allow it
+    #[error("{annotation} is not allowed on {component}")]
+    MisplacedAnnotation{
+        /// the misplaced annotation
+        annotation: String,
+        /// kind of the annotated component
+        component: &'static str,
+    },
+    /// a QName uses a namespace prefix that is not declared
+    #[error("undeclared namespace prefix: {0}")]
+    UnknownPrefix(String),
+    /// two global definitions of the same kind share a name
+    #[error("duplicate definition of {0}")]
+    DuplicateDefinition(String),
+    /// a `ref` names a format that is not defined via `dfdl:defineFormat`
+    #[error("undefined format: {0}")]
+    UndefinedFormat(String),
+    /// format definitions reference each other in a cycle
+    #[error("circular reference to format {0}")]
+    CircularFormatReference(String),
+    /// a referenced schema document could not be located or loaded
+    #[error("failed to resolve schema document '{location}': {reason}")]
+    Resolve{
+        /// the location that failed to resolve
+        location: String,
+        /// description of the failure
+        reason: String,
+    },
+    /// an included or imported document has an unexpected target namespace
+    #[error("schema document '{location}' has target namespace {found:?}, expected {expected:?}")]
+    NamespaceMismatch{
+        /// canonical location of the document
+        location: String,
+        /// namespace required by the `xs:include` or `xs:import`
+        expected: Option<String>,
+        /// target namespace declared by the document
+        found: Option<String>,
+    },
+    /// the schema uses an XML schema construct that is not part of the DFDL subset
+    #[error("unsupported schema construct: {0}")]
+    Unsupported(String),
+    /// a DFDL expression is not valid, or does not fit the schema it is used in
+    #[error("invalid expression '{{{expression}}}': {error}")]
+    InvalidExpression{
+        /// source text of the expression
+        expression: String,
+        /// the problem with the expression
+        error: ExpressionError,
+    },
+    /// an annotation that looks like a DFDL annotation is ignored
+    #[error("annotation ignored: {0}")]
+    IgnoredAnnotation(String),
+}
+
+/// How severe a [`Diagnostic`] is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// a schema definition error, the schema can not be used
+    Error,
+    /// a problem that does not prevent the schema from being used
+    Warning,
+}
+
+/// A position in a schema document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// location of the schema document, `None` for a root document given as a string
+    pub file: Option<String>,
+    /// line number, starting at 1
+    pub line: u32,
+    /// column number, starting at 1
+    pub column: u32,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file.as_deref().unwrap_or("<schema>"), self.line, self.column)
+    }
+}
+
+/// A single error or warning found while compiling a schema
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// whether this is an error or a warning
+    pub severity: Severity,
+    /// what went wrong
+    pub kind: SchemaError,
+    /// where in the schema documents the problem is, if known
+    pub location: Option<SourceLocation>,
+    /// path of the schema component the problem was found in, e.g. `/record/sequence[1]/field`
+    pub component: Option<String>,
+    /// the DFDL property the problem concerns, if any
+    pub property: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error diagnostic without location information
+    #[must_use]
+    pub fn error(kind: SchemaError) -> Self {
+        let property = match &kind {
+            SchemaError::UnknownProperty(property)
+            | SchemaError::DuplicateProperty(property)
+            | SchemaError::InvalidPropertyValue { property, .. } => Some(property.clone()),
+            _ => None,
+        };
+        Self { severity: Severity::Error, kind, location: None, component: None, property }
+    }
+    /// Creates a warning diagnostic without location information
+    #[must_use]
+    pub fn warning(kind: SchemaError) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(kind) }
+    }
+    /// Returns `self` located at `location`
+    #[must_use]
+    pub fn at(mut self, location: Option<SourceLocation>) -> Self {
+        self.location = location;
+        self
+    }
+    /// Returns `self` attributed to the component at `path`
+    #[must_use]
+    pub fn in_component(mut self, path: impl Into<String>) -> Self {
+        self.component = Some(path.into());
+        self
+    }
+}
+
+impl From<SchemaError> for Diagnostic {
+    fn from(kind: SchemaError) -> Self {
+        Self::error(kind)
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Error => f.write_str("error")?,
+            Severity::Warning => f.write_str("warning")?,
+        }
+        if let Some(location) = &self.location { write!(f, " at {location}")?; }
+        if let Some(component) = &self.component { write!(f, " in {component}")?; }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// The schema definition errors (SDEs) found while compiling a schema
+///
+/// Compilation continues past the first error where possible, so all errors of a schema,
+/// and the warnings found along the way, are reported at once.
+#[derive(Clone, Debug)]
+pub struct SchemaDefinitionError {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SchemaDefinitionError {
+    /// `diagnostics` has to contain at least one error
+    pub(crate) fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        debug_assert!(diagnostics.iter().any(|d| d.severity == Severity::Error));
+        Self { diagnostics }
+    }
+    /// Returns all errors and warnings, in the order they were found
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    /// Iterates over the errors
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+    /// Iterates over the warnings
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+    }
+    /// Returns the kind of the first error
+    #[must_use]
+    pub fn first(&self) -> &SchemaError {
+        &self.errors().next().expect("a schema definition error contains at least one error").kind
+    }
+}
+
+impl From<Diagnostic> for SchemaDefinitionError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self::new(vec![diagnostic])
+    }
+}
+
+impl From<SchemaError> for SchemaDefinitionError {
+    fn from(kind: SchemaError) -> Self {
+        Diagnostic::from(kind).into()
+    }
+}
+
+impl Display for SchemaDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self.errors().count();
+        write!(f, "schema definition error{}", if errors == 1 { "" } else { "s" })?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n  {diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SchemaDefinitionError {}
@@ -0,0 +1,372 @@
+use crate::schema::{ElementContent, ElementDecl, ModelGroup, PrimitiveType, Term};
+
+use super::{BinaryOperator, Expression, ExpressionError, ExpressionKind, Function, Literal, PathExpression, Step, Type};
+
+
+/// The type a property or statement requires of the value of its expression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Expected {
+    /// `xs:boolean`, for asserts and discriminators
+    Boolean,
+    /// an integer type, for lengths and counts
+    Integer,
+    /// `xs:string`, for properties such as `encoding`
+    String,
+    /// any simple type, for calculated values
+    Any,
+}
+
+/// The elements an expression can refer to: an element along with its child elements,
+/// as seen through any model groups between them
+#[derive(Clone, Debug)]
+pub(crate) struct Scope {
+    name: String,
+    ty: Type,
+    array: bool,
+    children: Vec<Scope>,
+}
+
+impl Scope {
+    pub(crate) fn new(decl: &ElementDecl) -> Self {
+        let (ty, children) = match &decl.content {
+            ElementContent::Simple(simple) => (Type::Atomic(simple.primitive), Vec::new()),
+            ElementContent::Complex(group) => {
+                let mut children = Vec::new();
+                collect_children(group, &mut children);
+                (Type::Complex, children)
+            },
+        };
+        Self { name: decl.name.clone(), ty, array: decl.is_array(), children }
+    }
+}
+
+/// adds the scopes of the elements of `group` to `children`, in schema order
+fn collect_children(group: &ModelGroup, children: &mut Vec<Scope>) {
+    for term in &group.terms {
+        match term {
+            Term::Element(decl) => children.push(Scope::new(decl)),
+            Term::Group(group) => collect_children(group, children),
+        }
+    }
+}
+
+/// type checks `expression`, evaluated at the element reached from `root` by following the
+/// children at the indices of `context`, and fills in the types of its nodes
+///
+/// # Errors
+///
+/// This function will return an error if a path of the expression does not lead to an element,
+/// or if a value does not have the type required where it is used.
+pub(crate) fn check(expression: &mut Expression, root: &Scope, context: &[usize], expected: Expected) -> Result<(), ExpressionError> {
+    let mut scopes = vec![root];
+    for &index in context {
+        scopes.push(&scopes[scopes.len() - 1].children[index]);
+    }
+    let checker = Checker { root, context: scopes };
+    let ty = checker.value(expression)?;
+    let matches = match expected {
+        Expected::Boolean => ty == PrimitiveType::Boolean,
+        Expected::Integer => is_integer(ty),
+        Expected::String => ty == PrimitiveType::String,
+        Expected::Any => true,
+    };
+    if !matches {
+        let expected = match expected {
+            Expected::Boolean => "xs:boolean",
+            Expected::Integer => "an integer",
+            Expected::String => "xs:string",
+            Expected::Any => unreachable!(),
+        };
+        return Err(ExpressionError::TypeMismatch(format!("expected {expected}, but the expression is of type {}", type_name(ty))));
+    }
+    Ok(())
+}
+
+/// the state of type checking an expression
+struct Checker<'s> {
+    root: &'s Scope,
+    /// the element the expression is evaluated at, along with its ancestors
+    context: Vec<&'s Scope>,
+}
+
+impl Checker<'_> {
+    /// type checks `expression`, which has to have a simple value
+    fn value(&self, expression: &mut Expression) -> Result<PrimitiveType, ExpressionError> {
+        let ty = self.node(expression)?;
+        match (&expression.kind, ty) {
+            (ExpressionKind::Path(path), _) if path.sequence => {
+                Err(ExpressionError::TypeMismatch(format!("the path '{path}' leads to an array, its occurrences have to be selected by an index")))
+            },
+            (_, Type::Atomic(primitive)) => Ok(primitive),
+            (ExpressionKind::Path(path), _) => Err(ExpressionError::TypeMismatch(format!("the path '{path}' leads to a complex element, which has no value"))),
+            _ => unreachable!("only paths lead to complex elements"),
+        }
+    }
+
+    /// type checks `expression`, which may be a path to a complex element or to every occurrence of an array
+    fn node(&self, expression: &mut Expression) -> Result<Type, ExpressionError> {
+        let ty = match &mut expression.kind {
+            ExpressionKind::Literal(literal) => Type::Atomic(match literal {
+                Literal::String(_) => PrimitiveType::String,
+                Literal::Integer(_) => PrimitiveType::Integer,
+                Literal::Decimal(_) => PrimitiveType::Decimal,
+                Literal::Double(_) => PrimitiveType::Double,
+            }),
+            ExpressionKind::Path(path) => self.path(path)?,
+            ExpressionKind::Negate(operand) => {
+                let ty = self.numeric(operand)?;
+                Type::Atomic(if is_integer(ty) { PrimitiveType::Integer } else { ty })
+            },
+            ExpressionKind::Binary { operator, left, right } => Type::Atomic(self.binary(*operator, left, right)?),
+            ExpressionKind::If { condition, then, otherwise } => {
+                self.boolean(condition)?;
+                let then = self.value(then)?;
+                let otherwise = self.value(otherwise)?;
+                Type::Atomic(if then == otherwise {
+                    then
+                }
+                else if is_numeric(then) && is_numeric(otherwise) {
+                    promote(then, otherwise)
+                }
+                else {
+                    return Err(ExpressionError::TypeMismatch(format!("the branches of an if expression have the different types {} and {}", type_name(then), type_name(otherwise))));
+                })
+            },
+            ExpressionKind::Cast { operand, target } => {
+                let from = self.value(operand)?;
+                if !castable(from, *target) {
+                    return Err(ExpressionError::TypeMismatch(format!("{} can not be converted to {}", type_name(from), type_name(*target))));
+                }
+                Type::Atomic(*target)
+            },
+            ExpressionKind::Call { function, arguments } => Type::Atomic(self.call(*function, arguments)?),
+        };
+        expression.ty = ty;
+        Ok(ty)
+    }
+
+    /// type checks `expression`, which has to be a number
+    fn numeric(&self, expression: &mut Expression) -> Result<PrimitiveType, ExpressionError> {
+        let ty = self.value(expression)?;
+        if !is_numeric(ty) {
+            return Err(ExpressionError::TypeMismatch(format!("expected a number, found {}", type_name(ty))));
+        }
+        Ok(ty)
+    }
+
+    /// type checks `expression`, which has to be of type `expected`
+    fn typed(&self, expression: &mut Expression, expected: PrimitiveType) -> Result<(), ExpressionError> {
+        let ty = self.value(expression)?;
+        if ty != expected && !(is_integer(expected) && is_integer(ty)) {
+            return Err(ExpressionError::TypeMismatch(format!("expected {}, found {}", type_name(expected), type_name(ty))));
+        }
+        Ok(())
+    }
+
+    fn boolean(&self, expression: &mut Expression) -> Result<(), ExpressionError> {
+        self.typed(expression, PrimitiveType::Boolean)
+    }
+
+    fn binary(&self, operator: BinaryOperator, left: &mut Expression, right: &mut Expression) -> Result<PrimitiveType, ExpressionError> {
+        match operator {
+            BinaryOperator::Or | BinaryOperator::And => {
+                self.boolean(left)?;
+                self.boolean(right)?;
+                Ok(PrimitiveType::Boolean)
+            },
+            _ if operator.is_comparison() => {
+                let (left, right) = (self.value(left)?, self.value(right)?);
+                if left != right && !(is_numeric(left) && is_numeric(right)) {
+                    return Err(ExpressionError::TypeMismatch(format!("values of types {} and {} can not be compared", type_name(left), type_name(right))));
+                }
+                Ok(PrimitiveType::Boolean)
+            },
+            _ => {
+                let (left, right) = (self.numeric(left)?, self.numeric(right)?);
+                Ok(match operator {
+                    BinaryOperator::IntegerDivide => PrimitiveType::Integer,
+                    BinaryOperator::Divide if is_integer(left) && is_integer(right) => PrimitiveType::Decimal,
+                    _ => promote(left, right),
+                })
+            },
+        }
+    }
+
+    fn call(&self, function: Function, arguments: &mut [Expression]) -> Result<PrimitiveType, ExpressionError> {
+        use PrimitiveType as P;
+
+        match function {
+            Function::Count | Function::Exists | Function::Empty => {
+                self.path_argument(function, &mut arguments[0], true)?;
+                Ok(if function == Function::Count { P::Integer } else { P::Boolean })
+            },
+            Function::True | Function::False => Ok(P::Boolean),
+            Function::Not => self.boolean(&mut arguments[0]).map(|()| P::Boolean),
+            Function::StringLength => self.typed(&mut arguments[0], P::String).map(|()| P::Integer),
+            Function::Concat => {
+                for argument in arguments {
+                    self.value(argument)?;
+                }
+                Ok(P::String)
+            },
+            Function::Substring => {
+                self.typed(&mut arguments[0], P::String)?;
+                for argument in &mut arguments[1..] {
+                    self.numeric(argument)?;
+                }
+                Ok(P::String)
+            },
+            Function::UpperCase | Function::LowerCase | Function::DecodeDfdlEntities | Function::EncodeDfdlEntities => {
+                self.typed(&mut arguments[0], P::String).map(|()| P::String)
+            },
+            Function::Contains | Function::StartsWith | Function::EndsWith => {
+                self.typed(&mut arguments[0], P::String)?;
+                self.typed(&mut arguments[1], P::String)?;
+                Ok(P::Boolean)
+            },
+            Function::Abs | Function::Ceiling | Function::Floor | Function::Round | Function::RoundHalfToEven => {
+                let ty = self.numeric(&mut arguments[0])?;
+                if let Some(precision) = arguments.get_mut(1) {
+                    self.typed(precision, P::Integer)?;
+                }
+                Ok(ty)
+            },
+            Function::ValueLength | Function::ContentLength => {
+                self.path_argument(function, &mut arguments[0], false)?;
+                self.typed(&mut arguments[1], P::String)?;
+                Ok(P::UnsignedLong)
+            },
+            Function::OccursIndex => Ok(P::UnsignedLong),
+            Function::CheckConstraints => {
+                if self.path_argument(function, &mut arguments[0], false)? == Type::Complex {
+                    return Err(ExpressionError::TypeMismatch(format!("{} requires a simple element", function.name())));
+                }
+                Ok(P::Boolean)
+            },
+            Function::TestBit => {
+                self.typed(&mut arguments[0], P::Integer)?;
+                self.typed(&mut arguments[1], P::Integer)?;
+                Ok(P::Boolean)
+            },
+            Function::SetBits => {
+                for argument in arguments {
+                    self.typed(argument, P::Integer)?;
+                }
+                Ok(P::UnsignedByte)
+            },
+        }
+    }
+
+    /// type checks the argument of `function` that has to be a path,
+    /// which may lead to every occurrence of an array if `sequence` is set
+    fn path_argument(&self, function: Function, argument: &mut Expression, sequence: bool) -> Result<Type, ExpressionError> {
+        let ExpressionKind::Path(path) = &mut argument.kind else {
+            return Err(ExpressionError::TypeMismatch(format!("the argument of {} has to be a path", function.name())));
+        };
+        let ty = self.path(path)?;
+        if path.sequence && !sequence {
+            return Err(ExpressionError::TypeMismatch(format!("the path '{path}' leads to an array, its occurrences have to be selected by an index")));
+        }
+        argument.ty = ty;
+        Ok(ty)
+    }
+
+    /// follows `path`, returning the type of the element it leads to and marking whether it leads to
+    /// every occurrence of an array
+    fn path(&self, path: &mut PathExpression) -> Result<Type, ExpressionError> {
+        let text = path.to_string();
+        // the elements from the root to the current one, empty for the document above the root
+        let mut position: Vec<&Scope> = if path.absolute { Vec::new() } else { self.context.clone() };
+        let mut sequence = false;
+        for step in &mut path.steps {
+            match step {
+                Step::Current => {},
+                Step::Parent => {
+                    if position.pop().is_none() {
+                        return Err(ExpressionError::UnknownPath(text.clone()));
+                    }
+                },
+                Step::Child { name, index } => {
+                    if let Some(index) = index {
+                        self.typed(index, PrimitiveType::Integer)?;
+                    }
+                    let child = match position.last() {
+                        None => (self.root.name == *name).then_some(self.root),
+                        Some(scope) => scope.children.iter().find(|child| child.name == *name),
+                    };
+                    let Some(child) = child else { return Err(ExpressionError::UnknownPath(text.clone())) };
+                    sequence |= child.array && index.is_none();
+                    position.push(child);
+                },
+            }
+        }
+        let Some(scope) = position.last() else { return Err(ExpressionError::UnknownPath(text)) };
+        path.sequence = sequence;
+        Ok(scope.ty)
+    }
+}
+
+/// whether `ty` is `xs:integer` or one of the types derived from it
+fn is_integer(ty: PrimitiveType) -> bool {
+    use PrimitiveType as P;
+    matches!(ty, P::Integer | P::NonNegativeInteger | P::Long | P::Int | P::Short | P::Byte | P::UnsignedLong | P::UnsignedInt | P::UnsignedShort | P::UnsignedByte)
+}
+
+fn is_numeric(ty: PrimitiveType) -> bool {
+    is_integer(ty) || matches!(ty, PrimitiveType::Decimal | PrimitiveType::Float | PrimitiveType::Double)
+}
+
+/// the type arithmetic on numbers of types `left` and `right` results in
+fn promote(left: PrimitiveType, right: PrimitiveType) -> PrimitiveType {
+    use PrimitiveType as P;
+    if left == P::Double || right == P::Double {
+        P::Double
+    }
+    else if left == P::Float || right == P::Float {
+        P::Float
+    }
+    else if left == P::Decimal || right == P::Decimal {
+        P::Decimal
+    }
+    else {
+        P::Integer
+    }
+}
+
+/// whether values of type `from` can be converted to `to`
+fn castable(from: PrimitiveType, to: PrimitiveType) -> bool {
+    use PrimitiveType as P;
+    let numeric_or_boolean = |ty| is_numeric(ty) || ty == P::Boolean;
+    from == to
+        || from == P::String
+        || to == P::String
+        || (numeric_or_boolean(from) && numeric_or_boolean(to))
+        || (from == P::DateTime && matches!(to, P::Date | P::Time))
+        || (is_integer(from) && to == P::HexBinary)
+}
+
+/// the name of `ty` in error messages
+fn type_name(ty: PrimitiveType) -> String {
+    let name = match ty {
+        PrimitiveType::Double => "double",
+        PrimitiveType::Float => "float",
+        PrimitiveType::Decimal => "decimal",
+        PrimitiveType::Integer => "integer",
+        PrimitiveType::NonNegativeInteger => "nonNegativeInteger",
+        PrimitiveType::Long => "long",
+        PrimitiveType::Int => "int",
+        PrimitiveType::Short => "short",
+        PrimitiveType::Byte => "byte",
+        PrimitiveType::UnsignedLong => "unsignedLong",
+        PrimitiveType::UnsignedInt => "unsignedInt",
+        PrimitiveType::UnsignedShort => "unsignedShort",
+        PrimitiveType::UnsignedByte => "unsignedByte",
+        PrimitiveType::String => "string",
+        PrimitiveType::DateTime => "dateTime",
+        PrimitiveType::Date => "date",
+        PrimitiveType::Time => "time",
+        PrimitiveType::HexBinary => "hexBinary",
+        PrimitiveType::Boolean => "boolean",
+    };
+    format!("xs:{name}")
+}
@@ -0,0 +1,436 @@
+//! The DFDL expression language, a subset of XPath 2.0
+//!
+//! Expressions are parsed into an [`Expression`] tree when a schema is loaded. Once the schema
+//! is compiled, every expression is type checked against the elements it refers to, which fills
+//! in the [`Type`] of each of its nodes.
+
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use super::PrimitiveType;
+
+mod checker;
+mod parser;
+
+pub(crate) use checker::{check, Expected, Scope};
+
+
+/// Problems with a DFDL expression, found while parsing or type checking it
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ExpressionError {
+    /// the expression is not well-formed
+    #[error("syntax error at offset {position}: {reason}")]
+    Syntax {
+        /// byte offset of the problem in the expression
+        position: usize,
+        /// description of the problem
+        reason: String,
+    },
+    /// the expression uses a construct of XPath 2.0 that is not part of the DFDL subset
+    #[error("{0} not allowed in DFDL expressions")]
+    Disallowed(String),
+    /// a function call names a function that does not exist
+    #[error("unknown function {0}")]
+    UnknownFunction(String),
+    /// a cast or constructor function names a type that does not exist
+    #[error("unknown type {0}")]
+    UnknownType(String),
+    /// a function is called with the wrong number of arguments
+    #[error("{function} takes {expected} arguments, but is called with {found}")]
+    Arity {
+        /// name of the function
+        function: &'static str,
+        /// the number of arguments the function takes
+        expected: &'static str,
+        /// the number of arguments given
+        found: usize,
+    },
+    /// a path does not lead to an element of the schema
+    #[error("the path '{0}' does not lead to an element")]
+    UnknownPath(String),
+    /// a value does not have the type required where it is used
+    #[error("type mismatch: {0}")]
+    TypeMismatch(String),
+}
+
+/// A node of a parsed DFDL expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expression {
+    /// what the node computes
+    pub kind: ExpressionKind,
+    /// the type of the value of the node, [`Type::Unknown`] until the expression is type checked
+    pub ty: Type,
+}
+
+/// The kinds of [`Expression`] nodes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExpressionKind {
+    /// a literal value
+    Literal(Literal),
+    /// the value of the element a path leads to
+    Path(PathExpression),
+    /// the negation of a number
+    Negate(Box<Expression>),
+    /// a binary operation
+    Binary {
+        /// the operator
+        operator: BinaryOperator,
+        /// the left operand
+        left: Box<Expression>,
+        /// the right operand
+        right: Box<Expression>,
+    },
+    /// `if (condition) then ... else ...`
+    If {
+        /// the condition
+        condition: Box<Expression>,
+        /// the value if the condition holds
+        then: Box<Expression>,
+        /// the value otherwise
+        otherwise: Box<Expression>,
+    },
+    /// a conversion to a builtin type, via `cast as` or a constructor function like `xs:int(...)`
+    Cast {
+        /// the value to convert
+        operand: Box<Expression>,
+        /// the type to convert to
+        target: PrimitiveType,
+    },
+    /// a call of a function of the function library
+    Call {
+        /// the function
+        function: Function,
+        /// the arguments
+        arguments: Vec<Expression>,
+    },
+}
+
+/// A literal in an expression
+///
+/// Decimal and double literals keep their source text, so that they can be read at full precision.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Literal {
+    /// a string literal, without its quotes
+    String(String),
+    /// an integer literal, of type `xs:integer`
+    Integer(i128),
+    /// a literal with a decimal point, of type `xs:decimal`
+    Decimal(String),
+    /// a literal with an exponent, of type `xs:double`
+    Double(String),
+}
+
+/// A path leading to an element of the infoset, such as `../header/count` or `/record/item[2]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathExpression {
+    /// whether the path starts at the root of the infoset rather than the context of the expression
+    pub absolute: bool,
+    /// the steps of the path
+    pub steps: Vec<Step>,
+    /// whether the path leads to every occurrence of an array rather than a single element,
+    /// which is only allowed as the argument of functions like `fn:count`; set by type checking
+    pub sequence: bool,
+}
+
+/// A step of a [`PathExpression`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `..`
+    Parent,
+    /// `.`
+    Current,
+    /// a child element
+    Child {
+        /// local name of the element, namespace prefixes are not significant
+        name: String,
+        /// the (1-based) index of the occurrence, for arrays
+        index: Option<Box<Expression>>,
+    },
+}
+
+/// The binary operators of the expression language
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOperator {
+    /// `or`
+    Or,
+    /// `and`
+    And,
+    /// `=` or `eq`
+    Equal,
+    /// `!=` or `ne`
+    NotEqual,
+    /// `<` or `lt`
+    Less,
+    /// `<=` or `le`
+    LessOrEqual,
+    /// `>` or `gt`
+    Greater,
+    /// `>=` or `ge`
+    GreaterOrEqual,
+    /// `+`
+    Add,
+    /// `-`
+    Subtract,
+    /// `*`
+    Multiply,
+    /// `div`
+    Divide,
+    /// `idiv`
+    IntegerDivide,
+    /// `mod`
+    Modulo,
+}
+
+impl BinaryOperator {
+    /// Returns `true` for the comparison operators
+    #[must_use]
+    pub fn is_comparison(self) -> bool {
+        matches!(self, Self::Equal | Self::NotEqual | Self::Less | Self::LessOrEqual | Self::Greater | Self::GreaterOrEqual)
+    }
+}
+
+/// The functions of the `fn:` and `dfdl:` function library
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+    /// `fn:count`
+    Count,
+    /// `fn:exists`
+    Exists,
+    /// `fn:empty`
+    Empty,
+    /// `fn:true`
+    True,
+    /// `fn:false`
+    False,
+    /// `fn:not`
+    Not,
+    /// `fn:string-length`
+    StringLength,
+    /// `fn:concat`
+    Concat,
+    /// `fn:substring`
+    Substring,
+    /// `fn:upper-case`
+    UpperCase,
+    /// `fn:lower-case`
+    LowerCase,
+    /// `fn:contains`
+    Contains,
+    /// `fn:starts-with`
+    StartsWith,
+    /// `fn:ends-with`
+    EndsWith,
+    /// `fn:abs`
+    Abs,
+    /// `fn:ceiling`
+    Ceiling,
+    /// `fn:floor`
+    Floor,
+    /// `fn:round`
+    Round,
+    /// `fn:round-half-to-even`
+    RoundHalfToEven,
+    /// `dfdl:valueLength`
+    ValueLength,
+    /// `dfdl:contentLength`
+    ContentLength,
+    /// `dfdl:occursIndex`
+    OccursIndex,
+    /// `dfdl:checkConstraints`
+    CheckConstraints,
+    /// `dfdl:testBit`
+    TestBit,
+    /// `dfdl:setBits`
+    SetBits,
+    /// `dfdl:decodeDFDLEntities`
+    DecodeDfdlEntities,
+    /// `dfdl:encodeDFDLEntities`
+    EncodeDfdlEntities,
+}
+
+impl Function {
+    /// Looks up a function by its namespace prefix (`fn`, `dfdl`, or none for `fn`) and local name
+    #[must_use]
+    pub fn from_name(prefix: Option<&str>, local: &str) -> Option<Self> {
+        let function = match (prefix.unwrap_or("fn"), local) {
+            ("fn", "count") => Self::Count,
+            ("fn", "exists") => Self::Exists,
+            ("fn", "empty") => Self::Empty,
+            ("fn", "true") => Self::True,
+            ("fn", "false") => Self::False,
+            ("fn", "not") => Self::Not,
+            ("fn", "string-length") => Self::StringLength,
+            ("fn", "concat") => Self::Concat,
+            ("fn", "substring") => Self::Substring,
+            ("fn", "upper-case") => Self::UpperCase,
+            ("fn", "lower-case") => Self::LowerCase,
+            ("fn", "contains") => Self::Contains,
+            ("fn", "starts-with") => Self::StartsWith,
+            ("fn", "ends-with") => Self::EndsWith,
+            ("fn", "abs") => Self::Abs,
+            ("fn", "ceiling") => Self::Ceiling,
+            ("fn", "floor") => Self::Floor,
+            ("fn", "round") => Self::Round,
+            ("fn", "round-half-to-even") => Self::RoundHalfToEven,
+            ("dfdl", "valueLength") => Self::ValueLength,
+            ("dfdl", "contentLength") => Self::ContentLength,
+            ("dfdl", "occursIndex") => Self::OccursIndex,
+            ("dfdl", "checkConstraints") => Self::CheckConstraints,
+            ("dfdl", "testBit") => Self::TestBit,
+            ("dfdl", "setBits") => Self::SetBits,
+            ("dfdl", "decodeDFDLEntities") => Self::DecodeDfdlEntities,
+            ("dfdl", "encodeDFDLEntities") => Self::EncodeDfdlEntities,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// Returns the name of the function, including its namespace prefix
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Count => "fn:count",
+            Self::Exists => "fn:exists",
+            Self::Empty => "fn:empty",
+            Self::True => "fn:true",
+            Self::False => "fn:false",
+            Self::Not => "fn:not",
+            Self::StringLength => "fn:string-length",
+            Self::Concat => "fn:concat",
+            Self::Substring => "fn:substring",
+            Self::UpperCase => "fn:upper-case",
+            Self::LowerCase => "fn:lower-case",
+            Self::Contains => "fn:contains",
+            Self::StartsWith => "fn:starts-with",
+            Self::EndsWith => "fn:ends-with",
+            Self::Abs => "fn:abs",
+            Self::Ceiling => "fn:ceiling",
+            Self::Floor => "fn:floor",
+            Self::Round => "fn:round",
+            Self::RoundHalfToEven => "fn:round-half-to-even",
+            Self::ValueLength => "dfdl:valueLength",
+            Self::ContentLength => "dfdl:contentLength",
+            Self::OccursIndex => "dfdl:occursIndex",
+            Self::CheckConstraints => "dfdl:checkConstraints",
+            Self::TestBit => "dfdl:testBit",
+            Self::SetBits => "dfdl:setBits",
+            Self::DecodeDfdlEntities => "dfdl:decodeDFDLEntities",
+            Self::EncodeDfdlEntities => "dfdl:encodeDFDLEntities",
+        }
+    }
+
+    /// the smallest and largest number of arguments of the function, `None` if there is no maximum
+    fn arity(self) -> (usize, Option<usize>) {
+        match self {
+            Self::True | Self::False | Self::OccursIndex => (0, Some(0)),
+            Self::Concat => (2, None),
+            Self::Substring => (2, Some(3)),
+            Self::RoundHalfToEven => (1, Some(2)),
+            Self::Contains | Self::StartsWith | Self::EndsWith | Self::ValueLength | Self::ContentLength | Self::TestBit => (2, Some(2)),
+            Self::SetBits => (8, Some(8)),
+            _ => (1, Some(1)),
+        }
+    }
+}
+
+/// The type of the value of an [`Expression`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    /// the expression has not been type checked yet
+    Unknown,
+    /// a value of a builtin type
+    Atomic(PrimitiveType),
+    /// a complex element, which has no value of its own
+    Complex,
+}
+
+impl Expression {
+    /// Parses `source`, the text of an expression without its enclosing braces
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `source` is not a well-formed expression,
+    /// or uses constructs of XPath 2.0 that DFDL does not allow.
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        parser::parse(source)
+    }
+
+    pub(crate) fn new(kind: ExpressionKind) -> Self {
+        Self { kind, ty: Type::Unknown }
+    }
+}
+
+impl Display for PathExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.absolute {
+            f.write_str("/")?;
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                f.write_str("/")?;
+            }
+            match step {
+                Step::Parent => f.write_str("..")?,
+                Step::Current => f.write_str(".")?,
+                Step::Child { name, index: None } => f.write_str(name)?,
+                Step::Child { name, index: Some(_) } => write!(f, "{name}[...]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(absolute: bool, steps: Vec<Step>) -> Expression {
+        Expression::new(ExpressionKind::Path(PathExpression { absolute, steps, sequence: false }))
+    }
+
+    fn child(name: &str) -> Step {
+        Step::Child { name: name.to_string(), index: None }
+    }
+
+    #[test]
+    fn parses_expressions() {
+        let parsed = Expression::parse("../header/count * 2 + 1 eq 5 and fn:true()").unwrap();
+        let ExpressionKind::Binary { operator: BinaryOperator::And, left, right } = parsed.kind else { panic!("expected and") };
+        assert_eq!(right.kind, ExpressionKind::Call { function: Function::True, arguments: Vec::new() });
+        let ExpressionKind::Binary { operator: BinaryOperator::Equal, left, .. } = left.kind else { panic!("expected eq") };
+        let ExpressionKind::Binary { operator: BinaryOperator::Add, left, .. } = left.kind else { panic!("expected +") };
+        let ExpressionKind::Binary { operator: BinaryOperator::Multiply, left, .. } = left.kind else { panic!("expected *") };
+        assert_eq!(*left, path(false, vec![Step::Parent, child("header"), child("count")]));
+
+        let parsed = Expression::parse("/r:record/item[../n - 1]").unwrap();
+        let ExpressionKind::Path(PathExpression { absolute: true, steps, .. }) = parsed.kind else { panic!("expected path") };
+        let Step::Child { name, index: Some(index) } = &steps[1] else { panic!("expected indexed step") };
+        assert_eq!((steps[0].clone(), name.as_str()), (child("record"), "item"));
+        assert!(matches!(index.kind, ExpressionKind::Binary { operator: BinaryOperator::Subtract, .. }));
+
+        assert_eq!(Expression::parse("xs:int('1')").unwrap().kind, ExpressionKind::Cast {
+            operand: Box::new(Expression::new(ExpressionKind::Literal(Literal::String("1".to_string())))),
+            target: PrimitiveType::Int,
+        });
+        assert!(matches!(Expression::parse(". cast as xs:string").unwrap().kind, ExpressionKind::Cast { target: PrimitiveType::String, .. }));
+        assert!(matches!(Expression::parse("if (. gt 1.5) then 'it''s' else \"no\"").unwrap().kind, ExpressionKind::If { .. }));
+        assert!(matches!(Expression::parse("-1.5e3").unwrap().kind, ExpressionKind::Negate(_)));
+        assert!(matches!(Expression::parse("dfdl:occursIndex() idiv 2").unwrap().kind, ExpressionKind::Binary { operator: BinaryOperator::IntegerDivide, .. }));
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for disallowed in ["1, 2", "a//b", "@id", "child::a", "$v", "for $i in a return $i", "a | b", "1 to 3",
+            "a is b", ". instance of xs:int", "(1)[1]", "a[1][2]", "()"] {
+            assert!(matches!(Expression::parse(disallowed), Err(ExpressionError::Disallowed(_))), "{disallowed}");
+        }
+        assert!(matches!(Expression::parse("fn:foo(1)"), Err(ExpressionError::UnknownFunction(name)) if name == "fn:foo"));
+        assert!(matches!(Expression::parse("xs:float3(1)"), Err(ExpressionError::UnknownType(_))));
+        assert!(matches!(Expression::parse("fn:substring('a')"), Err(ExpressionError::Arity { function: "fn:substring", found: 1, .. })));
+        assert_eq!(Expression::parse("1 +"), Err(ExpressionError::Syntax { position: 3, reason: "unexpected end of expression".to_string() }));
+        assert!(matches!(Expression::parse("'open"), Err(ExpressionError::Syntax { position: 0, .. })));
+        assert!(matches!(Expression::parse("(1"), Err(ExpressionError::Syntax { .. })));
+    }
+}
@@ -0,0 +1,460 @@
+use crate::schema::PrimitiveType;
+
+use super::{BinaryOperator, Expression, ExpressionError, ExpressionKind, Function, Literal, PathExpression, Step};
+
+
+/// a token of the expression language
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// a name, with its namespace prefix
+    Name(Option<String>, String),
+    String(String),
+    Integer(i128),
+    Decimal(String),
+    Double(String),
+    /// an operator or punctuation
+    Symbol(&'static str),
+    End,
+}
+
+/// symbols, longest first so that `//` is not read as two `/`
+const SYMBOLS: [&str; 24] = ["//", "..", "::", "!=", "<=", ">=", "<<", ">>", "(", ")", "[", "]", ",", "/", ".", "@", "*", "+", "-", "=", "<", ">", "|", "$"];
+
+/// splits `source` into tokens, along with their byte offsets
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    loop {
+        rest = rest.trim_start();
+        let position = source.len() - rest.len();
+        let Some(c) = rest.chars().next() else {
+            tokens.push((position, Token::End));
+            return Ok(tokens);
+        };
+        let (token, length) = if c == '\'' || c == '"' {
+            // a quote is escaped by doubling it
+            let mut text = String::new();
+            let mut chars = rest.char_indices().skip(1).peekable();
+            let end = loop {
+                match chars.next() {
+                    Some((index, next)) if next == c => {
+                        if chars.peek().is_some_and(|(_, after)| *after == c) {
+                            chars.next();
+                            text.push(c);
+                        }
+                        else {
+                            break index + 1;
+                        }
+                    },
+                    Some((_, next)) => text.push(next),
+                    None => return Err(ExpressionError::Syntax { position, reason: "unterminated string literal".to_string() }),
+                }
+            };
+            (Token::String(text), end)
+        }
+        else if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            number(rest, position)?
+        }
+        else if c.is_alphabetic() || c == '_' {
+            let name_length = |text: &str| text.find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))).unwrap_or(text.len());
+            let length = name_length(rest);
+            // a single ':' separates a namespace prefix, '::' is an axis
+            let after = &rest[length..];
+            if after.starts_with(':') && !after.starts_with("::") && after[1..].starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                let local = name_length(&after[1..]);
+                (Token::Name(Some(rest[..length].to_string()), after[1..=local].to_string()), length + 1 + local)
+            }
+            else {
+                (Token::Name(None, rest[..length].to_string()), length)
+            }
+        }
+        else {
+            let symbol = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| ExpressionError::Syntax { position, reason: format!("unexpected character '{c}'") })?;
+            (Token::Symbol(symbol), symbol.len())
+        };
+        tokens.push((position, token));
+        rest = &rest[length..];
+    }
+}
+
+/// reads the numeric literal at the start of `text`
+fn number(text: &str, position: usize) -> Result<(Token, usize), ExpressionError> {
+    let digits = |from: usize| from + text[from..].find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len() - from);
+    let mut length = digits(0);
+    let mut decimal = false;
+    if text[length..].starts_with('.') {
+        decimal = true;
+        length = digits(length + 1);
+    }
+    if text[length..].starts_with(['e', 'E']) {
+        let mut exponent = length + 1;
+        if text[exponent..].starts_with(['+', '-']) {
+            exponent += 1;
+        }
+        let end = digits(exponent);
+        if end == exponent {
+            return Err(ExpressionError::Syntax { position, reason: "missing digits in the exponent of a number".to_string() });
+        }
+        return Ok((Token::Double(text[..end].to_string()), end));
+    }
+    if decimal {
+        return Ok((Token::Decimal(text[..length].to_string()), length));
+    }
+    let value = text[..length].parse()
+        .map_err(|_| ExpressionError::Syntax { position, reason: "integer literal out of range".to_string() })?;
+    Ok((Token::Integer(value), length))
+}
+
+/// parses the DFDL expression `source`
+pub(super) fn parse(source: &str) -> Result<Expression, ExpressionError> {
+    let mut parser = Parser { tokens: tokenize(source)?, next: 0 };
+    let expression = parser.expression()?;
+    match parser.peek() {
+        Token::End => Ok(expression),
+        Token::Symbol(",") => Err(ExpressionError::Disallowed("sequence expressions".to_string())),
+        _ => Err(parser.unexpected()),
+    }
+}
+
+/// a recursive descent parser for the DFDL subset of XPath 2.0
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].1
+    }
+
+    /// the token after the next one
+    fn peek_second(&self) -> &Token {
+        self.tokens.get(self.next + 1).map_or(&Token::End, |(_, token)| token)
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].1.clone();
+        if token != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    /// whether the next token is `symbol`, consuming it if it is
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Token::Symbol(next) if *next == symbol);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    /// whether the next token is the keyword `keyword`, consuming it if it is
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Token::Name(None, name) if name == keyword);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ExpressionError> {
+        if self.eat(symbol) {
+            Ok(())
+        }
+        else {
+            Err(self.error(format!("expected '{symbol}'")))
+        }
+    }
+
+    fn error(&self, reason: String) -> ExpressionError {
+        ExpressionError::Syntax { position: self.tokens[self.next].0, reason }
+    }
+
+    fn unexpected(&self) -> ExpressionError {
+        match self.peek() {
+            Token::End => self.error("unexpected end of expression".to_string()),
+            _ => self.error("unexpected token".to_string()),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, ExpressionError> {
+        if let Token::Name(None, keyword) = self.peek() {
+            if matches!(keyword.as_str(), "for" | "some" | "every") && *self.peek_second() == Token::Symbol("$") {
+                return Err(ExpressionError::Disallowed(format!("'{keyword}' expressions")));
+            }
+            if keyword == "if" && *self.peek_second() == Token::Symbol("(") {
+                self.next += 2;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                if !self.eat_keyword("then") {
+                    return Err(self.error("expected 'then'".to_string()));
+                }
+                let then = self.expression()?;
+                if !self.eat_keyword("else") {
+                    return Err(self.error("expected 'else'".to_string()));
+                }
+                let otherwise = self.expression()?;
+                return Ok(Expression::new(ExpressionKind::If { condition: Box::new(condition), then: Box::new(then), otherwise: Box::new(otherwise) }));
+            }
+        }
+        self.or()
+    }
+
+    fn or(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.and()?;
+        while self.eat_keyword("or") {
+            let right = self.and()?;
+            left = binary(BinaryOperator::Or, left, right);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.comparison()?;
+        while self.eat_keyword("and") {
+            let right = self.comparison()?;
+            left = binary(BinaryOperator::And, left, right);
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expression, ExpressionError> {
+        let left = self.additive()?;
+        let operator = match self.peek() {
+            Token::Symbol("=") => BinaryOperator::Equal,
+            Token::Symbol("!=") => BinaryOperator::NotEqual,
+            Token::Symbol("<") => BinaryOperator::Less,
+            Token::Symbol("<=") => BinaryOperator::LessOrEqual,
+            Token::Symbol(">") => BinaryOperator::Greater,
+            Token::Symbol(">=") => BinaryOperator::GreaterOrEqual,
+            Token::Symbol(symbol @ ("<<" | ">>")) => return Err(ExpressionError::Disallowed(format!("the node comparison '{symbol}'"))),
+            Token::Name(None, name) => match name.as_str() {
+                "eq" => BinaryOperator::Equal,
+                "ne" => BinaryOperator::NotEqual,
+                "lt" => BinaryOperator::Less,
+                "le" => BinaryOperator::LessOrEqual,
+                "gt" => BinaryOperator::Greater,
+                "ge" => BinaryOperator::GreaterOrEqual,
+                "is" => return Err(ExpressionError::Disallowed("the node comparison 'is'".to_string())),
+                "to" => return Err(ExpressionError::Disallowed("range expressions".to_string())),
+                _ => return Ok(left),
+            },
+            _ => return Ok(left),
+        };
+        self.next += 1;
+        let right = self.additive()?;
+        Ok(binary(operator, left, right))
+    }
+
+    fn additive(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.multiplicative()?;
+        loop {
+            let operator = if self.eat("+") {
+                BinaryOperator::Add
+            }
+            else if self.eat("-") {
+                BinaryOperator::Subtract
+            }
+            else {
+                return Ok(left);
+            };
+            let right = self.multiplicative()?;
+            left = binary(operator, left, right);
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expression, ExpressionError> {
+        let mut left = self.cast()?;
+        loop {
+            let operator = if self.eat("*") {
+                BinaryOperator::Multiply
+            }
+            else if self.eat_keyword("div") {
+                BinaryOperator::Divide
+            }
+            else if self.eat_keyword("idiv") {
+                BinaryOperator::IntegerDivide
+            }
+            else if self.eat_keyword("mod") {
+                BinaryOperator::Modulo
+            }
+            else {
+                return Ok(left);
+            };
+            let right = self.cast()?;
+            left = binary(operator, left, right);
+        }
+    }
+
+    /// a unary expression, possibly followed by `cast as`
+    fn cast(&mut self) -> Result<Expression, ExpressionError> {
+        let operand = self.unary()?;
+        if let Token::Symbol("|") | Token::Name(None, _) = self.peek() {
+            let construct = match self.peek() {
+                Token::Symbol(_) => "union",
+                Token::Name(_, name) => name.as_str(),
+                _ => unreachable!(),
+            };
+            let keyword = matches!(self.peek_second(), Token::Name(None, next) if next == "of" || next == "as");
+            match construct {
+                "union" | "intersect" | "except" => return Err(ExpressionError::Disallowed(format!("'{construct}' expressions"))),
+                "instance" | "treat" | "castable" if keyword => return Err(ExpressionError::Disallowed(format!("'{construct}' expressions"))),
+                "cast" if keyword => {
+                    self.next += 2;
+                    let target = match self.advance() {
+                        Token::Name(Some(prefix), local) if prefix == "xs" => PrimitiveType::from_name(&local).ok_or(ExpressionError::UnknownType(format!("xs:{local}")))?,
+                        Token::Name(prefix, local) => return Err(ExpressionError::UnknownType(prefix.map_or(local.clone(), |prefix| format!("{prefix}:{local}")))),
+                        _ => return Err(self.error("expected a type name".to_string())),
+                    };
+                    return Ok(Expression::new(ExpressionKind::Cast { operand: Box::new(operand), target }));
+                },
+                _ => {},
+            }
+        }
+        Ok(operand)
+    }
+
+    fn unary(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat("-") {
+            let operand = self.unary()?;
+            return Ok(Expression::new(ExpressionKind::Negate(Box::new(operand))));
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        self.value()
+    }
+
+    /// a primary expression or a path
+    fn value(&mut self) -> Result<Expression, ExpressionError> {
+        let primary = match self.peek().clone() {
+            Token::String(text) => self.literal(Literal::String(text)),
+            Token::Integer(value) => self.literal(Literal::Integer(value)),
+            Token::Decimal(text) => self.literal(Literal::Decimal(text)),
+            Token::Double(text) => self.literal(Literal::Double(text)),
+            Token::Symbol("(") => {
+                self.next += 1;
+                if self.eat(")") {
+                    return Err(ExpressionError::Disallowed("empty sequences".to_string()));
+                }
+                let inner = self.expression()?;
+                if *self.peek() == Token::Symbol(",") {
+                    return Err(ExpressionError::Disallowed("sequence expressions".to_string()));
+                }
+                self.expect(")")?;
+                inner
+            },
+            Token::Symbol("$") => return Err(ExpressionError::Disallowed("variable references".to_string())),
+            Token::Name(prefix, local) if *self.peek_second() == Token::Symbol("(") => {
+                self.next += 2;
+                self.call(prefix, local)?
+            },
+            _ => return self.path(),
+        };
+        if *self.peek() == Token::Symbol("[") {
+            return Err(ExpressionError::Disallowed("predicates on values other than element steps".to_string()));
+        }
+        if let Token::Symbol(symbol @ ("/" | "//")) = self.peek() {
+            return Err(ExpressionError::Disallowed(format!("'{symbol}' after values other than element steps")));
+        }
+        Ok(primary)
+    }
+
+    /// `literal`, whose token is the next one
+    fn literal(&mut self, literal: Literal) -> Expression {
+        self.next += 1;
+        Expression::new(ExpressionKind::Literal(literal))
+    }
+
+    /// the arguments of a call of the function `prefix:local`, whose opening parenthesis has been read
+    fn call(&mut self, prefix: Option<String>, local: String) -> Result<Expression, ExpressionError> {
+        let mut arguments = Vec::new();
+        if !self.eat(")") {
+            loop {
+                arguments.push(self.expression()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        if prefix.as_deref() == Some("xs") {
+            let target = PrimitiveType::from_name(&local).ok_or(ExpressionError::UnknownType(format!("xs:{local}")))?;
+            let [operand] = <[Expression; 1]>::try_from(arguments)
+                .map_err(|arguments| ExpressionError::Arity { function: "type constructors", expected: "1", found: arguments.len() })?;
+            return Ok(Expression::new(ExpressionKind::Cast { operand: Box::new(operand), target }));
+        }
+        let function = Function::from_name(prefix.as_deref(), &local)
+            .ok_or_else(|| ExpressionError::UnknownFunction(prefix.map_or(local.clone(), |prefix| format!("{prefix}:{local}"))))?;
+        let (min, max) = function.arity();
+        if arguments.len() < min || max.is_some_and(|max| arguments.len() > max) {
+            let expected = match (min, max) {
+                (0, Some(0)) => "no",
+                (1, Some(1)) => "1",
+                (1, Some(2)) => "1 or 2",
+                (2, Some(2)) => "2",
+                (2, Some(3)) => "2 or 3",
+                (2, None) => "at least 2",
+                _ => "8",
+            };
+            return Err(ExpressionError::Arity { function: function.name(), expected, found: arguments.len() });
+        }
+        Ok(Expression::new(ExpressionKind::Call { function, arguments }))
+    }
+
+    fn path(&mut self) -> Result<Expression, ExpressionError> {
+        if *self.peek() == Token::Symbol("//") {
+            return Err(ExpressionError::Disallowed("'//'".to_string()));
+        }
+        let absolute = self.eat("/");
+        let mut steps = Vec::new();
+        loop {
+            steps.push(self.step()?);
+            if *self.peek() == Token::Symbol("//") {
+                return Err(ExpressionError::Disallowed("'//'".to_string()));
+            }
+            if !self.eat("/") {
+                break;
+            }
+        }
+        Ok(Expression::new(ExpressionKind::Path(PathExpression { absolute, steps, sequence: false })))
+    }
+
+    fn step(&mut self) -> Result<Step, ExpressionError> {
+        let step = match self.peek().clone() {
+            Token::Symbol("..") => Step::Parent,
+            Token::Symbol(".") => Step::Current,
+            Token::Symbol("@") => return Err(ExpressionError::Disallowed("attribute steps".to_string())),
+            Token::Symbol("*") => return Err(ExpressionError::Disallowed("wildcard steps".to_string())),
+            Token::Name(_, axis) if *self.peek_second() == Token::Symbol("::") => return Err(ExpressionError::Disallowed(format!("the axis '{axis}::'"))),
+            Token::Name(_, name) => {
+                self.next += 1;
+                let index = if self.eat("[") {
+                    let index = self.expression()?;
+                    self.expect("]")?;
+                    Some(Box::new(index))
+                }
+                else {
+                    None
+                };
+                if *self.peek() == Token::Symbol("[") {
+                    return Err(ExpressionError::Disallowed("more than one predicate on a step".to_string()));
+                }
+                // namespace prefixes are ignored, the names of siblings are unique
+                return Ok(Step::Child { name, index });
+            },
+            _ => return Err(self.unexpected()),
+        };
+        self.next += 1;
+        if *self.peek() == Token::Symbol("[") {
+            return Err(ExpressionError::Disallowed("predicates on '.' and '..'".to_string()));
+        }
+        Ok(step)
+    }
+}
+
+fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Expression {
+    Expression::new(ExpressionKind::Binary { operator, left: Box::new(left), right: Box::new(right) })
+}
@@ -0,0 +1,592 @@
+//! Loading DFDL schemas and compiling them into a tree of schema components
+//!
+//! A DFDL schema is an XML Schema (XSD) document annotated with DFDL properties.
+//! [`CompiledSchema`] turns such a document into [`ElementDecl`]s, [`ModelGroup`]s and
+//! [`SimpleType`]s that carry the [`Format`] in effect for them.
+//!
+//! Documents referenced via `xs:include` and `xs:import` are loaded through a [`SchemaResolver`],
+//! by default from the filesystem via [`FileSystemResolver`].
+
+mod compiler;
+mod component;
+mod document;
+mod error;
+mod expression;
+mod properties;
+mod resolver;
+
+pub use component::{ElementContent, ElementDecl, Facets, GroupKind, MaxOccurs, ModelGroup, PrimitiveType, SimpleType, Term};
+pub use error::{Diagnostic, SchemaDefinitionError, SchemaError, Severity, SourceLocation};
+pub use expression::{BinaryOperator, Expression, ExpressionError, ExpressionKind, Function, Literal, PathExpression, Step, Type};
+pub use properties::{
+    Alignment, AlignmentUnits, Assert, AssertFailureType, AssertTestKind, BidiProperties, BinaryBooleanTrueRep,
+    BinaryCalendarRep, BinaryFloatRep, BinaryNumberProperties, BinaryNumberRep, BitOrder, BooleanProperties, ByteOrder,
+    CalculationProperties, CalendarPatternKind, CalendarProperties, CharClass, CheckPolicy, ChoiceLengthKind,
+    ChoiceProperties, DelimiterPolicy, DelimiterProperties, DfdlExpression, Discriminator, DfdlLiteral, DfdlRegex, EmptyElementParsePolicy,
+    EncodingErrorPolicy, EscapeKind, EscapeSchemeProperties, Format, GenerateEscapeBlock, LengthKind, LengthProperties,
+    LengthUnits, LiteralPart, NilKind, NilProperties, OccursCountKind, OccursProperties, PackedSignCodes, PropertySet,
+    Representation, RuntimeValue, SeparatorPosition, SeparatorSuppressionPolicy, SequenceKind, SequenceProperties,
+    TextBidiNumeralShapes, TextBidiOrientation, TextBidiTextOrdering, TextJustification, TextNumberProperties, TextNumberRep,
+    TextNumberRounding, TextNumberRoundingMode, TextPadKind, TextProperties, TextTrimKind, TextZonedSignStyle, Utf16Width,
+    Weekday,
+};
+pub use resolver::{FileSystemResolver, MapResolver, SchemaResolver};
+
+
+/// A DFDL schema compiled into schema components
+#[derive(Clone, Debug)]
+pub struct CompiledSchema {
+    target_namespace: Option<String>,
+    default_format: Format,
+    elements: Vec<ElementDecl>,
+    /// the warnings found while compiling the schema
+    warnings: Vec<Diagnostic>,
+}
+
+impl CompiledSchema {
+    /// Compiles the schema contained in `xml`
+    ///
+    /// Included and imported documents are loaded from the filesystem,
+    /// relative to the current working directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `xml` is not a well-formed schema document
+    /// or uses constructs not allowed in a DFDL schema. The error lists all schema definition
+    /// errors found, together with their location.
+    pub fn new(xml: &str) -> Result<Self, SchemaDefinitionError> {
+        Self::with_resolver(xml, &FileSystemResolver)
+    }
+    /// Compiles the schema contained in `xml`, loading included and imported documents via `resolver`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `xml` or any document it references is not a
+    /// well-formed schema document, a reference can not be resolved, or the schema uses
+    /// constructs not allowed in a DFDL schema.
+    pub fn with_resolver(xml: &str, resolver: &dyn SchemaResolver) -> Result<Self, SchemaDefinitionError> {
+        Self::compile(document::load_documents(None, xml.to_string(), resolver)?)
+    }
+    /// Compiles the schema document at `location`, as resolved by `resolver`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `location` or any document it references can not be
+    /// resolved or is not a well-formed schema document, or the schema uses constructs not allowed
+    /// in a DFDL schema.
+    pub fn load(location: &str, resolver: &dyn SchemaResolver) -> Result<Self, SchemaDefinitionError> {
+        let location = resolver.resolve(location, None)?;
+        let source = resolver.load(&location)?.into_owned();
+        Self::compile(document::load_documents(Some(location), source, resolver)?)
+    }
+    /// Compiles the schema document at `path` in the filesystem
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error under the same conditions as [`load`](Self::load).
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, SchemaDefinitionError> {
+        Self::load(&path.as_ref().to_string_lossy(), &FileSystemResolver)
+    }
+    fn compile(documents: Vec<document::SchemaDocument>) -> Result<Self, SchemaDefinitionError> {
+        let trees = documents.iter()
+            .map(|document| document::parse(document.location.as_deref(), &document.source))
+            .collect::<Result<Vec<_>, _>>()?;
+        compiler::Compiler::new(&documents, &trees).compile()
+    }
+    /// Returns the target namespace of the schema, if it declares one
+    #[must_use]
+    pub fn target_namespace(&self) -> Option<&str> {
+        self.target_namespace.as_deref()
+    }
+    /// Returns the format defined in the schema's top-level `dfdl:format` annotation
+    #[must_use]
+    pub fn default_format(&self) -> &Format {
+        &self.default_format
+    }
+    /// Returns all global element declarations of the schema
+    #[must_use]
+    pub fn elements(&self) -> &[ElementDecl] {
+        &self.elements
+    }
+    /// Returns the warnings found while compiling the schema
+    #[must_use]
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+    /// Returns the global element with the given local name
+    #[must_use]
+    pub fn element(&self, name: &str) -> Option<&ElementDecl> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the kind of the first schema definition error of `result`, if it failed
+    fn first_error<T>(result: Result<T, SchemaDefinitionError>) -> Option<SchemaError> {
+        result.err().map(|e| e.first().clone())
+    }
+
+    const SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+<xs:annotation>
+  <xs:appinfo source="http://www.ogf.org/dfdl/">
+    <dfdl:format representation="binary" byteOrder="littleEndian" lengthKind="implicit"/>
+  </xs:appinfo>
+</xs:annotation>
+<xs:element name="test">
+  <xs:complexType>
+    <xs:sequence>
+      <xs:element name="w" type="xs:int"/>
+      <xs:element name="x" type="xs:int" maxOccurs="unbounded"/>
+      <xs:choice>
+        <xs:element name="y" type="xs:double"/>
+        <xs:element name="z">
+          <xs:simpleType><xs:restriction base="xs:float"/></xs:simpleType>
+        </xs:element>
+      </xs:choice>
+    </xs:sequence>
+  </xs:complexType>
+</xs:element>
+</xs:schema>"#;
+
+    #[test]
+    fn compiles_component_tree() {
+        let schema = CompiledSchema::new(SCHEMA).unwrap();
+        assert_eq!(schema.default_format().byte_order, Some(RuntimeValue::Value(ByteOrder::LittleEndian)));
+        let root = schema.element("test").unwrap();
+        let ElementContent::Complex(group) = &root.content else { panic!("expected complex content") };
+        assert_eq!(group.kind, GroupKind::Sequence);
+        assert_eq!(group.terms.len(), 3);
+        let Term::Element(x) = &group.terms[1] else { panic!("expected element") };
+        assert!(x.is_array());
+        assert_eq!(x.format.representation, Some(Representation::Binary));
+        let Term::Group(choice) = &group.terms[2] else { panic!("expected choice") };
+        let Term::Element(z) = &choice.terms[1] else { panic!("expected element") };
+        assert!(matches!(&z.content, ElementContent::Simple(SimpleType { primitive: PrimitiveType::Float, .. })));
+    }
+
+    #[test]
+    fn loads_included_and_imported_documents() {
+        const MAIN: &str = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:main">
+                <xs:include schemaLocation="parts/chameleon.xsd"/>
+                <xs:import namespace="urn:other" schemaLocation="parts/other.xsd"/>
+                <xs:import namespace="urn:elsewhere"/>
+                <xs:element name="main" type="xs:int"/>
+            </xs:schema>"#;
+        let resolver = MapResolver::new()
+            .with("main.xsd", MAIN)
+            .with("parts/chameleon.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+                <xs:include schemaLocation="../main.xsd"/>
+                <xs:simpleType name="id"><xs:restriction base="xs:int"/></xs:simpleType>
+                <xs:element name="included" type="id"/>
+                <xs:element name="wrapper"><xs:complexType><xs:sequence><xs:element ref="included"/></xs:sequence></xs:complexType></xs:element>
+            </xs:schema>"#)
+            .with("parts/other.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:other">
+                <xs:element name="imported" type="xs:int"/>
+            </xs:schema>"#);
+        let schema = CompiledSchema::load("main.xsd", &resolver).unwrap();
+        assert_eq!(schema.elements().len(), 4);
+        let included = schema.element("included").unwrap();
+        assert_eq!(included.namespace.as_deref(), Some("urn:main"));
+        assert!(matches!(&included.content, ElementContent::Simple(SimpleType { name: Some(name), .. }) if name == "id"));
+        assert_eq!(schema.element("imported").unwrap().namespace.as_deref(), Some("urn:other"));
+        // the root given as a string is not loaded again when the chameleon include refers back to it
+        assert_eq!(CompiledSchema::with_resolver(MAIN, &resolver).unwrap().elements().len(), 4);
+
+        let resolver = resolver.with("parts/other.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:wrong"/>"#);
+        assert!(matches!(first_error(CompiledSchema::load("main.xsd", &resolver)), Some(SchemaError::NamespaceMismatch { .. })));
+    }
+
+    #[test]
+    fn resolves_named_format_chains() {
+        let resolver = MapResolver::new()
+            .with("main.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/"
+                    xmlns:gen="urn:general" targetNamespace="urn:main">
+                <xs:import namespace="urn:general" schemaLocation="general.xsd"/>
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:format ref="gen:Binary" byteOrder="littleEndian"/>
+                </xs:appinfo></xs:annotation>
+            </xs:schema>"#)
+            .with("general.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/"
+                    xmlns:gen="urn:general" targetNamespace="urn:general">
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:defineFormat name="General"><dfdl:format encoding="utf-8" byteOrder="bigEndian"/></dfdl:defineFormat>
+                    <dfdl:defineFormat name="Binary"><dfdl:format ref="gen:General" representation="binary"/></dfdl:defineFormat>
+                </xs:appinfo></xs:annotation>
+            </xs:schema>"#);
+        let format = CompiledSchema::load("main.xsd", &resolver).unwrap().default_format().clone();
+        assert_eq!(format.encoding, Some(RuntimeValue::Value("utf-8".to_string())));
+        assert_eq!(format.representation, Some(Representation::Binary));
+        assert_eq!(format.byte_order, Some(RuntimeValue::Value(ByteOrder::LittleEndian)));
+
+        let cyclic = resolver.clone().with("general.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/"
+                xmlns:gen="urn:general" targetNamespace="urn:general">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                <dfdl:defineFormat name="General"><dfdl:format ref="gen:Binary"/></dfdl:defineFormat>
+                <dfdl:defineFormat name="Binary"><dfdl:format ref="gen:General"/></dfdl:defineFormat>
+            </xs:appinfo></xs:annotation>
+        </xs:schema>"#);
+        assert!(matches!(first_error(CompiledSchema::load("main.xsd", &cyclic)), Some(SchemaError::CircularFormatReference(_))));
+        let missing = resolver.with("general.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:general"/>"#);
+        assert!(matches!(first_error(CompiledSchema::load("main.xsd", &missing)), Some(SchemaError::UndefinedFormat(_))));
+    }
+
+    #[test]
+    fn applies_property_scoping_rules() {
+        let schema = CompiledSchema::new(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                <dfdl:defineFormat name="text"><dfdl:format representation="text" encoding="ascii"/></dfdl:defineFormat>
+                <dfdl:format byteOrder="bigEndian" representation="binary"/>
+            </xs:appinfo></xs:annotation>
+            <xs:element name="record">
+                <xs:complexType>
+                    <xs:sequence dfdl:separator=",">
+                        <xs:element name="a" type="xs:int" dfdl:byteOrder="littleEndian"/>
+                        <xs:element name="b" dfdl:lengthKind="delimited">
+                            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                                <dfdl:element ref="text" encoding="utf-8"/>
+                            </xs:appinfo></xs:annotation>
+                            <xs:simpleType>
+                                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                                    <dfdl:simpleType textPadKind="none"/>
+                                </xs:appinfo></xs:annotation>
+                                <xs:restriction base="xs:string"/>
+                            </xs:simpleType>
+                        </xs:element>
+                    </xs:sequence>
+                </xs:complexType>
+            </xs:element>
+        </xs:schema>"#).unwrap();
+        let ElementContent::Complex(sequence) = &schema.element("record").unwrap().content else { panic!("expected complex content") };
+        assert_eq!(sequence.format.sequence.separator, Some(RuntimeValue::Value(vec![DfdlLiteral::parse(",").unwrap()])));
+        let Term::Element(a) = &sequence.terms[0] else { panic!("expected element") };
+        assert_eq!(a.format.byte_order, Some(RuntimeValue::Value(ByteOrder::LittleEndian)));
+        // properties of enclosing components are not inherited
+        assert_eq!(a.format.sequence.separator, None);
+        let Term::Element(b) = &sequence.terms[1] else { panic!("expected element") };
+        assert_eq!(b.format.representation, Some(Representation::Text));
+        assert_eq!(b.format.encoding, Some(RuntimeValue::Value("utf-8".to_string())));
+        assert_eq!(b.format.length.length_kind, Some(LengthKind::Delimited));
+        assert_eq!(b.format.text.text_pad_kind, Some(TextPadKind::None));
+        assert_eq!(b.format.byte_order, Some(RuntimeValue::Value(ByteOrder::BigEndian)));
+
+        let duplicate = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="e" dfdl:encoding="ascii">
+                <xs:simpleType>
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                        <dfdl:simpleType encoding="utf-8"/>
+                    </xs:appinfo></xs:annotation>
+                    <xs:restriction base="xs:string"/>
+                </xs:simpleType>
+            </xs:element>
+        </xs:schema>"#;
+        assert!(matches!(first_error(CompiledSchema::new(duplicate)), Some(SchemaError::DuplicateProperty(p)) if p == "encoding"));
+        let misplaced = duplicate.replace("dfdl:simpleType", "dfdl:element");
+        assert!(matches!(first_error(CompiledSchema::new(&misplaced)), Some(SchemaError::MisplacedAnnotation { .. })));
+    }
+
+    #[test]
+    fn reads_property_elements() {
+        let schema = |properties: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="e" type="xs:string">
+                <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                    <dfdl:element {properties}</dfdl:element>
+                </xs:appinfo></xs:annotation>
+            </xs:element>
+        </xs:schema>"#));
+        let compiled = schema(r#"encoding="ascii"><dfdl:property name="initiator"><![CDATA[" ']]></dfdl:property>
+            <dfdl:property name="terminator">%NL;</dfdl:property>"#).unwrap();
+        let format = &compiled.element("e").unwrap().format;
+        assert_eq!(format.delimiters.initiator, Some(RuntimeValue::Value(vec![DfdlLiteral::parse("\"").unwrap(), DfdlLiteral::parse("'").unwrap()])));
+        assert_eq!(format.delimiters.terminator, Some(RuntimeValue::Value(vec![DfdlLiteral::parse("%NL;").unwrap()])));
+        assert_eq!(format.encoding, Some(RuntimeValue::Value("ascii".to_string())));
+
+        let both = schema(r#"initiator="["><dfdl:property name="initiator">[</dfdl:property>"#);
+        assert!(matches!(first_error(both), Some(SchemaError::DuplicateProperty(p)) if p == "initiator"));
+    }
+
+    #[test]
+    fn parses_typed_property_values() {
+        let format = |properties: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/"><dfdl:format {properties}/></xs:appinfo></xs:annotation>
+        </xs:schema>"#)).map(|schema| schema.default_format().clone());
+
+        let parsed = format(r#"fillByte="%#rFF;" leadingSkip="4" alignment="8" ignoreCase="yes" byteOrder="{ ../order }" encoding="{{literal""#).unwrap();
+        assert_eq!(parsed.fill_byte, Some(0xFF));
+        assert_eq!(parsed.leading_skip, Some(4));
+        assert_eq!(parsed.alignment, Some(Alignment::Fixed(8)));
+        assert_eq!(parsed.ignore_case, Some(true));
+        assert!(matches!(parsed.byte_order, Some(RuntimeValue::Expression(e)) if e.as_str() == "../order"));
+        assert_eq!(parsed.encoding, Some(RuntimeValue::Value("{literal".to_string())));
+
+        for invalid in [r#"byteOrder="littleEndain""#, r#"fillByte="ff""#, r#"fillByte="f""#, r#"leadingSkip="-1""#, r#"alignment="{ 4 }""#, r#"ignoreCase="true""#, r#"lengthPattern="[a-""#] {
+            assert!(matches!(first_error(format(invalid)), Some(SchemaError::InvalidPropertyValue { .. })), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn groups_the_full_property_set() {
+        let format = |properties: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/"><dfdl:format {properties}/></xs:appinfo></xs:annotation>
+        </xs:schema>"#)).map(|schema| schema.default_format().clone());
+
+        let parsed = format(r##"nilKind="literalValue" nilValue="%ES; NIL" textNumberPattern="#,##0.00" separatorPosition="postfix"
+            choiceLengthKind="explicit" outputNewLine="%CR;%LF;" textStringJustification="center" calendarCenturyStart="53"
+            lengthPattern="[0-9]+" prefixLengthType="lengthType" binaryPackedSignCodes="C D F C" occursCount="{ ../count }"
+            textZonedSignStyle="asciiTranslatedEBCDIC" escapeKind="escapeBlock" binaryBooleanTrueRep="""##).unwrap();
+        assert_eq!(parsed.nil.nil_kind, Some(NilKind::LiteralValue));
+        assert_eq!(parsed.nil.nil_value.map(|values| values.len()), Some(2));
+        assert_eq!(parsed.text_number.text_number_pattern.as_deref(), Some("#,##0.00"));
+        assert_eq!(parsed.sequence.separator_position, Some(SeparatorPosition::Postfix));
+        assert_eq!(parsed.choice.choice_length_kind, Some(ChoiceLengthKind::Explicit));
+        assert_eq!(parsed.delimiters.output_new_line, Some(RuntimeValue::Value(DfdlLiteral::parse("%CR;%LF;").unwrap())));
+        assert_eq!(parsed.text.text_string_justification, Some(TextJustification::Center));
+        assert_eq!(parsed.calendar.calendar_century_start, Some(53));
+        assert_eq!(parsed.length.length_pattern.as_ref().map(DfdlRegex::as_str), Some("[0-9]+"));
+        assert_eq!(parsed.length.prefix_length_type.as_deref(), Some("lengthType"));
+        assert_eq!(parsed.binary_number.binary_packed_sign_codes, Some(PackedSignCodes { positive: 0xC, negative: 0xD, unsigned: 0xF, zero: 0xC }));
+        assert_eq!(parsed.occurs.occurs_count.as_ref().map(DfdlExpression::as_str), Some("../count"));
+        assert_eq!(parsed.text_number.text_zoned_sign_style, Some(TextZonedSignStyle::AsciiTranslatedEbcdic));
+        assert_eq!(parsed.escape_scheme.escape_kind, Some(EscapeKind::EscapeBlock));
+        assert_eq!(parsed.boolean.binary_boolean_true_rep, Some(BinaryBooleanTrueRep::NotFalse));
+
+        for invalid in [r#"binaryPackedSignCodes="C D""#, r#"occursCount="3""#, r#"calendarDaysInFirstWeek="8""#, r#"textStandardBase="3""#] {
+            assert!(matches!(first_error(format(invalid)), Some(SchemaError::InvalidPropertyValue { .. })), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn collects_located_diagnostics() {
+        let resolver = MapResolver::new().with("main.xsd", r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+<xs:element name="record">
+  <xs:complexType>
+    <xs:sequence dfdl:separator="," dfdl:separatorPosition="between">
+      <xs:element name="a" type="xs:int" dfdl:byteOrder="littleEndain"/>
+      <xs:element type="xs:int"/>
+      <xs:element name="b" type="xs:int">
+        <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/dfdl-1.0/"><dfdl:element/></xs:appinfo></xs:annotation>
+      </xs:element>
+    </xs:sequence>
+  </xs:complexType>
+</xs:element>
+</xs:schema>"#);
+        let error = CompiledSchema::load("main.xsd", &resolver).unwrap_err();
+        let errors: Vec<_> = error.errors().collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].component.as_deref(), Some("/record/sequence[1]"));
+        assert_eq!(errors[0].property.as_deref(), Some("separatorPosition"));
+        assert_eq!(errors[0].location, Some(SourceLocation { file: Some("main.xsd".to_string()), line: 4, column: 37 }));
+        assert_eq!(errors[1].component.as_deref(), Some("/record/sequence[1]/a"));
+        assert_eq!(errors[1].property.as_deref(), Some("byteOrder"));
+        assert_eq!(errors[1].location.as_ref().map(|l| (l.line, l.column)), Some((5, 42)));
+        assert!(matches!(errors[2].kind, SchemaError::MissingAttribute { attribute: "name", .. }));
+        assert_eq!(errors[2].location.as_ref().map(|l| l.line), Some(6));
+        assert_eq!(error.warnings().count(), 1);
+        assert!(error.to_string().contains("main.xsd:5:42"));
+
+        let xml_error = first_error(CompiledSchema::new("<xs:schema>")).unwrap();
+        assert!(xml_error.to_string().starts_with("xml error: ") && xml_error.to_string().len() > "xml error: ".len());
+    }
+
+    #[test]
+    fn resolves_global_definitions() {
+        let schema = |body: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+            xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/" xmlns:t="urn:types" targetNamespace="urn:types">
+            <xs:simpleType name="length" dfdl:lengthKind="explicit"><xs:restriction base="xs:unsignedShort"/></xs:simpleType>
+            <xs:simpleType name="shortLength" dfdl:length="2"><xs:restriction base="t:length"/></xs:simpleType>
+            <xs:complexType name="record">
+                <xs:sequence>
+                    <xs:element ref="t:field" dfdl:byteOrder="littleEndian"/>
+                    <xs:group ref="t:fields" dfdl:separator=","/>
+                </xs:sequence>
+            </xs:complexType>
+            <xs:element name="field" type="t:shortLength" dfdl:representation="binary"/>
+            <xs:group name="fields"><xs:choice><xs:element ref="t:field" minOccurs="0" dfdl:byteOrder="bigEndian"/></xs:choice></xs:group>
+            {body}
+        </xs:schema>"#));
+
+        let compiled = schema(r#"<xs:element name="root" type="t:record"/>"#).unwrap();
+        let ElementContent::Complex(record) = &compiled.element("root").unwrap().content else { panic!("expected complex content") };
+        let Term::Element(first) = &record.terms[0] else { panic!("expected element") };
+        assert_eq!(first.namespace.as_deref(), Some("urn:types"));
+        assert_eq!(first.format.byte_order, Some(RuntimeValue::Value(ByteOrder::LittleEndian)));
+        assert_eq!(first.format.representation, Some(Representation::Binary));
+        assert_eq!(first.format.length.length_kind, Some(LengthKind::Explicit));
+        assert_eq!(first.format.length.length, Some(RuntimeValue::Value(2)));
+        assert!(matches!(&first.content, ElementContent::Simple(SimpleType { primitive: PrimitiveType::UnsignedShort, .. })));
+        let Term::Group(fields) = &record.terms[1] else { panic!("expected group") };
+        assert_eq!(fields.kind, GroupKind::Choice);
+        assert_eq!(fields.format.sequence.separator, Some(RuntimeValue::Value(vec![DfdlLiteral::parse(",").unwrap()])));
+        let Term::Element(second) = &fields.terms[0] else { panic!("expected element") };
+        assert_eq!(second.format.byte_order, Some(RuntimeValue::Value(ByteOrder::BigEndian)));
+        assert!(second.is_optional());
+
+        let duplicate = schema(r#"<xs:element name="root"><xs:complexType><xs:sequence>
+            <xs:element ref="t:field" dfdl:representation="text"/>
+        </xs:sequence></xs:complexType></xs:element>"#);
+        assert!(matches!(first_error(duplicate), Some(SchemaError::DuplicateProperty(p)) if p == "representation"));
+        let recursive = schema(r#"<xs:element name="root"><xs:complexType><xs:sequence>
+            <xs:element ref="t:root"/>
+        </xs:sequence></xs:complexType></xs:element>"#);
+        assert!(matches!(first_error(recursive), Some(SchemaError::CircularReference(_))));
+        let unresolved = schema(r#"<xs:element name="root" type="t:missing"/>"#);
+        assert!(matches!(first_error(unresolved), Some(SchemaError::UnresolvedType(_))));
+        let prefixed = schema(r#"<xs:element name="root" type="xs:string" dfdl:lengthKind="prefixed" dfdl:prefixLengthType="t:shortLength"/>"#).unwrap();
+        let prefix = prefixed.element("root").unwrap().prefix_length_type.as_deref().unwrap();
+        assert_eq!(prefix.primitive, PrimitiveType::UnsignedShort);
+        assert_eq!(prefix.format.length.length, Some(RuntimeValue::Value(2)));
+        let unprefixed = schema(r#"<xs:element name="root" type="xs:string" dfdl:lengthKind="prefixed"/>"#);
+        assert!(matches!(first_error(unprefixed), Some(SchemaError::MissingAttribute { attribute: "dfdl:prefixLengthType", .. })));
+    }
+
+    #[test]
+    fn compiles_facets() {
+        let schema = |restriction: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+            <xs:simpleType name="code"><xs:restriction base="xs:string"><xs:length value="3"/><xs:pattern value="[A-Z]+"/></xs:restriction></xs:simpleType>
+            <xs:element name="root"><xs:simpleType>{restriction}</xs:simpleType></xs:element>
+        </xs:schema>"#));
+
+        let compiled = schema(r#"<xs:restriction base="code"><xs:pattern value="A.*"/><xs:pattern value="B.*"/>
+            <xs:enumeration value="ABC"/><xs:enumeration value="BCD"/></xs:restriction>"#).unwrap();
+        let ElementContent::Simple(simple) = &compiled.element("root").unwrap().content else { panic!("expected simple content") };
+        assert_eq!((simple.facets.min_length, simple.max_length), (Some(3), Some(3)));
+        assert_eq!(simple.facets.patterns, [vec!["[A-Z]+"], vec!["A.*", "B.*"]]);
+        assert_eq!(simple.facets.enumeration.as_deref(), Some(&["ABC".to_string(), "BCD".to_string()][..]));
+        let compiled = schema(r#"<xs:restriction base="xs:decimal"><xs:minExclusive value="-1.5"/><xs:totalDigits value="4"/></xs:restriction>"#).unwrap();
+        let ElementContent::Simple(simple) = &compiled.element("root").unwrap().content else { panic!("expected simple content") };
+        assert_eq!((simple.facets.min_exclusive.as_deref(), simple.facets.total_digits), (Some("-1.5"), Some(4)));
+
+        let invalid = schema(r#"<xs:restriction base="xs:int"><xs:maxInclusive value="1.5"/></xs:restriction>"#);
+        assert!(matches!(first_error(invalid), Some(SchemaError::InvalidAttribute { attribute: "value", .. })));
+        let unordered = schema(r#"<xs:restriction base="xs:string"><xs:minInclusive value="a"/></xs:restriction>"#);
+        assert!(matches!(first_error(unordered), Some(SchemaError::Unsupported(_))));
+        let digits = schema(r#"<xs:restriction base="xs:double"><xs:fractionDigits value="2"/></xs:restriction>"#);
+        assert!(matches!(first_error(digits), Some(SchemaError::Unsupported(_))));
+        let unknown = schema(r#"<xs:restriction base="xs:string"><xs:whiteSpace value="collapse"/></xs:restriction>"#);
+        assert!(matches!(first_error(unknown), Some(SchemaError::Unsupported(_))));
+    }
+
+    #[test]
+    fn reads_statement_annotations() {
+        let schema = |annotations: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+            xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="root"><xs:complexType><xs:choice>
+                <xs:element name="a" type="xs:int">
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">{annotations}</xs:appinfo></xs:annotation>
+                </xs:element>
+                <xs:sequence>
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                        <dfdl:discriminator testKind="pattern" testPattern="[0-9]" message="no digit"/>
+                    </xs:appinfo></xs:annotation>
+                </xs:sequence>
+            </xs:choice></xs:complexType></xs:element>
+        </xs:schema>"#));
+
+        let compiled = schema(r#"<dfdl:assert>{ . gt 0 }</dfdl:assert><dfdl:discriminator>{ . eq 1 }</dfdl:discriminator>
+            <dfdl:assert testKind="pattern" testPattern="x+" failureType="recoverableError" message="no x"/>"#).unwrap();
+        let ElementContent::Complex(choice) = &compiled.element("root").unwrap().content else { panic!("expected complex content") };
+        let Term::Element(a) = &choice.terms[0] else { panic!("expected element") };
+        let discriminator = a.discriminator.as_ref().unwrap();
+        assert_eq!(discriminator.test_kind, AssertTestKind::Expression);
+        assert_eq!(discriminator.test.as_ref().map(DfdlExpression::as_str), Some(". eq 1"));
+        assert_eq!(a.asserts.len(), 2);
+        assert_eq!(a.asserts[0].test.as_ref().map(DfdlExpression::as_str), Some(". gt 0"));
+        assert_eq!(a.asserts[0].failure_type, AssertFailureType::ProcessingError);
+        assert_eq!((a.asserts[1].test_kind, a.asserts[1].test_pattern.as_deref()), (AssertTestKind::Pattern, Some("x+")));
+        assert_eq!((a.asserts[1].failure_type, a.asserts[1].message.as_str()), (AssertFailureType::RecoverableError, "no x"));
+        let Term::Group(sequence) = &choice.terms[1] else { panic!("expected group") };
+        let discriminator = sequence.discriminator.as_ref().unwrap();
+        assert_eq!((discriminator.test_kind, discriminator.test_pattern.as_deref()), (AssertTestKind::Pattern, Some("[0-9]")));
+        assert_eq!(discriminator.message, "no digit");
+
+        let duplicate = schema(r#"<dfdl:discriminator test="{ . eq 1 }"/><dfdl:discriminator test="{ . eq 2 }"/>"#);
+        assert!(matches!(first_error(duplicate), Some(SchemaError::DuplicateAnnotation { annotation: "dfdl:discriminator", .. })));
+        let missing = schema(r#"<dfdl:discriminator testKind="pattern"/>"#);
+        assert!(matches!(first_error(missing), Some(SchemaError::MissingAttribute { attribute: "testPattern", .. })));
+        let invalid = schema(r#"<dfdl:assert testKind="pattern" testPattern="[0-9"/>"#);
+        assert!(matches!(first_error(invalid), Some(SchemaError::InvalidAttribute { attribute: "testPattern", .. })));
+        let failure = schema(r#"<dfdl:assert test="{ . eq 1 }" failureType="warning"/>"#);
+        assert!(matches!(first_error(failure), Some(SchemaError::InvalidAttribute { attribute: "failureType", .. })));
+        let unbraced = schema(r#"<dfdl:discriminator test=". eq 1"/>"#);
+        assert!(matches!(first_error(unbraced), Some(SchemaError::InvalidAttribute { attribute: "test", .. })));
+    }
+
+    #[test]
+    fn type_checks_expressions() {
+        let schema = |count: &str, test: &str| CompiledSchema::new(&format!(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+            xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="record"><xs:complexType><xs:sequence>
+                <xs:element name="header"><xs:complexType><xs:sequence>
+                    <xs:element name="count" type="xs:unsignedByte"/>
+                    <xs:element name="kind" type="xs:string"/>
+                </xs:sequence></xs:complexType></xs:element>
+                <xs:element name="item" type="xs:int" maxOccurs="unbounded" dfdl:occursCount="{{ {count} }}">
+                    <xs:annotation><xs:appinfo source="http://www.ogf.org/dfdl/">
+                        <dfdl:assert test="{{ {test} }}"/>
+                    </xs:appinfo></xs:annotation>
+                </xs:element>
+            </xs:sequence></xs:complexType></xs:element>
+        </xs:schema>"#));
+
+        let compiled = schema("../header/count", "if (../header/kind eq 'a') then . gt 0 else fn:count(../item) le 10").unwrap();
+        let item = compiled.element("record").and_then(|record| match &record.content {
+            ElementContent::Complex(sequence) => sequence.terms.get(1),
+            ElementContent::Simple(_) => None,
+        });
+        let Some(Term::Element(item)) = item else { panic!("expected element") };
+        let count = item.format.occurs.occurs_count.as_ref().unwrap().expression();
+        assert_eq!(count.ty, Type::Atomic(PrimitiveType::UnsignedByte));
+        assert_eq!(item.asserts[0].test.as_ref().unwrap().expression().ty, Type::Atomic(PrimitiveType::Boolean));
+
+        let error = |count, test| match first_error(schema(count, test)) {
+            Some(SchemaError::InvalidExpression { error, .. }) => error,
+            other => panic!("expected an invalid expression, found {other:?}"),
+        };
+        assert_eq!(error("../header/size", ". gt 0"), ExpressionError::UnknownPath("../header/size".to_string()));
+        assert_eq!(error("../../../count", ". gt 0"), ExpressionError::UnknownPath("../../../count".to_string()));
+        assert!(matches!(error("../header/kind", ". gt 0"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("../header", ". gt 0"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("1", ". + 1"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("1", "../item gt 0"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("1", ". eq 'a'"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("1", "fn:string-length(.) gt 0"), ExpressionError::TypeMismatch(_)));
+        assert!(matches!(error("1", "//item"), ExpressionError::Disallowed(_)));
+        assert!(matches!(error("1", "fn:exists(../item, 2)"), ExpressionError::Arity { function: "fn:exists", .. }));
+        assert!(matches!(error("1", "fn:matches(., 'a')"), ExpressionError::UnknownFunction(_)));
+
+        // referenced elements are checked where they are referenced and as roots of their own
+        let compiled = CompiledSchema::new(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+            xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="record"><xs:complexType><xs:sequence>
+                <xs:element name="count" type="xs:unsignedByte"/>
+                <xs:element ref="items"/>
+            </xs:sequence></xs:complexType></xs:element>
+            <xs:element name="items"><xs:complexType><xs:sequence>
+                <xs:element name="size" type="xs:unsignedByte"/>
+                <xs:element name="item" type="xs:int" maxOccurs="unbounded" dfdl:occursCount="{ ../size }"/>
+                <xs:element name="rest" type="xs:int" maxOccurs="unbounded" dfdl:occursCount="{ ../../count }"/>
+            </xs:sequence></xs:complexType></xs:element>
+        </xs:schema>"#).unwrap();
+        let Some(ElementContent::Complex(sequence)) = compiled.element("items").map(|items| &items.content) else { panic!("expected a complex element") };
+        let Some(Term::Element(item)) = sequence.terms.get(1) else { panic!("expected element") };
+        assert_eq!(item.format.occurs.occurs_count.as_ref().unwrap().expression().ty, Type::Atomic(PrimitiveType::UnsignedByte));
+        // the count of the enclosing record is only known where `items` is referenced
+        assert!(matches!(compiled.warnings(), [Diagnostic { kind: SchemaError::InvalidExpression { .. }, component: Some(component), .. }] if component == "/items/sequence[1]/rest"));
+        assert!(matches!(first_error(CompiledSchema::new(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+            xmlns:dfdl="http://www.ogf.org/dfdl/dfdl-1.0/">
+            <xs:element name="items"><xs:complexType><xs:sequence>
+                <xs:element name="item" type="xs:int" maxOccurs="unbounded" dfdl:occursCount="{ ../../count }"/>
+            </xs:sequence></xs:complexType></xs:element>
+        </xs:schema>"#)), Some(SchemaError::InvalidExpression { .. })));
+    }
+
+    #[test]
+    fn rejects_non_schema_documents() {
+        assert!(matches!(first_error(CompiledSchema::new("<root/>")), Some(SchemaError::NotASchema)));
+        assert!(matches!(first_error(CompiledSchema::new(&SCHEMA.replace("byteOrder", "byteOrdr"))), Some(SchemaError::UnknownProperty(_))));
+    }
+}
@@ -0,0 +1,342 @@
+use std::borrow::Cow;
+
+use crate::schema::expression::Expected;
+
+use super::values::{self, PropertyValue, Alignment, AlignmentUnits, BinaryBooleanTrueRep, BinaryCalendarRep, BinaryFloatRep, BinaryNumberRep, BitOrder, ByteOrder, CalendarPatternKind, CheckPolicy, ChoiceLengthKind, DelimiterPolicy, EmptyElementParsePolicy, EncodingErrorPolicy, EscapeKind, GenerateEscapeBlock, LengthKind, LengthUnits, NilKind, OccursCountKind, PackedSignCodes, Representation, RuntimeValue, SeparatorPosition, SeparatorSuppressionPolicy, SequenceKind, TextBidiNumeralShapes, TextBidiOrientation, TextBidiTextOrdering, TextJustification, TextNumberRep, TextNumberRounding, TextNumberRoundingMode, TextPadKind, TextTrimKind, TextZonedSignStyle, Utf16Width, Weekday};
+use super::{DfdlExpression, DfdlLiteral, DfdlRegex, PropertySet, SchemaError};
+
+
+format_properties! {
+    /// The set of DFDL format properties in effect for a schema component
+    ///
+    /// The properties common to all components are held directly, all others are grouped
+    /// following the structure of the DFDL specification.
+    pub struct Format {
+        representation: Representation = "representation",
+        byte_order: RuntimeValue<ByteOrder> = "byteOrder",
+        bit_order: BitOrder = "bitOrder",
+        encoding: RuntimeValue<String> = "encoding",
+        utf16_width: Utf16Width = "utf16Width",
+        encoding_error_policy: EncodingErrorPolicy = "encodingErrorPolicy",
+        ignore_case: bool = "ignoreCase",
+        fill_byte: u8 = "fillByte" with values::parse_fill_byte,
+        alignment: Alignment = "alignment",
+        alignment_units: AlignmentUnits = "alignmentUnits",
+        leading_skip: u64 = "leadingSkip",
+        trailing_skip: u64 = "trailingSkip",
+        ;
+        /// initiators, terminators and related properties
+        delimiters: DelimiterProperties,
+        /// properties determining the length of a component
+        length: LengthProperties,
+        /// properties common to text representations
+        text: TextProperties,
+        /// bidirectional text properties
+        bidi: BidiProperties,
+        /// properties of numbers in text representation
+        text_number: TextNumberProperties,
+        /// properties of numbers in binary representation
+        binary_number: BinaryNumberProperties,
+        /// properties of booleans
+        boolean: BooleanProperties,
+        /// properties of dates and times
+        calendar: CalendarProperties,
+        /// properties of nillable elements
+        nil: NilProperties,
+        /// properties of sequences
+        sequence: SequenceProperties,
+        /// properties of choices
+        choice: ChoiceProperties,
+        /// properties of arrays and optional elements
+        occurs: OccursProperties,
+        /// properties of calculated elements
+        calculation: CalculationProperties,
+        /// properties of escape schemes
+        escape_scheme: EscapeSchemeProperties,
+    }
+}
+
+impl Format {
+    /// Interprets the raw properties of `properties`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `properties` contains an unknown property
+    /// or a property with a value that is not valid for it.
+    pub fn from_properties(properties: &PropertySet) -> Result<Self, SchemaError> {
+        let mut error = None;
+        let format = Self::from_properties_reporting(properties, |_, e| { error.get_or_insert(e); });
+        error.map_or(Ok(format), Err)
+    }
+
+    /// interprets the raw properties of `properties`, skipping and reporting every invalid property
+    /// together with its name, rather than stopping at the first
+    pub(crate) fn from_properties_reporting(properties: &PropertySet, mut report: impl FnMut(&str, SchemaError)) -> Self {
+        let mut format = Format::default();
+        for (name, value) in properties.iter() {
+            match format.set(name, value) {
+                Ok(true) => {},
+                Ok(false) => report(name, SchemaError::UnknownProperty(name.to_string())),
+                Err(e) => report(name, e),
+            }
+        }
+        format
+    }
+
+    /// the expressions of the properties, along with the name of their property
+    /// and the type their value has to have
+    pub(crate) fn expressions_mut(&mut self) -> Vec<(&'static str, Expected, &mut DfdlExpression)> {
+        fn runtime<T>(value: &mut Option<RuntimeValue<T>>) -> Option<&mut DfdlExpression> {
+            match value {
+                Some(RuntimeValue::Expression(expression)) => Some(expression),
+                _ => None,
+            }
+        }
+        let expressions = [
+            ("byteOrder", Expected::String, runtime(&mut self.byte_order)),
+            ("encoding", Expected::String, runtime(&mut self.encoding)),
+            ("initiator", Expected::String, runtime(&mut self.delimiters.initiator)),
+            ("terminator", Expected::String, runtime(&mut self.delimiters.terminator)),
+            ("outputNewLine", Expected::String, runtime(&mut self.delimiters.output_new_line)),
+            ("length", Expected::Integer, runtime(&mut self.length.length)),
+            ("textStandardDecimalSeparator", Expected::String, runtime(&mut self.text_number.text_standard_decimal_separator)),
+            ("textStandardGroupingSeparator", Expected::String, runtime(&mut self.text_number.text_standard_grouping_separator)),
+            ("textStandardExponentRep", Expected::String, runtime(&mut self.text_number.text_standard_exponent_rep)),
+            ("binaryFloatRep", Expected::String, runtime(&mut self.binary_number.binary_float_rep)),
+            ("textBooleanTrueRep", Expected::String, runtime(&mut self.boolean.text_boolean_true_rep)),
+            ("textBooleanFalseRep", Expected::String, runtime(&mut self.boolean.text_boolean_false_rep)),
+            ("calendarLanguage", Expected::String, runtime(&mut self.calendar.calendar_language)),
+            ("separator", Expected::String, runtime(&mut self.sequence.separator)),
+            ("choiceDispatchKey", Expected::String, self.choice.choice_dispatch_key.as_mut()),
+            ("occursCount", Expected::Integer, self.occurs.occurs_count.as_mut()),
+            ("inputValueCalc", Expected::Any, self.calculation.input_value_calc.as_mut()),
+            ("outputValueCalc", Expected::Any, self.calculation.output_value_calc.as_mut()),
+            ("escapeCharacter", Expected::String, runtime(&mut self.escape_scheme.escape_character)),
+            ("escapeEscapeCharacter", Expected::String, runtime(&mut self.escape_scheme.escape_escape_character)),
+        ];
+        expressions.into_iter().filter_map(|(name, expected, expression)| Some((name, expected, expression?))).collect()
+    }
+
+    /// the format with the runtime-valued properties given by expressions replaced by their values,
+    /// `evaluate` returning the text of the value of an expression
+    ///
+    /// Fails with a description of the problem, naming the property.
+    pub(crate) fn with_runtime_values(&self, mut evaluate: impl FnMut(&DfdlExpression) -> Result<String, String>) -> Result<Cow<'_, Self>, String> {
+        fn is_expression<T>(value: &Option<RuntimeValue<T>>) -> bool {
+            matches!(value, Some(RuntimeValue::Expression(_)))
+        }
+        fn substitute<T: PropertyValue>(value: &mut Option<RuntimeValue<T>>, property: &str, evaluate: &mut dyn FnMut(&DfdlExpression) -> Result<String, String>) -> Result<(), String> {
+            if let Some(RuntimeValue::Expression(expression)) = value {
+                let text = evaluate(expression).map_err(|reason| format!("property '{property}': {reason}"))?;
+                let parsed = T::parse_value(&text).map_err(|reason| format!("property '{property}': '{text}' is not a valid value: {reason}"))?;
+                *value = Some(RuntimeValue::Value(parsed));
+            }
+            Ok(())
+        }
+        macro_rules! substitute_all {
+            ($($($field:ident).+ = $property:literal,)*) => {{
+                if !($(is_expression(&self.$($field).+))||*) {
+                    return Ok(Cow::Borrowed(self));
+                }
+                let mut format = self.clone();
+                $(substitute(&mut format.$($field).+, $property, &mut evaluate)?;)*
+                Ok(Cow::Owned(format))
+            }};
+        }
+        substitute_all! {
+            byte_order = "byteOrder",
+            encoding = "encoding",
+            delimiters.initiator = "initiator",
+            delimiters.terminator = "terminator",
+            delimiters.output_new_line = "outputNewLine",
+            length.length = "length",
+            text_number.text_standard_decimal_separator = "textStandardDecimalSeparator",
+            text_number.text_standard_grouping_separator = "textStandardGroupingSeparator",
+            text_number.text_standard_exponent_rep = "textStandardExponentRep",
+            binary_number.binary_float_rep = "binaryFloatRep",
+            boolean.text_boolean_true_rep = "textBooleanTrueRep",
+            boolean.text_boolean_false_rep = "textBooleanFalseRep",
+            calendar.calendar_language = "calendarLanguage",
+            sequence.separator = "separator",
+            escape_scheme.escape_character = "escapeCharacter",
+            escape_scheme.escape_escape_character = "escapeEscapeCharacter",
+        }
+    }
+}
+
+format_properties! {
+    /// Initiators, terminators and related properties
+    pub struct DelimiterProperties {
+        initiator: RuntimeValue<Vec<DfdlLiteral>> = "initiator",
+        terminator: RuntimeValue<Vec<DfdlLiteral>> = "terminator",
+        output_new_line: RuntimeValue<DfdlLiteral> = "outputNewLine",
+        empty_value_delimiter_policy: DelimiterPolicy = "emptyValueDelimiterPolicy",
+        document_final_terminator_can_be_missing: bool = "documentFinalTerminatorCanBeMissing",
+    }
+}
+
+format_properties! {
+    /// Properties determining the length of a component
+    pub struct LengthProperties {
+        length_kind: LengthKind = "lengthKind",
+        length: RuntimeValue<u64> = "length",
+        length_units: LengthUnits = "lengthUnits",
+        length_pattern: DfdlRegex = "lengthPattern",
+        prefix_includes_prefix_length: bool = "prefixIncludesPrefixLength",
+        prefix_length_type: String = "prefixLengthType",
+    }
+}
+
+format_properties! {
+    /// Properties common to text representations, including those of strings
+    pub struct TextProperties {
+        text_pad_kind: TextPadKind = "textPadKind",
+        text_trim_kind: TextTrimKind = "textTrimKind",
+        text_output_min_length: u64 = "textOutputMinLength",
+        escape_scheme_ref: String = "escapeSchemeRef",
+        text_string_justification: TextJustification = "textStringJustification",
+        text_string_pad_character: DfdlLiteral = "textStringPadCharacter",
+        truncate_specified_length_string: bool = "truncateSpecifiedLengthString",
+    }
+}
+
+format_properties! {
+    /// Bidirectional text properties
+    pub struct BidiProperties {
+        text_bidi: bool = "textBidi",
+        text_bidi_text_ordering: TextBidiTextOrdering = "textBidiTextOrdering",
+        text_bidi_orientation: TextBidiOrientation = "textBidiOrientation",
+        text_bidi_symmetric: bool = "textBidiSymmetric",
+        text_bidi_text_shaped: bool = "textBidiTextShaped",
+        text_bidi_numeral_shapes: TextBidiNumeralShapes = "textBidiNumeralShapes",
+    }
+}
+
+format_properties! {
+    /// Properties of numbers in text representation
+    pub struct TextNumberProperties {
+        text_number_rep: TextNumberRep = "textNumberRep",
+        text_number_justification: TextJustification = "textNumberJustification",
+        text_number_pad_character: DfdlLiteral = "textNumberPadCharacter",
+        text_number_pattern: String = "textNumberPattern",
+        text_number_rounding: TextNumberRounding = "textNumberRounding",
+        text_number_rounding_mode: TextNumberRoundingMode = "textNumberRoundingMode",
+        text_number_rounding_increment: f64 = "textNumberRoundingIncrement",
+        text_number_check_policy: CheckPolicy = "textNumberCheckPolicy",
+        text_standard_decimal_separator: RuntimeValue<Vec<DfdlLiteral>> = "textStandardDecimalSeparator",
+        text_standard_grouping_separator: RuntimeValue<DfdlLiteral> = "textStandardGroupingSeparator",
+        text_standard_exponent_rep: RuntimeValue<DfdlLiteral> = "textStandardExponentRep",
+        text_standard_infinity_rep: DfdlLiteral = "textStandardInfinityRep",
+        text_standard_nan_rep: DfdlLiteral = "textStandardNaNRep",
+        text_standard_zero_rep: Vec<DfdlLiteral> = "textStandardZeroRep",
+        text_standard_base: u32 = "textStandardBase" with values::parse_text_standard_base,
+        text_zoned_sign_style: TextZonedSignStyle = "textZonedSignStyle",
+        decimal_signed: bool = "decimalSigned",
+    }
+}
+
+format_properties! {
+    /// Properties of numbers in binary representation
+    pub struct BinaryNumberProperties {
+        binary_number_rep: BinaryNumberRep = "binaryNumberRep",
+        binary_decimal_virtual_point: i64 = "binaryDecimalVirtualPoint",
+        binary_packed_sign_codes: PackedSignCodes = "binaryPackedSignCodes",
+        binary_number_check_policy: CheckPolicy = "binaryNumberCheckPolicy",
+        binary_float_rep: RuntimeValue<BinaryFloatRep> = "binaryFloatRep",
+    }
+}
+
+format_properties! {
+    /// Properties of booleans in text and binary representation
+    pub struct BooleanProperties {
+        text_boolean_true_rep: RuntimeValue<Vec<DfdlLiteral>> = "textBooleanTrueRep",
+        text_boolean_false_rep: RuntimeValue<Vec<DfdlLiteral>> = "textBooleanFalseRep",
+        text_boolean_justification: TextJustification = "textBooleanJustification",
+        text_boolean_pad_character: DfdlLiteral = "textBooleanPadCharacter",
+        binary_boolean_true_rep: BinaryBooleanTrueRep = "binaryBooleanTrueRep",
+        binary_boolean_false_rep: u64 = "binaryBooleanFalseRep",
+    }
+}
+
+format_properties! {
+    /// Properties of dates and times in text and binary representation
+    pub struct CalendarProperties {
+        calendar_pattern_kind: CalendarPatternKind = "calendarPatternKind",
+        calendar_pattern: String = "calendarPattern",
+        calendar_check_policy: CheckPolicy = "calendarCheckPolicy",
+        calendar_time_zone: String = "calendarTimeZone",
+        calendar_observe_dst: bool = "calendarObserveDST",
+        calendar_first_day_of_week: Weekday = "calendarFirstDayOfWeek",
+        calendar_days_in_first_week: u8 = "calendarDaysInFirstWeek" with values::parse_days_in_first_week,
+        calendar_century_start: u8 = "calendarCenturyStart" with values::parse_century_start,
+        calendar_language: RuntimeValue<String> = "calendarLanguage",
+        text_calendar_justification: TextJustification = "textCalendarJustification",
+        text_calendar_pad_character: DfdlLiteral = "textCalendarPadCharacter",
+        binary_calendar_rep: BinaryCalendarRep = "binaryCalendarRep",
+        binary_calendar_epoch: String = "binaryCalendarEpoch",
+    }
+}
+
+format_properties! {
+    /// Properties of nillable elements
+    pub struct NilProperties {
+        nil_kind: NilKind = "nilKind",
+        nil_value: Vec<DfdlLiteral> = "nilValue",
+        nil_value_delimiter_policy: DelimiterPolicy = "nilValueDelimiterPolicy",
+        use_nil_for_default: bool = "useNilForDefault",
+        empty_element_parse_policy: EmptyElementParsePolicy = "emptyElementParsePolicy",
+    }
+}
+
+format_properties! {
+    /// Properties of sequences
+    pub struct SequenceProperties {
+        sequence_kind: SequenceKind = "sequenceKind",
+        hidden_group_ref: String = "hiddenGroupRef",
+        initiated_content: bool = "initiatedContent",
+        separator: RuntimeValue<Vec<DfdlLiteral>> = "separator",
+        separator_position: SeparatorPosition = "separatorPosition",
+        separator_suppression_policy: SeparatorSuppressionPolicy = "separatorSuppressionPolicy",
+        floating: bool = "floating",
+    }
+}
+
+format_properties! {
+    /// Properties of choices
+    pub struct ChoiceProperties {
+        choice_length_kind: ChoiceLengthKind = "choiceLengthKind",
+        choice_length: u64 = "choiceLength",
+        choice_dispatch_key: DfdlExpression = "choiceDispatchKey",
+        choice_branch_key: Vec<DfdlLiteral> = "choiceBranchKey",
+    }
+}
+
+format_properties! {
+    /// Properties of arrays and optional elements
+    pub struct OccursProperties {
+        occurs_count_kind: OccursCountKind = "occursCountKind",
+        occurs_count: DfdlExpression = "occursCount",
+        occurs_stop_value: Vec<DfdlLiteral> = "occursStopValue",
+    }
+}
+
+format_properties! {
+    /// Properties of calculated elements
+    pub struct CalculationProperties {
+        input_value_calc: DfdlExpression = "inputValueCalc",
+        output_value_calc: DfdlExpression = "outputValueCalc",
+    }
+}
+
+format_properties! {
+    /// Properties of escape schemes, as given in a `dfdl:escapeScheme` annotation
+    pub struct EscapeSchemeProperties {
+        escape_kind: EscapeKind = "escapeKind",
+        escape_character: RuntimeValue<DfdlLiteral> = "escapeCharacter",
+        escape_block_start: DfdlLiteral = "escapeBlockStart",
+        escape_block_end: DfdlLiteral = "escapeBlockEnd",
+        escape_escape_character: RuntimeValue<DfdlLiteral> = "escapeEscapeCharacter",
+        extra_escaped_characters: Vec<DfdlLiteral> = "extraEscapedCharacters",
+        generate_escape_block: GenerateEscapeBlock = "generateEscapeBlock",
+    }
+}
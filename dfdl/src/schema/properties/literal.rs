@@ -0,0 +1,183 @@
+use std::fmt::{Display, Write};
+
+
+/// A DFDL string literal, e.g. the value of `dfdl:initiator`
+///
+/// Besides plain characters, string literals may contain DFDL entities:
+/// named characters such as `%NUL;` or `%SP;`, numeric characters (`%#65;`, `%#x41;`),
+/// raw bytes (`%#rFF;`), character classes (`%NL;`, `%WSP;`, `%WSP*;`, `%WSP+;`, `%ES;`)
+/// and `%%` for a literal percent sign.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DfdlLiteral {
+    parts: Vec<LiteralPart>,
+}
+
+/// A single element of a [`DfdlLiteral`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LiteralPart {
+    /// a character
+    Char(char),
+    /// a raw byte, given as `%#rXX;`
+    Byte(u8),
+    /// a character class entity
+    Class(CharClass),
+}
+
+/// The character class entities of DFDL string literals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    /// `%NL;`, any newline sequence
+    NewLine,
+    /// `%WSP;`, a single whitespace character
+    Whitespace,
+    /// `%WSP*;`, zero or more whitespace characters
+    WhitespaceStar,
+    /// `%WSP+;`, one or more whitespace characters
+    WhitespacePlus,
+    /// `%ES;`, the empty string
+    Empty,
+}
+
+/// the named character entities of DFDL string literals
+const NAMED_CHARACTERS: &[(&str, char)] = &[
+    ("NUL", '\u{00}'), ("SOH", '\u{01}'), ("STX", '\u{02}'), ("ETX", '\u{03}'),
+    ("EOT", '\u{04}'), ("ENQ", '\u{05}'), ("ACK", '\u{06}'), ("BEL", '\u{07}'),
+    ("BS", '\u{08}'), ("HT", '\u{09}'), ("LF", '\u{0A}'), ("VT", '\u{0B}'),
+    ("FF", '\u{0C}'), ("CR", '\u{0D}'), ("SO", '\u{0E}'), ("SI", '\u{0F}'),
+    ("DLE", '\u{10}'), ("DC1", '\u{11}'), ("DC2", '\u{12}'), ("DC3", '\u{13}'),
+    ("DC4", '\u{14}'), ("NAK", '\u{15}'), ("SYN", '\u{16}'), ("ETB", '\u{17}'),
+    ("CAN", '\u{18}'), ("EM", '\u{19}'), ("SUB", '\u{1A}'), ("ESC", '\u{1B}'),
+    ("FS", '\u{1C}'), ("GS", '\u{1D}'), ("RS", '\u{1E}'), ("US", '\u{1F}'),
+    ("SP", '\u{20}'), ("DEL", '\u{7F}'), ("NBSP", '\u{A0}'), ("NEL", '\u{85}'),
+    ("LS", '\u{2028}'),
+];
+
+impl DfdlLiteral {
+    /// Parses a single DFDL string literal
+    ///
+    /// # Errors
+    ///
+    /// This function will return a description of the problem if `literal` contains a malformed entity.
+    pub fn parse(literal: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut rest = literal;
+        while let Some(c) = rest.chars().next() {
+            if c != '%' {
+                parts.push(LiteralPart::Char(c));
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix("%%") {
+                parts.push(LiteralPart::Char('%'));
+                rest = after;
+                continue;
+            }
+            let end = rest.find(';').ok_or_else(|| format!("unterminated entity in '{literal}'"))?;
+            let entity = &rest[1..end];
+            rest = &rest[end + 1..];
+            parts.push(Self::parse_entity(entity)?);
+        }
+        Ok(Self { parts })
+    }
+
+    fn parse_entity(entity: &str) -> Result<LiteralPart, String> {
+        let part = match entity {
+            "NL" => LiteralPart::Class(CharClass::NewLine),
+            "WSP" => LiteralPart::Class(CharClass::Whitespace),
+            "WSP*" => LiteralPart::Class(CharClass::WhitespaceStar),
+            "WSP+" => LiteralPart::Class(CharClass::WhitespacePlus),
+            "ES" => LiteralPart::Class(CharClass::Empty),
+            _ => if let Some(byte) = entity.strip_prefix("#r") {
+                LiteralPart::Byte(u8::from_str_radix(byte, 16).map_err(|_| format!("invalid byte entity '%{entity};'"))?)
+            }
+            else if let Some(code) = entity.strip_prefix("#x") {
+                LiteralPart::Char(u32::from_str_radix(code, 16).ok().and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid character entity '%{entity};'"))?)
+            }
+            else if let Some(code) = entity.strip_prefix('#') {
+                LiteralPart::Char(code.parse().ok().and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid character entity '%{entity};'"))?)
+            }
+            else {
+                let (_, c) = NAMED_CHARACTERS.iter().find(|(name, _)| *name == entity)
+                    .ok_or_else(|| format!("unknown entity '%{entity};'"))?;
+                LiteralPart::Char(*c)
+            },
+        };
+        Ok(part)
+    }
+
+    /// Parses a whitespace separated list of DFDL string literals, as used by delimiter properties
+    ///
+    /// # Errors
+    ///
+    /// This function will return a description of the problem if any literal contains a malformed entity.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>, String> {
+        list.split_ascii_whitespace().map(Self::parse).collect()
+    }
+
+    /// Returns the parts of the literal
+    #[must_use]
+    pub fn parts(&self) -> &[LiteralPart] {
+        &self.parts
+    }
+
+    /// Returns `true` if the literal is the empty string
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns the literal as plain text, if it consists only of characters
+    #[must_use]
+    pub fn as_text(&self) -> Option<String> {
+        self.parts.iter().map(|part| match part {
+            LiteralPart::Char(c) => Some(*c),
+            LiteralPart::Byte(_) | LiteralPart::Class(_) => None,
+        }).collect()
+    }
+}
+
+impl Display for DfdlLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for part in &self.parts {
+            match part {
+                LiteralPart::Char('%') => f.write_str("%%")?,
+                LiteralPart::Char(c) if c.is_whitespace() || c.is_control() => write!(f, "%#x{:X};", u32::from(*c))?,
+                LiteralPart::Char(c) => f.write_char(*c)?,
+                LiteralPart::Byte(b) => write!(f, "%#r{b:02X};")?,
+                LiteralPart::Class(CharClass::NewLine) => f.write_str("%NL;")?,
+                LiteralPart::Class(CharClass::Whitespace) => f.write_str("%WSP;")?,
+                LiteralPart::Class(CharClass::WhitespaceStar) => f.write_str("%WSP*;")?,
+                LiteralPart::Class(CharClass::WhitespacePlus) => f.write_str("%WSP+;")?,
+                LiteralPart::Class(CharClass::Empty) => f.write_str("%ES;")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entities() {
+        let literal = DfdlLiteral::parse("a%%%SP;%#x0D;%#65;%#rFF;%NL;%WSP*;").unwrap();
+        assert_eq!(literal.parts(), &[
+            LiteralPart::Char('a'),
+            LiteralPart::Char('%'),
+            LiteralPart::Char(' '),
+            LiteralPart::Char('\r'),
+            LiteralPart::Char('A'),
+            LiteralPart::Byte(0xFF),
+            LiteralPart::Class(CharClass::NewLine),
+            LiteralPart::Class(CharClass::WhitespaceStar),
+        ]);
+        assert_eq!(DfdlLiteral::parse(&literal.to_string()).unwrap(), literal);
+        assert!(DfdlLiteral::parse("%FOO;").is_err());
+        assert!(DfdlLiteral::parse("%NL").is_err());
+        assert_eq!(DfdlLiteral::parse_list(" , %NL;  ;").unwrap().len(), 3);
+    }
+}
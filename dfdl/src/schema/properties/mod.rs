@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use super::expression::{Expression, ExpressionError};
+use super::{SchemaError, SourceLocation};
+
+mod literal;
+mod values;
+
+pub use literal::{CharClass, DfdlLiteral, LiteralPart};
+pub use values::{
+    Alignment, AlignmentUnits, BinaryBooleanTrueRep, BinaryCalendarRep, BinaryFloatRep, BinaryNumberRep, BitOrder, ByteOrder,
+    CalendarPatternKind, CheckPolicy, ChoiceLengthKind, EmptyElementParsePolicy, EncodingErrorPolicy, EscapeKind,
+    GenerateEscapeBlock, DelimiterPolicy, LengthKind, LengthUnits, NilKind, OccursCountKind, PackedSignCodes, Representation,
+    RuntimeValue, SeparatorPosition, SeparatorSuppressionPolicy, SequenceKind, TextBidiNumeralShapes, TextBidiOrientation,
+    TextBidiTextOrdering, TextJustification, TextNumberRep, TextNumberRounding, TextNumberRoundingMode, TextPadKind,
+    TextTrimKind, TextZonedSignStyle, Utf16Width, Weekday,
+};
+
+/// DFDL properties as written in a schema, before they are interpreted
+///
+/// This is the unit property scoping works on: the properties of the annotations of a schema
+/// component, of the formats it references, and of the schema document it is defined in are
+/// collected into property sets, which are combined into the [`Format`] of the component.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropertySet {
+    properties: BTreeMap<String, String>,
+    /// where in the schema each property is set, if known
+    locations: BTreeMap<String, SourceLocation>,
+}
+
+impl PropertySet {
+    /// Returns the raw value of the property `name`, if it is set
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+    /// Returns where in the schema the property `name` is set, if known
+    #[must_use]
+    pub fn location(&self, name: &str) -> Option<&SourceLocation> {
+        self.locations.get(name)
+    }
+    /// Iterates over all set properties as pairs of name and raw value
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+    /// Returns `true` if no property is set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+    /// Sets the property `name` to `value`
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the property is already set.
+    pub fn insert(&mut self, name: &str, value: &str) -> Result<(), SchemaError> {
+        if self.properties.contains_key(name) {
+            return Err(SchemaError::DuplicateProperty(name.to_string()));
+        }
+        self.properties.insert(name.to_string(), value.to_string());
+        Ok(())
+    }
+    /// sets the property `name` to `value`, as given at `location`
+    pub(crate) fn insert_at(&mut self, name: &str, value: &str, location: SourceLocation) -> Result<(), SchemaError> {
+        self.insert(name, value)?;
+        self.locations.insert(name.to_string(), location);
+        Ok(())
+    }
+    /// Overrides the properties of `self` with all properties set in `other`
+    pub fn merge_in_place(&mut self, other: Self) {
+        for name in other.properties.keys() {
+            self.locations.remove(name);
+        }
+        self.properties.extend(other.properties);
+        self.locations.extend(other.locations);
+    }
+    /// Returns `self` with all properties set in `other` overriding its own
+    #[must_use]
+    pub fn merge(mut self, other: Self) -> Self {
+        self.merge_in_place(other);
+        self
+    }
+}
+
+/// parses a property value, either via its [`PropertyValue`](values::PropertyValue) implementation or a dedicated parser
+macro_rules! parse_property {
+    ($ty:ty, $value:expr) => { values::parse_value::<$ty>($value) };
+    ($ty:ty, $value:expr, $parser:path) => { $parser($value) };
+}
+
+/// defines a struct holding the typed values of a group of properties
+///
+/// Properties are listed as `field: Type = "propertyName"`, optionally followed by `with parser`
+/// to use a dedicated parser instead of the [`PropertyValue`](values::PropertyValue) implementation of `Type`.
+/// Nested groups may be listed after a `;`.
+macro_rules! format_properties {
+    ($(#[$meta:meta])* pub struct $name:ident {
+        $( $field:ident : $ty:ty = $prop:literal $(with $parser:path)?, )*
+        $( ; $( $(#[$group_meta:meta])* $group:ident : $group_ty:ty, )* )?
+    }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, PartialEq)]
+        pub struct $name {
+            $( #[doc = concat!("`dfdl:", $prop, "`")] pub $field: Option<$ty>, )*
+            $($( $(#[$group_meta])* pub $group: $group_ty, )*)?
+        }
+        impl $name {
+            /// sets the property `name` to the parsed `value`, returns `false` if the property is not part of this group
+            fn set(&mut self, name: &str, value: &str) -> Result<bool, SchemaError> {
+                match name {
+                    $( $prop => self.$field = Some(parse_property!($ty, value $(, $parser)?)
+                        .map_err(|reason| SchemaError::InvalidPropertyValue {
+                            property: name.to_string(),
+                            value: value.to_string(),
+                            reason,
+                        })?), )*
+                    _ => {
+                        $($( if self.$group.set(name, value)? { return Ok(true); } )*)?
+                        return Ok(false);
+                    },
+                }
+                Ok(true)
+            }
+        }
+    };
+}
+
+mod format;
+
+pub use format::{
+    BidiProperties, BinaryNumberProperties, BooleanProperties, CalculationProperties, CalendarProperties, ChoiceProperties,
+    DelimiterProperties, EscapeSchemeProperties, Format, LengthProperties, NilProperties, OccursProperties,
+    SequenceProperties, TextNumberProperties, TextProperties,
+};
+
+/// A `dfdl:assert` annotation
+#[derive(Clone, Debug, Default)]
+pub struct Assert {
+    /// whether the assert is checked via an expression or a regular expression
+    pub test_kind: AssertTestKind,
+    /// the expression to evaluate, for [`AssertTestKind::Expression`]
+    pub test: Option<DfdlExpression>,
+    /// the regular expression to match, for [`AssertTestKind::Pattern`]
+    pub test_pattern: Option<String>,
+    /// message reported when the assert fails
+    pub message: String,
+    /// the kind of error a failing assert produces
+    pub failure_type: AssertFailureType,
+}
+/// A `dfdl:discriminator` annotation
+///
+/// A discriminator that holds resolves the nearest point of uncertainty, e.g. commits to the branch of a choice.
+#[derive(Clone, Debug, Default)]
+pub struct Discriminator {
+    /// whether the discriminator is checked via an expression or a regular expression
+    pub test_kind: AssertTestKind,
+    /// the expression to evaluate, for [`AssertTestKind::Expression`]
+    pub test: Option<DfdlExpression>,
+    /// the regular expression to match, for [`AssertTestKind::Pattern`]
+    pub test_pattern: Option<String>,
+    /// message reported when the discriminator does not hold
+    pub message: String,
+}
+
+/// `dfdl:testKind` of an [`Assert`] or [`Discriminator`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssertTestKind {
+    /// the assert is checked by evaluating an expression
+    #[default]
+    Expression,
+    /// the assert is checked by matching a regular expression against the data
+    Pattern,
+}
+/// `dfdl:failureType` of an [`Assert`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AssertFailureType {
+    /// a failing assert is a processing error
+    #[default]
+    ProcessingError,
+    /// a failing assert is a recoverable error and does not stop processing
+    RecoverableError,
+}
+
+/// A DFDL expression, as used by runtime-valued properties and asserts
+///
+/// Two expressions are equal if they have the same source text.
+#[derive(Clone, Debug)]
+pub struct DfdlExpression {
+    source: String,
+    expression: Expression,
+}
+impl DfdlExpression {
+    pub(crate) fn new(source: &str) -> Result<Self, ExpressionError> {
+        Ok(Self { source: source.to_string(), expression: Expression::parse(source)? })
+    }
+    /// Returns the source text of the expression
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+    /// Returns the parsed expression, whose types are known once the schema is compiled
+    #[must_use]
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+    pub(crate) fn expression_mut(&mut self) -> &mut Expression {
+        &mut self.expression
+    }
+}
+impl PartialEq for DfdlExpression {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+impl Eq for DfdlExpression {}
+/// A DFDL regular expression
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DfdlRegex(String);
+impl DfdlRegex {
+    /// the regular expression `pattern`, which has to be valid
+    pub(crate) fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::Regex::new(pattern)?;
+        Ok(Self(pattern.to_string()))
+    }
+    /// Returns the source text of the regular expression
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,546 @@
+use super::{DfdlExpression, DfdlLiteral, DfdlRegex};
+
+
+/// Conversion of the raw text of a property into its typed value
+pub(crate) trait PropertyValue: Sized {
+    /// parses `value`, returning a description of the expected values on failure
+    fn parse_value(value: &str) -> Result<Self, String>;
+}
+
+/// parses `value` as a `T`, pointing out expressions given for properties that can not take them
+pub(super) fn parse_value<T: PropertyValue>(value: &str) -> Result<T, String> {
+    T::parse_value(value).map_err(|reason| {
+        if value.starts_with('{') && !value.starts_with("{{") {
+            "this property does not accept expressions".to_string()
+        }
+        else {
+            reason
+        }
+    })
+}
+
+/// The value of a property that may be computed at runtime via a DFDL expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeValue<T> {
+    /// the value is given literally
+    Value(T),
+    /// the value is the result of evaluating the expression
+    Expression(DfdlExpression),
+}
+
+impl<T> RuntimeValue<T> {
+    /// Returns the literal value, or `None` if the value is computed at runtime
+    #[must_use]
+    pub fn as_value(&self) -> Option<&T> {
+        match self {
+            Self::Value(value) => Some(value),
+            Self::Expression(_) => None,
+        }
+    }
+}
+
+impl<T: PropertyValue> PropertyValue for RuntimeValue<T> {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        // a leading '{{' escapes a literal value starting with '{'
+        if let Some(literal) = value.strip_prefix("{{") {
+            return T::parse_value(&format!("{{{literal}")).map(Self::Value);
+        }
+        if let Some(expression) = value.strip_prefix('{') {
+            let expression = expression.strip_suffix('}').ok_or("expression is missing its closing '}'")?;
+            return DfdlExpression::new(expression.trim()).map(Self::Expression).map_err(|e| e.to_string());
+        }
+        T::parse_value(value).map(Self::Value)
+    }
+}
+
+impl PropertyValue for bool {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value {
+            "yes" => Ok(true),
+            "no" => Ok(false),
+            _ => Err("expected 'yes' or 'no'".to_string()),
+        }
+    }
+}
+
+impl PropertyValue for u64 {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        value.parse().map_err(|_| "expected a non-negative integer".to_string())
+    }
+}
+
+impl PropertyValue for String {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        Ok(value.to_string())
+    }
+}
+
+impl PropertyValue for Vec<DfdlLiteral> {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        DfdlLiteral::parse_list(value)
+    }
+}
+
+/// parses `dfdl:fillByte`, a single byte given as a byte entity
+///
+/// Characters are not accepted, as the byte they stand for depends on the encoding.
+pub(super) fn parse_fill_byte(value: &str) -> Result<u8, String> {
+    use super::LiteralPart;
+    let literal = DfdlLiteral::parse(value)?;
+    match literal.parts() {
+        [LiteralPart::Byte(byte)] => Ok(*byte),
+        _ => Err("expected a single byte, given as a '%#rXX;' entity".to_string()),
+    }
+}
+
+/// Value of `dfdl:alignment`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// `implicit`, the natural alignment of the type
+    Implicit,
+    /// alignment to a multiple of the given number of `dfdl:alignmentUnits`
+    Fixed(u64),
+}
+
+impl PropertyValue for Alignment {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        if value == "implicit" { return Ok(Self::Implicit); }
+        match value.parse() {
+            Ok(alignment) if alignment > 0 => Ok(Self::Fixed(alignment)),
+            _ => Err("expected 'implicit' or a positive integer".to_string()),
+        }
+    }
+}
+
+/// defines an enum for a property with a fixed set of values
+macro_rules! property_enum {
+    ($(#[$meta:meta])* $name:ident { $( $variant:ident = $text:literal, )* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $( #[doc = concat!("`", $text, "`")] $variant, )*
+        }
+        impl $name {
+            /// Returns the value as written in a schema
+            #[must_use]
+            pub fn as_str(self) -> &'static str {
+                match self { $( Self::$variant => $text, )* }
+            }
+        }
+        impl PropertyValue for $name {
+            fn parse_value(value: &str) -> Result<Self, String> {
+                match value {
+                    $( $text => Ok(Self::$variant), )*
+                    _ => Err(concat!("expected one of" $(, " '", $text, "'")*).to_string()),
+                }
+            }
+        }
+    };
+}
+
+property_enum! {
+    /// Value of `dfdl:representation`
+    Representation {
+        Binary = "binary",
+        Text = "text",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:binaryNumberRep`
+    BinaryNumberRep {
+        Binary = "binary",
+        Packed = "packed",
+        Bcd = "bcd",
+        Ibm4690Packed = "ibm4690Packed",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:binaryFloatRep`
+    BinaryFloatRep {
+        Ieee = "ieee",
+        Ibm390Hex = "ibm390Hex",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:byteOrder`
+    ByteOrder {
+        BigEndian = "bigEndian",
+        LittleEndian = "littleEndian",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:bitOrder`
+    BitOrder {
+        MostSignificantBitFirst = "mostSignificantBitFirst",
+        LeastSignificantBitFirst = "leastSignificantBitFirst",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:lengthKind`
+    LengthKind {
+        Explicit = "explicit",
+        Delimited = "delimited",
+        Prefixed = "prefixed",
+        Implicit = "implicit",
+        Pattern = "pattern",
+        EndOfParent = "endOfParent",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:lengthUnits`
+    LengthUnits {
+        Bits = "bits",
+        Bytes = "bytes",
+        Characters = "characters",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:alignmentUnits`
+    AlignmentUnits {
+        Bits = "bits",
+        Bytes = "bytes",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:sequenceKind`
+    SequenceKind {
+        Ordered = "ordered",
+        Unordered = "unordered",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textPadKind`
+    TextPadKind {
+        None = "none",
+        PadChar = "padChar",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:encodingErrorPolicy`
+    EncodingErrorPolicy {
+        Error = "error",
+        Replace = "replace",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:occursCountKind`
+    OccursCountKind {
+        Fixed = "fixed",
+        Expression = "expression",
+        Implicit = "implicit",
+        Parsed = "parsed",
+        StopValue = "stopValue",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:utf16Width`
+    Utf16Width {
+        Fixed = "fixed",
+        Variable = "variable",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:emptyValueDelimiterPolicy` and `dfdl:nilValueDelimiterPolicy`
+    DelimiterPolicy {
+        None = "none",
+        Initiator = "initiator",
+        Terminator = "terminator",
+        Both = "both",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textTrimKind`
+    TextTrimKind {
+        None = "none",
+        PadChar = "padChar",
+    }
+}
+
+property_enum! {
+    /// Value of the `dfdl:text*Justification` properties
+    TextJustification {
+        Left = "left",
+        Right = "right",
+        Center = "center",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textBidiTextOrdering`
+    TextBidiTextOrdering {
+        Implicit = "implicit",
+        Visual = "visual",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textBidiOrientation`
+    TextBidiOrientation {
+        LeftToRight = "LTR",
+        RightToLeft = "RTL",
+        Contextual = "contextual",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textBidiNumeralShapes`
+    TextBidiNumeralShapes {
+        Nominal = "nominal",
+        National = "national",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textNumberRep`
+    TextNumberRep {
+        Standard = "standard",
+        Zoned = "zoned",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textNumberRounding`
+    TextNumberRounding {
+        Pattern = "pattern",
+        Explicit = "explicit",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textNumberRoundingMode`
+    TextNumberRoundingMode {
+        Ceiling = "roundCeiling",
+        Floor = "roundFloor",
+        Down = "roundDown",
+        Up = "roundUp",
+        HalfEven = "roundHalfEven",
+        HalfDown = "roundHalfDown",
+        HalfUp = "roundHalfUp",
+        Unnecessary = "roundUnnecessary",
+    }
+}
+
+property_enum! {
+    /// Value of the `dfdl:*CheckPolicy` properties
+    CheckPolicy {
+        Strict = "strict",
+        Lax = "lax",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:textZonedSignStyle`
+    TextZonedSignStyle {
+        AsciiStandard = "asciiStandard",
+        AsciiTranslatedEbcdic = "asciiTranslatedEBCDIC",
+        AsciiCaRealiaModified = "asciiCARealiaModified",
+        AsciiTandemModified = "asciiTandemModified",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:calendarPatternKind`
+    CalendarPatternKind {
+        Explicit = "explicit",
+        Implicit = "implicit",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:calendarFirstDayOfWeek`
+    Weekday {
+        Monday = "Monday",
+        Tuesday = "Tuesday",
+        Wednesday = "Wednesday",
+        Thursday = "Thursday",
+        Friday = "Friday",
+        Saturday = "Saturday",
+        Sunday = "Sunday",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:binaryCalendarRep`
+    BinaryCalendarRep {
+        Packed = "packed",
+        Bcd = "bcd",
+        Ibm4690Packed = "ibm4690Packed",
+        BinarySeconds = "binarySeconds",
+        BinaryMilliseconds = "binaryMilliseconds",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:nilKind`
+    NilKind {
+        LiteralValue = "literalValue",
+        LogicalValue = "logicalValue",
+        LiteralCharacter = "literalCharacter",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:emptyElementParsePolicy`
+    EmptyElementParsePolicy {
+        TreatAsEmpty = "treatAsEmpty",
+        TreatAsAbsent = "treatAsAbsent",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:separatorPosition`
+    SeparatorPosition {
+        Infix = "infix",
+        Prefix = "prefix",
+        Postfix = "postfix",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:separatorSuppressionPolicy`
+    SeparatorSuppressionPolicy {
+        Never = "never",
+        TrailingEmpty = "trailingEmpty",
+        TrailingEmptyStrict = "trailingEmptyStrict",
+        AnyEmpty = "anyEmpty",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:choiceLengthKind`
+    ChoiceLengthKind {
+        Implicit = "implicit",
+        Explicit = "explicit",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:escapeKind`
+    EscapeKind {
+        EscapeCharacter = "escapeCharacter",
+        EscapeBlock = "escapeBlock",
+    }
+}
+
+property_enum! {
+    /// Value of `dfdl:generateEscapeBlock`
+    GenerateEscapeBlock {
+        Always = "always",
+        WhenNeeded = "whenNeeded",
+    }
+}
+
+impl PropertyValue for i64 {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        value.parse().map_err(|_| "expected an integer".to_string())
+    }
+}
+
+impl PropertyValue for f64 {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        value.parse().map_err(|_| "expected a number".to_string())
+    }
+}
+
+impl PropertyValue for DfdlLiteral {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        DfdlLiteral::parse(value)
+    }
+}
+
+impl PropertyValue for DfdlExpression {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        let expression = value.strip_prefix('{').and_then(|e| e.strip_suffix('}'))
+            .ok_or_else(|| "expected an expression enclosed in '{' and '}'".to_string())?;
+        DfdlExpression::new(expression.trim()).map_err(|e| e.to_string())
+    }
+}
+
+impl PropertyValue for DfdlRegex {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        DfdlRegex::new(value).map_err(|e| format!("expected a regular expression: {e}"))
+    }
+}
+
+/// Value of `dfdl:binaryPackedSignCodes`, the sign nibbles of packed decimals
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedSignCodes {
+    /// nibble of positive values
+    pub positive: u8,
+    /// nibble of negative values
+    pub negative: u8,
+    /// nibble of unsigned values
+    pub unsigned: u8,
+    /// nibble of zero
+    pub zero: u8,
+}
+
+impl PropertyValue for PackedSignCodes {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        const ERROR: &str = "expected four sign codes for positive, negative, unsigned and zero values";
+        let codes = value.split_ascii_whitespace()
+            .map(|code| u8::from_str_radix(code, 16).ok().filter(|_| code.len() == 1).ok_or_else(|| format!("invalid sign code '{code}'")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [positive, negative, unsigned, zero] = codes[..] else { return Err(ERROR.to_string()) };
+        let valid = [0xA, 0xC, 0xE, 0xF].contains(&positive)
+            && [0xB, 0xD].contains(&negative)
+            && unsigned == 0xF
+            && [0xA, 0xC, 0xE, 0xF, 0x0].contains(&zero);
+        if !valid { return Err(format!("invalid sign codes '{value}'")); }
+        Ok(Self { positive, negative, unsigned, zero })
+    }
+}
+
+/// Value of `dfdl:binaryBooleanTrueRep`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryBooleanTrueRep {
+    /// the empty string, any value other than `dfdl:binaryBooleanFalseRep` is true
+    NotFalse,
+    /// the given value is true
+    Value(u64),
+}
+
+impl PropertyValue for BinaryBooleanTrueRep {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        if value.is_empty() { return Ok(Self::NotFalse); }
+        u64::parse_value(value).map(Self::Value)
+    }
+}
+
+/// parses `dfdl:textStandardBase`
+pub(super) fn parse_text_standard_base(value: &str) -> Result<u32, String> {
+    match value {
+        "2" | "8" | "10" | "16" => Ok(value.parse().unwrap()),
+        _ => Err("expected one of '2' '8' '10' '16'".to_string()),
+    }
+}
+
+/// parses `dfdl:calendarDaysInFirstWeek`
+pub(super) fn parse_days_in_first_week(value: &str) -> Result<u8, String> {
+    match value.parse() {
+        Ok(days @ 1..=7) => Ok(days),
+        _ => Err("expected an integer from 1 to 7".to_string()),
+    }
+}
+
+/// parses `dfdl:calendarCenturyStart`
+pub(super) fn parse_century_start(value: &str) -> Result<u8, String> {
+    match value.parse() {
+        Ok(year @ 0..=99) => Ok(year),
+        _ => Err("expected an integer from 0 to 99".to_string()),
+    }
+}
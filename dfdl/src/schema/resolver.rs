@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::SchemaError;
+
+
+/// Locates and loads the schema documents referenced by `xs:include` and `xs:import`
+pub trait SchemaResolver {
+    /// Resolves `location`, as found in a `schemaLocation` attribute of the document at `base`,
+    /// into the canonical location of the referenced document.
+    ///
+    /// `base` is `None` for references made from a root document that has no location of its own.
+    /// Two references resolving to the same canonical location refer to the same document.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `location` can not be resolved.
+    fn resolve(&self, location: &str, base: Option<&str>) -> Result<String, SchemaError>;
+    /// Loads the content of the document at the canonical `location` returned by [`resolve`](Self::resolve)
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the document can not be loaded.
+    fn load(&self, location: &str) -> Result<Cow<'_, str>, SchemaError>;
+}
+
+/// A [`SchemaResolver`] loading schema documents from the filesystem
+///
+/// Relative locations are resolved against the directory of the referencing document,
+/// or against the current working directory for references from a root document without location.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileSystemResolver;
+
+impl SchemaResolver for FileSystemResolver {
+    fn resolve(&self, location: &str, base: Option<&str>) -> Result<String, SchemaError> {
+        let path = match base.and_then(|base| Path::new(base).parent()) {
+            Some(dir) => dir.join(location),
+            None => Path::new(location).to_path_buf(),
+        };
+        let path = path.canonicalize()
+            .map_err(|e| SchemaError::Resolve { location: location.to_string(), reason: e.to_string() })?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    fn load(&self, location: &str) -> Result<Cow<'_, str>, SchemaError> {
+        std::fs::read_to_string(location)
+            .map(Cow::Owned)
+            .map_err(|e| SchemaError::Resolve { location: location.to_string(), reason: e.to_string() })
+    }
+}
+
+/// A [`SchemaResolver`] serving schema documents from memory, e.g. from `include_str!` bundles
+///
+/// Locations are treated as `/`-separated paths; relative locations are resolved
+/// against the "directory" of the referencing document.
+#[derive(Clone, Debug, Default)]
+pub struct MapResolver {
+    documents: HashMap<String, Cow<'static, str>>,
+}
+
+impl MapResolver {
+    /// Creates an empty [`MapResolver`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the document `source` under `location`, replacing any previous document there
+    pub fn insert(&mut self, location: impl Into<String>, source: impl Into<Cow<'static, str>>) {
+        self.documents.insert(normalize(&location.into()), source.into());
+    }
+    /// Returns `self` with the document `source` added under `location`
+    #[must_use]
+    pub fn with(mut self, location: impl Into<String>, source: impl Into<Cow<'static, str>>) -> Self {
+        self.insert(location, source);
+        self
+    }
+}
+
+impl<L: Into<String>, S: Into<Cow<'static, str>>> FromIterator<(L, S)> for MapResolver {
+    fn from_iter<T: IntoIterator<Item = (L, S)>>(iter: T) -> Self {
+        let mut resolver = Self::new();
+        for (location, source) in iter {
+            resolver.insert(location, source);
+        }
+        resolver
+    }
+}
+
+impl SchemaResolver for MapResolver {
+    fn resolve(&self, location: &str, base: Option<&str>) -> Result<String, SchemaError> {
+        let joined = match base.and_then(|base| base.rsplit_once('/')) {
+            Some((dir, _)) if !location.starts_with('/') => format!("{dir}/{location}"),
+            _ => location.to_string(),
+        };
+        let resolved = normalize(&joined);
+        if !self.documents.contains_key(&resolved) {
+            return Err(SchemaError::Resolve { location: location.to_string(), reason: format!("no document at '{resolved}'") });
+        }
+        Ok(resolved)
+    }
+
+    fn load(&self, location: &str) -> Result<Cow<'_, str>, SchemaError> {
+        self.documents.get(location)
+            .map(|source| Cow::Borrowed(source.as_ref()))
+            .ok_or_else(|| SchemaError::Resolve { location: location.to_string(), reason: "no such document".to_string() })
+    }
+}
+
+/// removes `.` and `..` segments from a `/`-separated path
+fn normalize(location: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in location.split('/') {
+        match segment {
+            "." => {},
+            ".." if segments.last().is_some_and(|s| !s.is_empty() && *s != "..") => { segments.pop(); },
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_resolver_resolves_relative_locations() {
+        let resolver = MapResolver::new()
+            .with("formats/main.xsd", "")
+            .with("common/types.xsd", "");
+        assert_eq!(resolver.resolve("../common/types.xsd", Some("formats/main.xsd")).unwrap(), "common/types.xsd");
+        assert_eq!(resolver.resolve("./main.xsd", Some("formats/other.xsd")).unwrap(), "formats/main.xsd");
+        assert!(resolver.resolve("missing.xsd", None).is_err());
+    }
+}
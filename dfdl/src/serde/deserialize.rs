@@ -7,16 +7,26 @@ use thiserror::Error;
 
 use crate::{Data, Element};
 
+/// Errors occuring while deserializing a value from an [`Infoset`](crate::Infoset)
 #[derive(Error, Debug)]
 pub enum DeserializationError {
+    /// custom error raised by a [`Deserialize`](serde::Deserialize) implementation
     #[error("")]
     Custom(String),
+    /// the infoset holds data of a different type than requested
     #[error("missmatched type: expected {expected}, found {infoset}")]
-    TypeMismatch{infoset: &'static str, expected: &'static str},
-
+    TypeMismatch{
+        /// type found in the infoset
+        infoset: &'static str,
+        /// type requested by the deserializer
+        expected: &'static str,
+    },
+
+    /// a requested element is not present in the infoset
     #[error("element not found")]
     ElementNotFound,
 
+    /// the requested kind of deserialization is not supported yet
     #[error("not yet implemented")]
     NotImplemented,
 }
@@ -67,10 +77,30 @@ impl<'de> Deserializer<'de> for InfosetDeserializer<'de>
 {
     type Error = DeserializationError;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        Err(DeserializationError::NotImplemented)
+        ensure!(self.elements.len() == 1);
+        let Element::SimpleElement(ref el) = self.elements[0] else { return Err(DeserializationError::NotImplemented) };
+        let Some(d) = &el.data else { return Err(DeserializationError::ElementNotFound) };
+        match d {
+            Data::Boolean(v) => visitor.visit_bool(*v),
+            Data::Byte(v) => visitor.visit_i8(*v),
+            Data::Short(v) => visitor.visit_i16(*v),
+            Data::Int(v) | Data::Integer(v) => visitor.visit_i32(*v),
+            Data::Long(v) => visitor.visit_i64(*v),
+            Data::UnsignedByte(v) => visitor.visit_u8(*v),
+            Data::UnsignedShort(v) => visitor.visit_u16(*v),
+            Data::UnsignedInt(v) | Data::NonNegativeInteger(v) => visitor.visit_u32(*v),
+            Data::UnsignedLong(v) => visitor.visit_u64(*v),
+            Data::Float(v) => visitor.visit_f32(*v),
+            Data::Double(v) => visitor.visit_f64(*v),
+            // decimals are handed out as their text, which keeps their precision and scale
+            Data::Decimal(v) => visitor.visit_string(v.to_string()),
+            Data::String(v) => visitor.visit_borrowed_str(v),
+            Data::HexBinary(v) => visitor.visit_borrowed_bytes(v),
+            Data::DateTime | Data::Date | Data::Time => Err(DeserializationError::NotImplemented),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -170,8 +200,12 @@ impl<'de> Deserializer<'de> for InfosetDeserializer<'de>
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let v = deserialize_builtin!(self, Data::String(data), data, "string");
-        visitor.visit_borrowed_str(v)
+        let v = deserialize_builtin!(self, data @ (Data::String(_) | Data::Decimal(_)), data, "string");
+        match v {
+            Data::Decimal(decimal) => visitor.visit_string(decimal.to_string()),
+            Data::String(data) => visitor.visit_borrowed_str(data),
+            _ => unreachable!(),
+        }
 
     }
 
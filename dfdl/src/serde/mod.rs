@@ -25,7 +25,7 @@ pub fn to_infoset<S: Serialize>(item: &S) -> Result<Infoset<'static>, Serializat
         array: false,
     };
     let (elem, _) = item.serialize(elem)?;
-    Ok(Infoset { dfdl_version: "1.0".to_string(), root_element: Some(elem) })
+    Ok(Infoset { dfdl_version: "1.0".to_string(), root_element: Some(elem), diagnostics: Vec::new() })
 }
 
 
@@ -37,7 +37,7 @@ pub fn to_infoset<S: Serialize>(item: &S) -> Result<Infoset<'static>, Serializat
 pub fn from_infoset<'de, 'is, D: Deserialize<'de>>(infoset: &'is Infoset<'is>) -> Result<D, DeserializationError> 
     where 'is: 'de
 {
-    let elements: &[Element] = infoset.root_element.as_ref().map_or(&[], std::slice::from_ref);
+    let elements: &[Element] = infoset.root_element.as_slice();
     let deserializer = deserialize::InfosetDeserializer {
         elements,
         //name: Cow::Borrowed("root"),
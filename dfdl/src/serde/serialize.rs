@@ -5,10 +5,13 @@ use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVar
 use serde::Serializer;
 use thiserror::Error;
 
+/// Errors occuring while serializing a value into an [`Infoset`](crate::Infoset)
 #[derive(Error, Debug)]
 pub enum SerializationError {
+    /// custom error raised by a [`Serialize`](serde::Serialize) implementation
     #[error("")]
     Custom(String),
+    /// a map value was serialized without a key
     #[error("no associated key found")]
     NoKey,
 }
@@ -21,6 +24,7 @@ impl serde::ser::Error for SerializationError {
 }
 
 
+use crate::decimal::DECIMAL_TOKEN;
 use crate::{ComplexElement, Data, Element, SimpleElement};
 
 #[derive(Clone, Debug)]
@@ -321,12 +325,20 @@ impl<'e> Serializer for ElementSerializer<'e> {
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + serde::Serialize {
-        value.serialize(self)
+        let (mut element, present) = value.serialize(self)?;
+        // decimals serialize as their text, wrapped in a newtype struct of this name
+        if name == DECIMAL_TOKEN {
+            if let Element::SimpleElement(SimpleElement { data: data @ Some(Data::String(_)), .. }) = &mut element {
+                let Some(Data::String(text)) = data.take() else { unreachable!() };
+                *data = Some(Data::Decimal(text.parse().map_err(serde::ser::Error::custom)?));
+            }
+        }
+        Ok((element, present))
     }
 
     fn serialize_newtype_variant<T>(